@@ -4,4 +4,12 @@
 rec {
   ssh-portfolio = (builtins.getFlake (builtins.toString ./.)).packages.${pkgs.system}.ssh-portfolio;
   ssh-portfolio-blog = ssh-portfolio.override { features = [ "blog" ]; };
+  ssh-portfolio-full = ssh-portfolio.override {
+    features = [
+      "blog"
+      "forge"
+      "guestbook"
+      "contact"
+    ];
+  };
 }
@@ -82,6 +82,7 @@
               (lib.fileset.fromSource ./assets)
               (lib.fileset.fromSource ./patches)
               (lib.fileset.fromSource ./src/atproto/lexicons)
+              (lib.fileset.maybeMissing ./src/snapshots)
             ]);
           };
           strictDeps = true;
@@ -14,17 +14,52 @@ pub enum Action {
     Resume,
     Quit,
     ClearScreen,
+    CycleColorDepth,
+    ToggleMouse,
+    Shutdown,
     Error(String),
     Help,
 
     // Tab management
     NextTab,
     PrevTab,
+    SelectTab(usize),
 
     // Selection management
     SelectNext,
     SelectPrev,
     Continue(Option<usize>),
+
+    // Search
+    Search,
+    ConfirmSearch,
+    CancelSearch,
+    NextMatch,
+    PrevMatch,
+
+    // Sorting and filtering
+    CycleSort,
+    CycleTag,
+
+    // Reading
+    ToggleToc,
+
+    // Guestbook and contact form
+    Sign,
+    Contact,
+    ConfirmCompose,
+    CancelCompose,
+    NextField,
+
+    // Command palette
+    CommandPalette,
+    ClosePalette,
+    RunCommand,
+    OpenPost(usize),
+    OpenProject(usize),
+
+    // Fetched in the background, only redraws the screen
+    ImageLoaded,
 }
 
 // HACK: should probably make this nicer
@@ -57,11 +92,29 @@ impl<'de> Deserialize<'de> for Action {
                         Resume,
                         Quit,
                         ClearScreen,
+                        CycleColorDepth,
+                        ToggleMouse,
                         Help,
                         NextTab,
                         PrevTab,
                         SelectNext,
                         SelectPrev,
+                        Search,
+                        ConfirmSearch,
+                        CancelSearch,
+                        NextMatch,
+                        PrevMatch,
+                        CycleSort,
+                        CycleTag,
+                        ToggleToc,
+                        Sign,
+                        Contact,
+                        ConfirmCompose,
+                        CancelCompose,
+                        NextField,
+                        CommandPalette,
+                        ClosePalette,
+                        RunCommand,
                     }
 
                     let helper: Helper = serde_json::from_str(&format!("\"{v}\""))
@@ -73,11 +126,29 @@ impl<'de> Deserialize<'de> for Action {
                         Helper::Resume => Action::Resume,
                         Helper::Quit => Action::Quit,
                         Helper::ClearScreen => Action::ClearScreen,
+                        Helper::CycleColorDepth => Action::CycleColorDepth,
+                        Helper::ToggleMouse => Action::ToggleMouse,
                         Helper::Help => Action::Help,
                         Helper::NextTab => Action::NextTab,
                         Helper::PrevTab => Action::PrevTab,
                         Helper::SelectNext => Action::SelectNext,
                         Helper::SelectPrev => Action::SelectPrev,
+                        Helper::Search => Action::Search,
+                        Helper::ConfirmSearch => Action::ConfirmSearch,
+                        Helper::CancelSearch => Action::CancelSearch,
+                        Helper::NextMatch => Action::NextMatch,
+                        Helper::PrevMatch => Action::PrevMatch,
+                        Helper::CycleSort => Action::CycleSort,
+                        Helper::CycleTag => Action::CycleTag,
+                        Helper::ToggleToc => Action::ToggleToc,
+                        Helper::Sign => Action::Sign,
+                        Helper::Contact => Action::Contact,
+                        Helper::ConfirmCompose => Action::ConfirmCompose,
+                        Helper::CancelCompose => Action::CancelCompose,
+                        Helper::NextField => Action::NextField,
+                        Helper::CommandPalette => Action::CommandPalette,
+                        Helper::ClosePalette => Action::ClosePalette,
+                        Helper::RunCommand => Action::RunCommand,
                     })
                 }
             }
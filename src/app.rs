@@ -1,9 +1,10 @@
+use std::collections::HashMap;
 use std::sync::atomic::AtomicUsize;
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use color_eyre::{eyre, Result};
-use crossterm::event::{KeyCode, KeyEvent};
+use crossterm::event::{KeyCode, KeyEvent, MouseEvent, MouseEventKind};
 use ratatui::layout::{Alignment, Constraint, Direction, Layout, Rect};
 use ratatui::style::{Color, Modifier, Style};
 use ratatui::text::{Line, Span};
@@ -11,16 +12,26 @@ use ratatui::widgets::{Block, Borders, Clear, Paragraph, Wrap};
 use serde::{Deserialize, Serialize};
 use tokio::sync::{mpsc, Mutex, RwLock};
 use tokio::task::block_in_place;
-use tokio_util::sync::CancellationToken;
+use tokio::time::sleep_until;
 use tracing::debug;
 
 use crate::action::Action;
 use crate::components::*;
 use crate::config::Config;
-use crate::keycode::KeyCodeExt;
-use crate::tui::terminal::{TerminalInfo, TerminalKind, UnsupportedReason};
+#[cfg(feature = "contact")]
+use crate::contact::{Contact, CONTACT};
+#[cfg(feature = "guestbook")]
+use crate::guestbook::{Guestbook, GUESTBOOK};
+use crate::keycode::{parse_mouse_event, parse_paste, KeyCodeExt};
+use crate::keymap::KeyTrie;
+use crate::metrics::METRICS;
+#[cfg(any(feature = "guestbook", feature = "contact"))]
+use crate::ssh::Visitor;
+use crate::tui::backend::SessionBackend;
+use crate::tui::probe::PROBE_TIMEOUT;
+use crate::tui::terminal::TerminalInfo;
 use crate::tui::{Event, Terminal, Tui};
-use crate::CONFIG;
+use crate::{CONFIG, OPTIONS, SHUTDOWN};
 
 pub struct App {
     config: Config,
@@ -30,10 +41,18 @@ pub struct App {
 
     should_quit: bool,
     should_suspend: bool,
+    suspended_at: Option<Instant>,
+    shutdown_deadline: Option<Instant>,
     needs_resize: bool,
+    /// Whether anything changed since the last frame, nothing is rendered otherwise.
+    dirty: bool,
 
     mode: Mode,
-    last_tick_key_events: Vec<KeyEvent>,
+    /// The keybindings of each mode, to look key sequences up in as they are pressed.
+    keymaps: HashMap<Mode, KeyTrie>,
+    /// The keys pressed so far of a sequence, whose rest is waited for until the deadline.
+    pending_keys: Vec<KeyEvent>,
+    pending_deadline: Option<Instant>,
     action_tx: mpsc::UnboundedSender<Action>,
     action_rx: mpsc::UnboundedReceiver<Action>,
 
@@ -46,6 +65,12 @@ pub struct App {
     cat: Arc<Mutex<Cat>>,
     #[cfg(feature = "blog")]
     blog_posts: Arc<Mutex<BlogPosts>>,
+    #[cfg(feature = "guestbook")]
+    guestbook: Arc<Mutex<GuestbookEntries>>,
+    #[cfg(feature = "contact")]
+    contact: Arc<Mutex<ContactForm>>,
+    palette: Arc<Mutex<CommandPalette>>,
+    which_key: Arc<Mutex<WhichKey>>,
     version_info: Arc<Mutex<VersionInfo>>,
 }
 
@@ -53,50 +78,112 @@ pub struct App {
 pub enum Mode {
     #[default]
     Home,
+    /// Typing a query to search blog posts with.
+    Search,
+    /// Writing an entry in the guestbook, or a message in the contact form.
+    Compose,
+    /// Picking something to jump to from the command palette.
+    Palette,
 }
 
 impl App {
     pub const MIN_TUI_DIMS: (u16, u16) = (105, 25);
 
+    /// How long the restart notice is shown for before the session is ended on shutdown.
+    const SHUTDOWN_NOTICE_DURATION: Duration = Duration::from_secs(3);
+
     pub fn new(
         terminal_info: Arc<RwLock<TerminalInfo>>,
         tick_rate: f64,
         frame_rate: f64,
         keystroke_rx: mpsc::UnboundedReceiver<Vec<u8>>,
         resize_rx: mpsc::UnboundedReceiver<(u16, u16)>,
+    ) -> Result<Self> {
+        #[cfg(feature = "blog")]
+        let posts = tokio::runtime::Handle::current().block_on(Content::blog_content())?;
+
+        let app = Self::with_posts(
+            terminal_info,
+            tick_rate,
+            frame_rate,
+            keystroke_rx,
+            resize_rx,
+            #[cfg(feature = "blog")]
+            posts,
+        )?;
+
+        #[cfg(feature = "guestbook")]
+        let app = app.guestbook(GUESTBOOK.clone());
+        #[cfg(feature = "contact")]
+        let app = app.contact(Arc::clone(&CONTACT));
+
+        Ok(app)
+    }
+
+    /// Creates the app with the given blog posts, instead of fetching them.
+    pub fn with_posts(
+        terminal_info: Arc<RwLock<TerminalInfo>>,
+        tick_rate: f64,
+        frame_rate: f64,
+        keystroke_rx: mpsc::UnboundedReceiver<Vec<u8>>,
+        resize_rx: mpsc::UnboundedReceiver<(u16, u16)>,
+        #[cfg(feature = "blog")] posts: Vec<Post>,
     ) -> Result<Self> {
         let (action_tx, action_rx) = mpsc::unbounded_channel();
 
         // Initialize components
         let active_tab = Arc::new(AtomicUsize::new(0));
-        let tabs = Arc::new(Mutex::new(Tabs::new(
-            vec!["about", "projects", "blog"],
-            Arc::clone(&active_tab),
-        )));
+        let tab_names = vec!["about", "projects", "blog", "guestbook"];
+        let palette = CommandPalette::new(Arc::clone(&active_tab), &tab_names)
+            .projects(Content::projects_content().iter().map(|card| card.repository));
+        #[cfg(feature = "blog")]
+        let palette = palette.posts(
+            posts
+                .iter()
+                .map(|post| post.title.clone().unwrap_or_else(|| String::from("untitled"))),
+        );
+        let palette = Arc::new(Mutex::new(palette));
+
+        let tabs = Arc::new(Mutex::new(Tabs::new(tab_names, Arc::clone(&active_tab))));
         let content = Arc::new(Mutex::new(Content::new(active_tab)));
 
         let cat = Arc::new(Mutex::new(Cat::new()));
 
         #[cfg(feature = "blog")]
-        let rt = tokio::runtime::Handle::current();
-        #[cfg(feature = "blog")]
-        let blog_posts = Arc::new(Mutex::new(BlogPosts::new(
-            rt.block_on(content.try_lock()?.blog_content())?,
-        )));
+        let blog_posts = Arc::new(Mutex::new(BlogPosts::new(posts)));
 
+        #[cfg(feature = "guestbook")]
+        let guestbook = Arc::new(Mutex::new(GuestbookEntries::default()));
+
+        #[cfg(feature = "contact")]
+        let contact = Arc::new(Mutex::new(ContactForm::default()));
+
+        let which_key = Arc::new(Mutex::new(WhichKey::default()));
         let version_info = Arc::new(Mutex::new(VersionInfo::new()));
 
+        let config = CONFIG.clone();
+        let keymaps = config
+            .keybindings
+            .iter()
+            .map(|(mode, bindings)| (*mode, KeyTrie::new(bindings)))
+            .collect();
+
         Ok(Self {
             terminal_info,
             tick_rate,
             frame_rate,
             should_quit: false,
             should_suspend: false,
+            suspended_at: None,
+            shutdown_deadline: None,
             needs_resize: false,
+            dirty: true,
 
-            config: CONFIG.clone(),
+            config,
             mode: Mode::Home,
-            last_tick_key_events: Vec::new(),
+            keymaps,
+            pending_keys: Vec::new(),
+            pending_deadline: None,
             action_tx,
             action_rx,
 
@@ -108,19 +195,66 @@ impl App {
             cat,
             #[cfg(feature = "blog")]
             blog_posts,
+            #[cfg(feature = "guestbook")]
+            guestbook,
+            #[cfg(feature = "contact")]
+            contact,
+            palette,
+            which_key,
             version_info,
         })
     }
 
+    /// Sets who signs the guestbook and sends messages, which is filled in once they
+    /// authenticated.
+    #[cfg(any(feature = "guestbook", feature = "contact"))]
+    pub fn visitor(self, visitor: Arc<RwLock<Visitor>>) -> Self {
+        #[cfg(feature = "guestbook")]
+        if let Ok(mut guestbook) = self.guestbook.try_lock() {
+            guestbook.set_visitor(Arc::clone(&visitor));
+        }
+        #[cfg(feature = "contact")]
+        if let Ok(mut contact) = self.contact.try_lock() {
+            contact.set_visitor(Arc::clone(&visitor));
+        }
+
+        self
+    }
+
+    /// Sets the guestbook shown in its tab, there is none to show otherwise.
+    #[cfg(feature = "guestbook")]
+    pub fn guestbook(self, guestbook: Option<Arc<Guestbook>>) -> Self {
+        if let Ok(mut entries) = self.guestbook.try_lock() {
+            entries.set_guestbook(guestbook);
+        }
+
+        self
+    }
+
+    /// Sets where messages sent through the contact form are delivered to.
+    #[cfg(feature = "contact")]
+    pub fn contact(self, contact: Arc<Contact>) -> Self {
+        if let Ok(mut form) = self.contact.try_lock() {
+            form.set_contact(contact);
+        }
+
+        self
+    }
+
     #[optimize(speed)]
-    pub async fn run(
+    pub async fn run<B: SessionBackend>(
         &mut self,
-        term: Arc<Mutex<Terminal>>,
-        tui: Arc<RwLock<Option<Tui>>>,
+        term: Arc<Mutex<Terminal<B>>>,
+        tui: Arc<RwLock<Option<Tui<B>>>>,
     ) -> Result<()> {
         let mut tui = tui.write().await;
         let tui = tui.get_or_insert(
-            Tui::new(term)?.tick_rate(self.tick_rate).frame_rate(self.frame_rate),
+            Tui::new(term)?
+                .tick_rate(self.tick_rate)
+                .frame_rate(self.frame_rate)
+                // Left to the visitor to turn on, as it keeps them from selecting text
+                .mouse(false)
+                .paste(true),
         );
 
         // Force the dimensions to be validated before rendering anything by sending a `Resize` event
@@ -137,6 +271,11 @@ impl App {
             self.cat.try_lock()?.register_action_handler(self.action_tx.clone())?;
             #[cfg(feature = "blog")]
             self.blog_posts.try_lock()?.register_action_handler(self.action_tx.clone())?;
+            #[cfg(feature = "guestbook")]
+            self.guestbook.try_lock()?.register_action_handler(self.action_tx.clone())?;
+            #[cfg(feature = "contact")]
+            self.contact.try_lock()?.register_action_handler(self.action_tx.clone())?;
+            self.palette.try_lock()?.register_action_handler(self.action_tx.clone())?;
 
             // Register config handlers
             self.tabs.try_lock()?.register_config_handler(self.config.clone())?;
@@ -144,16 +283,35 @@ impl App {
             self.cat.try_lock()?.register_config_handler(self.config.clone())?;
             #[cfg(feature = "blog")]
             self.blog_posts.try_lock()?.register_config_handler(self.config.clone())?;
+            #[cfg(feature = "guestbook")]
+            self.guestbook.try_lock()?.register_config_handler(self.config.clone())?;
+            #[cfg(feature = "contact")]
+            self.contact.try_lock()?.register_config_handler(self.config.clone())?;
+            self.palette.try_lock()?.register_config_handler(self.config.clone())?;
             self.version_info.try_lock()?.register_config_handler(self.config.clone())?;
 
-            for _ in 1..5 {
-                if matches!(
-                    self.terminal_info.blocking_read().kind(),
-                    TerminalKind::Unsupported(UnsupportedReason::Unprobed)
-                ) {
-                    tracing::debug!("Waiting for for terminal info to be probed");
-                    std::thread::sleep(Duration::from_millis(100));
+            // Wait for the terminal to answer the capability probe sent by the SSH session, if
+            // it doesn't in time, we carry on with whatever we know by then
+            let probe_deadline = Instant::now() + PROBE_TIMEOUT;
+            while !self.terminal_info.blocking_read().is_probed() {
+                if Instant::now() >= probe_deadline {
+                    tracing::warn!("Terminal did not answer capability probe in time");
+                    self.terminal_info.blocking_write().finish_probe();
+                    break;
                 }
+
+                tracing::trace!("Waiting for terminal info to be probed");
+                std::thread::sleep(Duration::from_millis(10));
+            }
+
+            tracing::info!("Terminal info: {:?}", self.terminal_info.blocking_read());
+            {
+                let terminal_info = self.terminal_info.blocking_read();
+                METRICS.terminal_kinds_total.inc(&[&terminal_info.kind().to_string()]);
+
+                let mut terminal = tui.terminal.try_lock()?;
+                terminal.backend_mut().set_color_depth(terminal_info.color_depth());
+                terminal.backend_mut().set_glyphs(terminal_info.glyphs());
             }
 
             // Initialize components
@@ -163,37 +321,38 @@ impl App {
             self.cat.try_lock()?.init(self.terminal_info.clone(), size)?;
             #[cfg(feature = "blog")]
             self.blog_posts.try_lock()?.init(self.terminal_info.clone(), size)?;
+            #[cfg(feature = "guestbook")]
+            self.guestbook.try_lock()?.init(self.terminal_info.clone(), size)?;
+            #[cfg(feature = "contact")]
+            self.contact.try_lock()?.init(self.terminal_info.clone(), size)?;
+            self.palette.try_lock()?.init(self.terminal_info.clone(), size)?;
             self.version_info.try_lock()?.init(self.terminal_info.clone(), size)?;
 
             Ok::<_, eyre::Error>(())
         })?;
 
-        let action_tx = self.action_tx.clone();
-        let mut resume_tx: Option<Arc<CancellationToken>> = None;
         loop {
             self.handle_events(tui).await?;
             block_in_place(|| self.handle_actions(tui))?;
-            if self.should_suspend {
-                if let Some(ref tx) = resume_tx {
-                    tx.cancel();
-                    resume_tx = None;
-                } else {
-                    resume_tx = Some(tui.suspend().await?);
-                    continue;
-                }
-                action_tx.send(Action::Resume)?;
-                action_tx.send(Action::ClearScreen)?;
-                block_in_place(|| tui.enter())?;
-            } else if self.should_quit {
+            if self.should_quit {
+                // Quitting takes precedence, so that idle sessions are ended while suspended
                 tui.stop().await?;
                 break;
+            } else if self.should_suspend && !tui.is_suspended() {
+                tui.suspend();
+                self.suspended_at = Some(Instant::now());
+                self.dirty = true;
+            } else if !self.should_suspend && tui.is_suspended() {
+                tui.resume();
+                self.suspended_at = None;
+                self.dirty = true;
             }
         }
 
         tui.exit().await
     }
 
-    async fn handle_events(&mut self, tui: &mut Tui) -> Result<()> {
+    async fn handle_events<B: SessionBackend>(&mut self, tui: &mut Tui<B>) -> Result<()> {
         tokio::select! {
             Some(event) = tui.next_event() => {
                 let action_tx = self.action_tx.clone();
@@ -203,6 +362,9 @@ impl App {
                     Event::Render => action_tx.send(Action::Render)?,
                     Event::Resize(x, y) => action_tx.send(Action::Resize(x, y))?,
                     Event::Key(key) => block_in_place(|| self.handle_key_event(key))?,
+                    Event::Paste(ref text) => {
+                        block_in_place(|| self.handle_paste(text.clone()))?
+                    }
                     _ => {}
                 };
 
@@ -219,59 +381,245 @@ impl App {
             }
 
             Some(keystroke_data) = self.ssh_keystroke_rx.recv() => {
-                let key_event = KeyCode::from_xterm_seq(&keystroke_data[..]).into_key_event();
-                block_in_place(|| self.handle_key_event(key_event))?;
+                if let Some(mouse_event) = parse_mouse_event(&keystroke_data) {
+                    block_in_place(|| self.handle_mouse_event(mouse_event))?;
+                } else if let Some(text) = parse_paste(&keystroke_data) {
+                    block_in_place(|| self.handle_paste(text))?;
+                } else {
+                    let key_event = KeyCode::from_xterm_seq(&keystroke_data[..]).into_key_event();
+                    block_in_place(|| self.handle_key_event(key_event))?;
+                }
             }
 
             Some((width, height)) = self.ssh_resize_rx.recv() => {
                 self.action_tx.send(Action::Resize(width, height))?;
             }
+
+            _ = sleep_until(self.pending_deadline.unwrap_or_else(Instant::now).into()),
+                if self.pending_deadline.is_some() =>
+            {
+                block_in_place(|| self.resolve_key_sequence(true))?;
+            }
+
+            _ = SHUTDOWN.cancelled(), if self.shutdown_deadline.is_none() => {
+                self.action_tx.send(Action::Shutdown)?;
+            }
         }
         Ok(())
     }
 
     fn handle_key_event(&mut self, key: KeyEvent) -> Result<()> {
         let action_tx = self.action_tx.clone();
-        let Some(keymap) = self.config.keybindings.get(&self.mode) else {
+        if self.should_suspend {
+            // Any key resumes, without triggering what it is bound to
+            return Ok(action_tx.send(Action::Resume)?);
+        }
+
+        // Whatever is being typed into gets keys first, so that typing `q` doesn't quit
+        if self.handle_focused_event(Event::Key(key))? {
+            self.dirty = true;
             return Ok(());
-        };
-        match keymap.get(&vec![key]) {
-            Some(action) => {
-                debug!("Got action: {action:?}");
-                action_tx.send(action.clone())?;
+        }
+
+        // Escape gets out of a sequence, rather than doing what it is bound to
+        if key.code == KeyCode::Esc && !self.pending_keys.is_empty() {
+            return self.cancel_key_sequence();
+        }
+
+        self.pending_keys.push(key);
+        self.resolve_key_sequence(false)
+    }
+
+    /// Runs what the keys pressed so far are bound to, or waits for more of them if they are
+    /// the start of a longer sequence, listing what can follow them in the meantime. Once it
+    /// has been too long, whatever they are bound to is run as is.
+    fn resolve_key_sequence(&mut self, timed_out: bool) -> Result<()> {
+        let keymap = self.keymaps.get(&self.mode);
+        match keymap.and_then(|keymap| keymap.get(&self.pending_keys)) {
+            Some(next) if next.is_prefix() && !timed_out => {
+                let timeout = Duration::from_millis(self.config.key_timeout);
+                self.pending_deadline = Some(Instant::now() + timeout);
+                self.which_key.try_lock()?.show(&self.pending_keys, next);
+                self.dirty = true;
             }
-            _ => {
-                self.last_tick_key_events.push(key);
-                if let Some(action) = keymap.get(&self.last_tick_key_events) {
+            Some(next) => {
+                if let Some(action) = next.action() {
                     debug!("Got action: {action:?}");
-                    action_tx.send(action.clone())?;
+                    self.action_tx.send(action.clone())?;
                 }
+                self.cancel_key_sequence()?;
+            }
+            // The last key broke off the sequence, so whatever was pressed before it is run
+            // and it starts a sequence of its own
+            None if self.pending_keys.len() > 1 => {
+                let key = self.pending_keys.pop();
+                self.resolve_key_sequence(true)?;
+                self.pending_keys.extend(key);
+                self.resolve_key_sequence(false)?;
             }
+            None => self.cancel_key_sequence()?,
+        }
+
+        Ok(())
+    }
+
+    /// Forgets the keys pressed so far of a sequence.
+    fn cancel_key_sequence(&mut self) -> Result<()> {
+        self.pending_keys.clear();
+        self.pending_deadline = None;
+
+        let mut which_key = self.which_key.try_lock()?;
+        if which_key.is_shown() {
+            which_key.hide();
+            self.dirty = true;
         }
         Ok(())
     }
 
-    fn handle_actions(&mut self, tui: &mut Tui) -> Result<()> {
+    fn handle_paste(&mut self, text: String) -> Result<()> {
+        if self.should_suspend {
+            return Ok(self.action_tx.send(Action::Resume)?);
+        }
+
+        // Pasting only does anything while something is being typed into
+        if self.handle_focused_event(Event::Paste(text))? {
+            self.dirty = true;
+        }
+        Ok(())
+    }
+
+    /// Hands the event to the component with focus, if any, returning whether it was used.
+    fn handle_focused_event(&self, event: Event) -> Result<bool> {
+        // The palette and contact form are shown over everything else, so they come first
+        let focusable: &[&Mutex<dyn Component>] = &[
+            &*self.palette,
+            #[cfg(feature = "contact")]
+            &*self.contact,
+            #[cfg(feature = "guestbook")]
+            &*self.guestbook,
+            #[cfg(feature = "blog")]
+            &*self.blog_posts,
+        ];
+
+        for component in focusable {
+            let mut component = component.try_lock()?;
+            if component.is_focused() {
+                return component.handle_focused_event(&event);
+            }
+        }
+        Ok(false)
+    }
+
+    fn handle_mouse_event(&mut self, mouse: MouseEvent) -> Result<()> {
+        // Moving the mouse around does nothing, and shouldn't resume a suspended app either
+        if self.should_suspend || mouse.kind == MouseEventKind::Moved {
+            return Ok(());
+        }
+
+        let event = Some(Event::Mouse(mouse));
+        if let Some(action) = self.content.try_lock()?.handle_events(event)? {
+            self.action_tx.send(action)?;
+        }
+
+        // Components change what they show right away, rather than through actions
+        self.dirty = true;
+        Ok(())
+    }
+
+    fn handle_actions<B: SessionBackend>(&mut self, tui: &mut Tui<B>) -> Result<()> {
         while let Ok(action) = self.action_rx.try_recv() {
             if action != Action::Tick && action != Action::Render {
                 debug!("{action:?}");
+
+                // Anything else may change what is displayed
+                self.dirty = true;
             }
             match action {
+                // The palette is shown over everything else, so it moves its own selection
+                // rather than that of what is behind it while open
+                Action::SelectNext | Action::SelectPrev
+                    if self.palette.try_lock()?.is_open() =>
+                {
+                    self.palette.try_lock()?.update(action)?;
+                    continue;
+                }
                 Action::Tick => {
-                    self.last_tick_key_events.drain(..);
+                    if let Some(suspended_at) = self.suspended_at {
+                        if suspended_at.elapsed() >= Self::idle_timeout() {
+                            tracing::info!("Suspended session has been idle for too long");
+                            self.should_quit = true;
+                        } else {
+                            // Keeps the idle countdown on the paused screen up to date
+                            self.dirty = true;
+                        }
+                    }
+
+                    if self
+                        .shutdown_deadline
+                        .is_some_and(|deadline| Instant::now() >= deadline)
+                    {
+                        self.should_quit = true;
+                    }
                 }
                 #[cfg(feature = "blog")]
-                Action::Quit => self.should_quit = !self.blog_posts.try_lock()?.is_in_post(),
+                Action::Quit => {
+                    // Leaving a post or clearing the search comes before quitting
+                    let blog_posts = self.blog_posts.try_lock()?;
+                    self.should_quit = !self.content.try_lock()?.is_viewing_project()
+                        && !blog_posts.is_in_post()
+                        && !blog_posts.is_filtered();
+                }
                 #[cfg(not(feature = "blog"))]
-                Action::Quit => self.should_quit = true,
+                Action::Quit => {
+                    // Closing a project comes before quitting
+                    self.should_quit = !self.content.try_lock()?.is_viewing_project();
+                }
                 Action::Suspend => self.should_suspend = true,
                 Action::Resume => self.should_suspend = false,
+                Action::Shutdown => {
+                    tracing::info!("Server is shutting down, notifying session");
+                    self.shutdown_deadline =
+                        Some(Instant::now() + Self::SHUTDOWN_NOTICE_DURATION);
+
+                    // The notice is hidden behind the paused screen, so resume to show it
+                    self.should_suspend = false;
+                }
+                #[cfg(feature = "blog")]
+                Action::Search if self.tabs.try_lock()?.current_tab() == 2 => {
+                    self.mode = Mode::Search
+                }
+                // Only blog posts can be searched through, sorted and filtered
+                #[cfg(feature = "blog")]
+                Action::CycleSort | Action::CycleTag
+                    if self.tabs.try_lock()?.current_tab() == 2 => {}
+                Action::Search | Action::CycleSort | Action::CycleTag => continue,
+                // Only the guestbook can be signed, which switches modes further below
+                #[cfg(feature = "guestbook")]
+                Action::Sign if self.tabs.try_lock()?.current_tab() == 3 => {}
+                Action::Sign => continue,
+                Action::ConfirmSearch | Action::CancelSearch => self.mode = Mode::Home,
                 Action::ClearScreen => tui.terminal.try_lock()?.clear()?,
+                Action::ToggleMouse => tui.set_mouse(!tui.mouse)?,
+                Action::CycleColorDepth => {
+                    let mut terminal_info = self.terminal_info.blocking_write();
+                    let color_depth = terminal_info.color_depth().next();
+                    terminal_info.force_color_depth(color_depth);
+                    tracing::info!("Switching to {color_depth} color depth");
+
+                    // Everything has to be redrawn, since the buffers are diffed before colors
+                    // are mapped by the backend
+                    let mut term = tui.terminal.try_lock()?;
+                    term.backend_mut().set_color_depth(color_depth);
+                    term.clear()?;
+                }
                 Action::Resize(w, h) => {
                     self.needs_resize = w < Self::MIN_TUI_DIMS.0 || h < Self::MIN_TUI_DIMS.1;
                     self.resize(tui, w, h)?;
                 }
-                Action::Render => self.render(tui)?,
+                Action::Render if self.dirty => {
+                    self.dirty = false;
+                    self.render(tui)?;
+                }
                 _ => {}
             }
 
@@ -290,21 +638,79 @@ impl App {
             if let Some(action) = self.blog_posts.try_lock()?.update(action.clone())? {
                 self.action_tx.send(action)?;
             }
+
+            #[cfg(feature = "guestbook")]
+            if self.tabs.try_lock()?.current_tab() == 3 {
+                let mut guestbook = self.guestbook.try_lock()?;
+                if let Some(action) = guestbook.update(action.clone())? {
+                    self.action_tx.send(action)?;
+                }
+
+                // Typing lasts for as long as an entry is being written, which a rejected
+                // entry still is
+                if guestbook.is_composing() {
+                    self.mode = Mode::Compose;
+                } else if self.mode == Mode::Compose {
+                    self.mode = Mode::Home;
+                }
+            }
+
+            // The contact form can be opened from any tab, and only while nothing else is
+            // being typed into
+            #[cfg(feature = "contact")]
+            {
+                let mut contact = self.contact.try_lock()?;
+                let was_open = contact.is_open();
+                if let Some(action) = contact.update(action.clone())? {
+                    self.action_tx.send(action)?;
+                }
+
+                if contact.is_open() {
+                    self.mode = Mode::Compose;
+                } else if was_open {
+                    self.mode = Mode::Home;
+                }
+            }
+
+            let mut palette = self.palette.try_lock()?;
+            let was_open = palette.is_open();
+            if let Some(action) = palette.update(action.clone())? {
+                self.action_tx.send(action)?;
+            }
+
+            if palette.is_open() {
+                self.mode = Mode::Palette;
+            } else if was_open {
+                self.mode = Mode::Home;
+            }
         }
         Ok(())
     }
 
-    pub fn resize(&mut self, tui: &mut Tui, w: u16, h: u16) -> Result<()> {
+    pub fn resize<B: SessionBackend>(
+        &mut self,
+        tui: &mut Tui<B>,
+        w: u16,
+        h: u16,
+    ) -> Result<()> {
         let mut term = tui.terminal.try_lock()?;
-        term.backend_mut().dims = (w, h);
+        term.backend_mut().set_dims((w, h));
         term.resize(Rect::new(0, 0, w, h))?;
-        drop(term);
 
-        self.render(tui)
+        // Redrawn on the next frame, so that resizes in quick succession are only drawn once
+        self.dirty = true;
+        Ok(())
     }
 
-    fn render(&mut self, tui: &mut Tui) -> Result<()> {
+    fn render<B: SessionBackend>(&mut self, tui: &mut Tui<B>) -> Result<()> {
         let mut term = tui.terminal.try_lock()?;
+        if let Some(suspended_at) = self.suspended_at {
+            let remaining = Self::idle_timeout().saturating_sub(suspended_at.elapsed());
+            term.draw(|frame| Self::render_paused(frame, remaining))?;
+
+            return Ok(());
+        }
+
         if self.needs_resize {
             term.draw(|frame| {
                 let size = frame.area();
@@ -450,8 +856,123 @@ impl App {
                 }
             }
 
+            if tabs.current_tab() == 3 {
+                let mut content_rect = content_rect;
+                content_rect.x += 1;
+                content_rect.y += 1;
+                content_rect.width -= 2;
+                content_rect.height -= 2;
+
+                #[cfg(feature = "guestbook")]
+                self.guestbook
+                    .try_lock()
+                    .map_err(std::io::Error::other)?
+                    .draw(frame, content_rect)
+                    .map_err(std::io::Error::other)?;
+
+                #[cfg(not(feature = "guestbook"))]
+                {
+                    content_rect.height = 1;
+                    let placeholder = Paragraph::new(
+                        "Guestbook feature is disabled. Enable the `guestbook` feature to \
+                         view this tab.",
+                    )
+                    .style(Style::default().fg(Color::Red).add_modifier(Modifier::BOLD));
+
+                    frame.render_widget(placeholder, content_rect);
+                }
+            }
+
+            #[cfg(feature = "contact")]
+            self.contact
+                .try_lock()
+                .map_err(std::io::Error::other)?
+                .draw(frame, content_rect)
+                .map_err(std::io::Error::other)?;
+
+            self.palette
+                .try_lock()
+                .map_err(std::io::Error::other)?
+                .draw(frame, frame.area())
+                .map_err(std::io::Error::other)?;
+            self.which_key
+                .try_lock()
+                .map_err(std::io::Error::other)?
+                .draw(frame, frame.area())
+                .map_err(std::io::Error::other)?;
+
+            if self.shutdown_deadline.is_some() {
+                Self::render_shutdown_notice(frame);
+            }
+
             Ok::<_, std::io::Error>(())
         })?;
+
+        METRICS.frames_rendered_total.inc();
         Ok(())
     }
+
+    /// How long a suspended session may stay idle before it is disconnected.
+    fn idle_timeout() -> Duration {
+        Duration::from_secs_f64(OPTIONS.idle_timeout)
+    }
+
+    /// Renders the screen shown while suspended in place of everything else, counting down
+    /// the time left until the session is disconnected.
+    fn render_paused(frame: &mut ratatui::Frame, remaining: Duration) {
+        let secs = remaining.as_secs();
+        let lines = [
+            Line::from("paused").style(Style::default().add_modifier(Modifier::BOLD)),
+            Line::from("press any key to resume"),
+            Line::from(format!("disconnecting in {}:{:02}", secs / 60, secs % 60))
+                .style(Style::default().fg(Color::DarkGray)),
+        ];
+
+        let area = frame.area();
+        let width = lines.iter().map(Line::width).max().unwrap_or_default() as u16 + 4;
+        let height = lines.len() as u16 + 2;
+        let popup = Rect::new(
+            area.width.saturating_sub(width) / 2,
+            area.height.saturating_sub(height) / 2,
+            width.min(area.width),
+            height.min(area.height),
+        );
+
+        frame.render_widget(Clear, area);
+        frame.render_widget(
+            Paragraph::new(lines.to_vec()).alignment(Alignment::Center).block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .style(Style::default().fg(Color::White)),
+            ),
+            popup,
+        );
+    }
+
+    /// Renders a popup over everything else, letting the visitor know that the server is about
+    /// to restart and end their session.
+    fn render_shutdown_notice(frame: &mut ratatui::Frame) {
+        let message = "the server is restarting, reconnect in a bit!";
+        let area = frame.area();
+        let (width, height) = (message.len() as u16 + 4, 3);
+        let popup = Rect::new(
+            area.width.saturating_sub(width) / 2,
+            area.height.saturating_sub(height) / 2,
+            width.min(area.width),
+            height.min(area.height),
+        );
+
+        frame.render_widget(Clear, popup);
+        frame.render_widget(
+            Paragraph::new(Line::from(message))
+                .alignment(Alignment::Center)
+                .style(Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD))
+                .block(
+                    Block::default()
+                        .borders(Borders::ALL)
+                        .style(Style::default().fg(Color::White)),
+                ),
+            popup,
+        );
+    }
 }
@@ -19,14 +19,16 @@ pub mod blog {
     use color_eyre::Result;
     use ipld_core::ipld::Ipld;
     use lazy_static::lazy_static;
-    use tokio::time::{Duration, Instant};
+    use tokio::time::Instant;
     use tracing::instrument;
 
     use super::*;
+    use crate::blog::{BlogPost, CACHE_INVALIDATION_PERIOD};
+    use crate::health::HEALTH;
+    use crate::metrics::METRICS;
 
-    const CACHE_INVALIDATION_PERIOD: Duration = Duration::from_secs(30 * 60); // 30 minutes
     lazy_static! {
-        static ref POSTS_CACHE_STORE: MemoryStore<usize, (Instant, com::whtwnd::blog::entry::Record)> =
+        static ref POSTS_CACHE_STORE: MemoryStore<usize, (Instant, BlogPost)> =
             MemoryStore::default();
         static ref AGENT: Agent<CredentialSession<MemoryStore<(), Object<SessionOutputData>>, ReqwestClient>> =
             Agent::new(CredentialSession::new(
@@ -36,12 +38,13 @@ pub mod blog {
     }
 
     #[instrument(level = "debug")]
-    pub async fn get_all_posts() -> Result<Vec<com::whtwnd::blog::entry::Record>> {
+    pub async fn get_all_posts() -> Result<Vec<BlogPost>> {
         let mut i = 0;
         let mut posts = Vec::new();
         while let Some((cache_creation_time, post)) = POSTS_CACHE_STORE.get(&i).await? {
             if cache_creation_time.elapsed() > CACHE_INVALIDATION_PERIOD {
                 tracing::info!("Cache for post #{} is stale, fetching new posts", i);
+                METRICS.blog_cache_misses_total.inc();
                 POSTS_CACHE_STORE.clear().await?;
                 return fetch_posts_into_cache().await;
             }
@@ -53,32 +56,33 @@ pub mod blog {
 
         if posts.is_empty() {
             tracing::info!("No blog posts found in cache, fetching from ATProto");
+            METRICS.blog_cache_misses_total.inc();
             return fetch_posts_into_cache().await;
         }
 
+        METRICS.blog_cache_hits_total.inc();
         Ok(posts)
     }
 
     #[instrument(level = "trace")]
-    async fn fetch_posts_into_cache() -> Result<Vec<com::whtwnd::blog::entry::Record>> {
-        let records = &AGENT
-            .api
-            .com
-            .atproto
-            .repo
-            .list_records(list_records::Parameters {
-                extra_data: Ipld::Null,
-                data: list_records::ParametersData {
-                    collection: com::whtwnd::blog::Entry::nsid(),
-                    cursor: None,
-                    limit: None,
-                    reverse: None,
-                    repo: AtIdentifier::Handle(
-                        Handle::from_str("devcomp.xyz")
-                            .map_err(|_| eyre!("Invalid repo handle"))?,
-                    ),
-                },
-            })
+    async fn fetch_posts_into_cache() -> Result<Vec<BlogPost>> {
+        let parameters = list_records::Parameters {
+            extra_data: Ipld::Null,
+            data: list_records::ParametersData {
+                collection: com::whtwnd::blog::Entry::nsid(),
+                cursor: None,
+                limit: None,
+                reverse: None,
+                repo: AtIdentifier::Handle(
+                    Handle::from_str("devcomp.xyz")
+                        .map_err(|_| eyre!("Invalid repo handle"))?,
+                ),
+            },
+        };
+
+        let records = &METRICS
+            .atproto_fetch_seconds
+            .time(AGENT.api.com.atproto.repo.list_records(parameters))
             .await?
             .records;
 
@@ -89,17 +93,21 @@ pub mod blog {
                     let ser = serde_json::to_string(&btree_map)?;
                     let des = serde_json::from_str::<com::whtwnd::blog::entry::Record>(&ser)?;
 
-                    return Ok(des);
+                    // Record URIs are of the form `at://{repo}/{collection}/{rkey}`
+                    let rkey =
+                        elem.data.uri.rsplit('/').next().unwrap_or_default().to_string();
+                    return Ok(BlogPost { rkey, record: des });
                 }
 
                 Err(eyre!("Did not get posts back from atproto"))
             })
-            .collect::<Result<Vec<com::whtwnd::blog::entry::Record>>>()?;
+            .collect::<Result<Vec<BlogPost>>>()?;
 
         for (i, post) in posts.iter().enumerate() {
             POSTS_CACHE_STORE.set(i, (Instant::now(), post.clone())).await?;
         }
 
+        HEALTH.blog_fetched();
         Ok(posts)
     }
 }
@@ -0,0 +1,185 @@
+use std::collections::HashSet;
+use std::path::PathBuf;
+
+use chrono::{DateTime, FixedOffset};
+use color_eyre::Result;
+use futures::future::BoxFuture;
+use lazy_static::lazy_static;
+use serde::Serialize;
+use tokio::time::Duration;
+use tracing::instrument;
+
+use crate::com::whtwnd::blog::entry::Record;
+use crate::config::{expand_path, BlogConfig};
+use crate::CONFIG;
+
+mod feed;
+mod markdown;
+pub mod search;
+pub mod tags;
+
+pub use feed::FeedSource;
+pub use markdown::MarkdownSource;
+
+/// How long posts are kept around for before being fetched again.
+pub const CACHE_INVALIDATION_PERIOD: Duration = Duration::from_secs(30 * 60); // 30 minutes
+const MAX_WARMUP_DELAY: Duration = Duration::from_secs(5 * 60); // 5 minutes
+/// How many words are read in a minute, for estimating reading times.
+const WORDS_PER_MINUTE: usize = 200;
+
+lazy_static! {
+    pub(crate) static ref SOURCE: Box<dyn BlogSource> = source(&CONFIG.blog);
+}
+
+/// Somewhere posts for the blog tab can be fetched from.
+pub trait BlogSource: Send + Sync {
+    /// Fetches all posts, in the order they should be listed in.
+    fn posts(&self) -> BoxFuture<'_, Result<Vec<BlogPost>>>;
+}
+
+/// A post, along with the key identifying it within its source.
+#[derive(Clone, Debug)]
+pub struct BlogPost {
+    /// The record key on ATProto, or a slug for other sources. Used in the post's URL.
+    pub rkey: String,
+    pub record: Record,
+}
+
+/// Creates the source configured by the `blog` section of the config.
+pub fn source(config: &BlogConfig) -> Box<dyn BlogSource> {
+    match config {
+        BlogConfig::Atproto => Box::new(AtprotoSource),
+        BlogConfig::Markdown { path } => Box::new(MarkdownSource::new(
+            expand_path(path).unwrap_or_else(|| PathBuf::from(path)),
+        )),
+        BlogConfig::Feed { url } => Box::new(FeedSource::new(url.clone())),
+    }
+}
+
+/// Fetches posts ahead of the first session, retrying with an exponential backoff until it
+/// succeeds. The server is not ready until this has finished.
+#[instrument(level = "debug")]
+pub async fn warm_cache() {
+    let mut delay = Duration::from_secs(1);
+    while let Err(err) = SOURCE.posts().await {
+        tracing::warn!("Failed to warm blog cache, retrying in {delay:?}: {err}");
+        tokio::time::sleep(delay).await;
+        delay = (delay * 2).min(MAX_WARMUP_DELAY);
+    }
+}
+
+/// WhiteWind entries published on ATProto.
+#[derive(Debug)]
+pub struct AtprotoSource;
+
+impl BlogSource for AtprotoSource {
+    fn posts(&self) -> BoxFuture<'_, Result<Vec<BlogPost>>> {
+        Box::pin(crate::atproto::blog::get_all_posts())
+    }
+}
+
+/// What a source knows about a post, converted into the WhiteWind entry the blog tab is built
+/// around.
+#[derive(Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PostData {
+    pub content: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub subtitle: Option<String>,
+    /// When the post was published, as an RFC 3339 timestamp.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub created_at: Option<String>,
+    #[serde(rename = "ogp", skip_serializing_if = "Option::is_none")]
+    pub header_image: Option<HeaderImage>,
+    /// Not part of WhiteWind entries, these end up with the other fields it doesn't know about.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct HeaderImage {
+    pub url: String,
+}
+
+impl PostData {
+    pub fn into_post(self, rkey: String) -> Result<BlogPost> {
+        Ok(BlogPost { rkey, record: serde_json::from_value(serde_json::to_value(self)?)? })
+    }
+}
+
+/// When a post was published, if it says.
+pub fn created_at(post: &Record) -> Option<DateTime<FixedOffset>> {
+    let created_at = post.created_at.as_ref()?;
+    DateTime::parse_from_rfc3339(created_at.as_str()).ok()
+}
+
+/// How many words a post's markdown has, not counting markup such as `#` or `-`.
+pub fn word_count(markdown: &str) -> usize {
+    markdown.split_whitespace().filter(|word| word.contains(char::is_alphanumeric)).count()
+}
+
+/// Roughly how many minutes a post with the given number of words takes to read.
+pub fn reading_time(words: usize) -> usize {
+    words.div_ceil(WORDS_PER_MINUTE).max(1)
+}
+
+/// Turns an arbitrary identifier into something usable as a URL path segment.
+pub fn slugify(id: &str) -> String {
+    let mut slug = String::with_capacity(id.len());
+    for c in id.chars() {
+        if c.is_ascii_alphanumeric() || c == '_' {
+            slug.push(c.to_ascii_lowercase());
+        } else if !slug.is_empty() && !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+
+    slug.trim_end_matches('-').to_string()
+}
+
+/// Numbers the keys of posts which share one with a post before them, such as `hello-world-2`
+/// for the second `hello-world`, so that each post has a URL of its own.
+pub fn dedup_rkeys(posts: &mut [BlogPost]) {
+    let mut taken = HashSet::new();
+    for post in posts {
+        let mut rkey = post.rkey.clone();
+        let mut n = 1;
+        while !taken.insert(rkey.clone()) {
+            n += 1;
+            rkey = format!("{}-{n}", post.rkey);
+        }
+
+        post.rkey = rkey;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_slugify() {
+        assert_eq!(slugify("hello-world"), "hello-world");
+        assert_eq!(slugify("Writing a TUI!"), "writing-a-tui");
+        assert_eq!(slugify("/posts/2025/03/"), "posts-2025-03");
+    }
+
+    #[test]
+    fn test_dedup_rkeys() {
+        let mut posts = ["tui", "hello", "tui", "tui"]
+            .map(|rkey| PostData::default().into_post(rkey.to_string()).unwrap());
+        dedup_rkeys(&mut posts);
+        assert_eq!(posts.map(|post| post.rkey), ["tui", "hello", "tui-2", "tui-3"]);
+    }
+
+    #[test]
+    fn test_word_count() {
+        assert_eq!(word_count("## Why\n\n- Because it's *fun*, 100%."), 5);
+        assert_eq!(reading_time(0), 1);
+        assert_eq!(reading_time(201), 2);
+    }
+}
@@ -0,0 +1,172 @@
+use std::sync::Mutex;
+use std::time::Duration;
+
+use color_eyre::Result;
+use feed_rs::model::{Entry, Text};
+use futures::future::BoxFuture;
+use tokio::time::Instant;
+use tracing::instrument;
+
+use super::{
+    dedup_rkeys, slugify, BlogPost, BlogSource, HeaderImage, PostData,
+    CACHE_INVALIDATION_PERIOD,
+};
+use crate::health::HEALTH;
+use crate::metrics::METRICS;
+
+/// How long fetching the feed can take before it's given up on.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// An RSS or Atom feed, such as one published by another blogging platform.
+#[derive(Debug)]
+pub struct FeedSource {
+    url: String,
+    client: reqwest::Client,
+    cache: Mutex<Option<(Instant, Vec<BlogPost>)>>,
+}
+
+impl FeedSource {
+    pub fn new(url: String) -> Self {
+        Self {
+            url,
+            client: reqwest::Client::builder()
+                .timeout(REQUEST_TIMEOUT)
+                .build()
+                .expect("Failed to build HTTP client"),
+            cache: Mutex::default(),
+        }
+    }
+
+    #[instrument(level = "debug", skip(self), fields(url = %self.url))]
+    async fn fetch_posts(&self) -> Result<Vec<BlogPost>> {
+        if let Some((fetched_at, posts)) =
+            self.cache.lock().ok().and_then(|cache| cache.clone())
+        {
+            if fetched_at.elapsed() <= CACHE_INVALIDATION_PERIOD {
+                METRICS.blog_cache_hits_total.inc();
+                return Ok(posts);
+            }
+        }
+
+        tracing::info!("Blog feed not cached or stale, fetching");
+        METRICS.blog_cache_misses_total.inc();
+        let response = self.client.get(&self.url).send().await?;
+        let body = response.error_for_status()?.bytes().await?;
+        let posts = parse_feed(&body)?;
+
+        if let Ok(mut cache) = self.cache.lock() {
+            *cache = Some((Instant::now(), posts.clone()));
+        }
+
+        HEALTH.blog_fetched();
+        Ok(posts)
+    }
+}
+
+impl BlogSource for FeedSource {
+    fn posts(&self) -> BoxFuture<'_, Result<Vec<BlogPost>>> {
+        Box::pin(self.fetch_posts())
+    }
+}
+
+/// Parses the entries of an RSS or Atom feed into posts, skipping any which are invalid.
+fn parse_feed(body: &[u8]) -> Result<Vec<BlogPost>> {
+    let mut posts = feed_rs::parser::parse(body)?
+        .entries
+        .into_iter()
+        .filter_map(|entry| {
+            // Entry IDs are usually the post's URL, the last part of which makes a fine key
+            let id = entry.id.trim_end_matches('/');
+            let rkey = slugify(id.rsplit(['/', ':']).next().unwrap_or(id));
+            entry_post(entry)
+                .into_post(rkey.clone())
+                .inspect_err(|err| tracing::warn!("Skipping invalid feed entry {rkey}: {err}"))
+                .ok()
+        })
+        .collect::<Vec<_>>();
+
+    // Keys only come from the end of each ID, which can be the same for several entries
+    dedup_rkeys(&mut posts);
+    Ok(posts)
+}
+
+fn entry_post(entry: Entry) -> PostData {
+    // The summary is used as the content if there is none, rather than as the subtitle
+    let (content, subtitle) = match entry.content {
+        Some(content) => (
+            content.body.map(|body| markdown(body, content.content_type.essence_str())),
+            entry.summary.as_ref().map(text_markdown),
+        ),
+        None => (entry.summary.as_ref().map(text_markdown), None),
+    };
+
+    let header_image = entry
+        .media
+        .iter()
+        .flat_map(|media| &media.thumbnails)
+        .map(|thumbnail| HeaderImage { url: thumbnail.image.uri.clone() })
+        .next();
+
+    PostData {
+        content: content.unwrap_or_default(),
+        title: entry.title.map(|title| title.content),
+        subtitle,
+        created_at: entry
+            .published
+            .or(entry.updated)
+            .map(|date| date.to_rfc3339_opts(chrono::SecondsFormat::Millis, true)),
+        header_image,
+        tags: entry.categories.into_iter().map(|category| category.term).collect(),
+    }
+}
+
+fn text_markdown(text: &Text) -> String {
+    markdown(text.content.clone(), text.content_type.essence_str())
+}
+
+/// Converts text of the given media type to markdown. Feeds usually carry HTML, which the
+/// blog tab can't display as is.
+fn markdown(text: String, media_type: &str) -> String {
+    match media_type {
+        "text/html" | "application/xhtml+xml" => html2md::parse_html(&text).trim().to_string(),
+        _ => text,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_parse_feed() {
+        let atom = br#"<?xml version="1.0" encoding="utf-8"?>
+            <feed xmlns="http://www.w3.org/2005/Atom">
+              <title>Example</title>
+              <id>urn:example</id>
+              <updated>2025-03-14T18:30:00Z</updated>
+              <entry>
+                <title>Writing a TUI</title>
+                <id>urn:example:tui</id>
+                <published>2025-03-14T18:30:00Z</published>
+                <updated>2025-03-15T09:00:00Z</updated>
+                <summary>Notes on terminal interfaces</summary>
+                <category term="tui"/>
+                <content type="html">&lt;p&gt;Built with &lt;strong&gt;ratatui&lt;/strong&gt;&lt;/p&gt;</content>
+              </entry>
+            </feed>"#;
+
+        let posts = parse_feed(atom).unwrap();
+        assert_eq!(posts.len(), 1);
+        assert_eq!(posts[0].rkey, "tui");
+        assert_eq!(posts[0].record.title.as_deref(), Some("Writing a TUI"));
+        assert_eq!(posts[0].record.subtitle.as_deref(), Some("Notes on terminal interfaces"));
+        assert_eq!(posts[0].record.content, "Built with **ratatui**");
+        assert_eq!(crate::blog::tags::tags(&posts[0].record), ["tui".to_string()].into());
+        assert_eq!(
+            posts[0].record.created_at.as_ref().map(|date| date.as_str()),
+            Some("2025-03-14T18:30:00.000Z")
+        );
+    }
+}
@@ -0,0 +1,235 @@
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use chrono::{DateTime, NaiveDate};
+use color_eyre::eyre::eyre;
+use color_eyre::Result;
+use futures::future::BoxFuture;
+use tokio::time::Instant;
+use tracing::instrument;
+
+use super::{dedup_rkeys, slugify, BlogPost, BlogSource, HeaderImage, PostData};
+use crate::health::HEALTH;
+use crate::metrics::METRICS;
+
+/// How long posts are kept around for before the directory is read again. Much shorter than
+/// for other sources, since reading a directory is cheap and edits should show up quickly.
+const REREAD_PERIOD: Duration = Duration::from_secs(60);
+
+/// A directory of markdown files, each one a post with its metadata in front matter:
+///
+/// ```markdown
+/// ---
+/// title: Hello, world
+/// subtitle: The obligatory first post
+/// date: 2024-11-02
+/// image: https://example.com/header.png
+/// tags: meta, ssh
+/// ---
+///
+/// This blog is served over SSH.
+/// ```
+///
+/// Posts are listed newest first, and read again at most once a minute so that edits show up
+/// without a restart. Each post is identified by its file name, e.g. `hello-world.md` by
+/// `hello-world`.
+#[derive(Debug)]
+pub struct MarkdownSource {
+    dir: PathBuf,
+    cache: Mutex<Option<(Instant, Vec<BlogPost>)>>,
+}
+
+impl MarkdownSource {
+    pub fn new(dir: PathBuf) -> Self {
+        Self { dir, cache: Mutex::default() }
+    }
+
+    async fn cached_posts(&self) -> Result<Vec<BlogPost>> {
+        if let Some((read_at, posts)) = self.cache.lock().ok().and_then(|cache| cache.clone())
+        {
+            if read_at.elapsed() <= REREAD_PERIOD {
+                METRICS.blog_cache_hits_total.inc();
+                return Ok(posts);
+            }
+        }
+
+        METRICS.blog_cache_misses_total.inc();
+        let posts = self.read_posts().await?;
+        if let Ok(mut cache) = self.cache.lock() {
+            *cache = Some((Instant::now(), posts.clone()));
+        }
+
+        Ok(posts)
+    }
+
+    #[instrument(level = "debug", skip(self), fields(dir = %self.dir.display()))]
+    async fn read_posts(&self) -> Result<Vec<BlogPost>> {
+        let mut posts = Vec::new();
+        let mut entries = tokio::fs::read_dir(&self.dir).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            let path = entry.path();
+            if path.extension().is_none_or(|ext| ext != "md") {
+                continue;
+            }
+
+            // A post with a mistake in it shouldn't take the rest of the blog down with it
+            let post = match tokio::fs::read_to_string(&path).await {
+                Ok(source) => parse_post(&source),
+                Err(err) => Err(err.into()),
+            };
+            let post = match post {
+                Ok(post) => post,
+                Err(err) => {
+                    tracing::warn!("Skipping invalid post {}: {err}", path.display());
+                    continue;
+                }
+            };
+
+            let rkey = slugify(&path.file_stem().unwrap_or_default().to_string_lossy());
+            posts.push((rkey, post));
+        }
+
+        // RFC 3339 timestamps in UTC sort chronologically
+        posts.sort_by(|(_, a): &(String, PostData), (_, b)| b.created_at.cmp(&a.created_at));
+        let mut posts = posts
+            .into_iter()
+            .filter_map(|(rkey, post)| {
+                post.into_post(rkey.clone())
+                    .inspect_err(|err| tracing::warn!("Skipping invalid post {rkey}: {err}"))
+                    .ok()
+            })
+            .collect::<Vec<_>>();
+        dedup_rkeys(&mut posts);
+
+        HEALTH.blog_fetched();
+        Ok(posts)
+    }
+}
+
+impl BlogSource for MarkdownSource {
+    fn posts(&self) -> BoxFuture<'_, Result<Vec<BlogPost>>> {
+        Box::pin(self.cached_posts())
+    }
+}
+
+/// Parses a markdown file, with optional front matter of `key: value` pairs between `---`
+/// lines.
+fn parse_post(source: &str) -> Result<PostData> {
+    let mut post = PostData::default();
+    let Some((front_matter, content)) = source
+        .strip_prefix("---")
+        .and_then(|rest| rest.trim_start_matches([' ', '\t']).strip_prefix('\n'))
+        .and_then(|rest| rest.split_once("\n---"))
+    else {
+        post.content = source.to_string();
+        return Ok(post);
+    };
+
+    for line in front_matter.lines().filter(|line| !line.trim().is_empty()) {
+        let (key, value) =
+            line.split_once(':').ok_or(eyre!("Front matter line without a key: {line}"))?;
+        let value = value.trim().trim_matches(['"', '\'']).to_string();
+
+        match key.trim() {
+            "title" => post.title = Some(value),
+            "subtitle" => post.subtitle = Some(value),
+            "date" => post.created_at = Some(parse_date(&value)?),
+            "image" => post.header_image = Some(HeaderImage { url: value }),
+            "tags" => {
+                let tags = value.trim_start_matches('[').trim_end_matches(']').split(',');
+                let tags = tags.map(|tag| tag.trim().trim_matches(['"', '\'']));
+                post.tags = tags.filter(|tag| !tag.is_empty()).map(str::to_string).collect();
+            }
+            _ => tracing::debug!("Ignoring unknown front matter key: {key}"),
+        }
+    }
+
+    // Skip the rest of the closing `---` line
+    post.content = content.split_once('\n').map_or("", |(_, content)| content).trim().into();
+    Ok(post)
+}
+
+/// Parses a date or timestamp into an RFC 3339 timestamp in UTC.
+fn parse_date(date: &str) -> Result<String> {
+    let date = match DateTime::parse_from_rfc3339(date) {
+        Ok(date) => date.to_utc(),
+        Err(_) => NaiveDate::parse_from_str(date, "%Y-%m-%d")
+            .map_err(|_| eyre!("Expected a date such as 2024-11-02, got {date}"))?
+            .and_hms_opt(0, 0, 0)
+            .unwrap_or_default()
+            .and_utc(),
+    };
+
+    Ok(date.to_rfc3339_opts(chrono::SecondsFormat::Millis, true))
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_parse_post() {
+        let post = parse_post(
+            "---\ntitle: \"Hello, world\"\nsubtitle: The obligatory first post\ndate: \
+             2024-11-02\nimage: https://example.com/header.png\ntags: [meta, \"ssh\"]\n---\n\n\
+             This blog is served over SSH.\n",
+        )
+        .unwrap();
+
+        assert_eq!(post.title.as_deref(), Some("Hello, world"));
+        assert_eq!(post.subtitle.as_deref(), Some("The obligatory first post"));
+        assert_eq!(post.created_at.as_deref(), Some("2024-11-02T00:00:00.000Z"));
+        assert_eq!(post.header_image.unwrap().url, "https://example.com/header.png");
+        assert_eq!(post.tags, vec!["meta", "ssh"]);
+        assert_eq!(post.content, "This blog is served over SSH.");
+
+        let post = parse_post("# No front matter\n\n---\n").unwrap();
+        assert_eq!(post.title, None);
+        assert_eq!(post.content, "# No front matter\n\n---\n");
+
+        assert!(parse_post("---\ndate: yesterday\n---\n").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_skips_invalid_posts() {
+        let dir =
+            std::env::temp_dir().join(format!("ssh-portfolio-posts-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("hello-world.md"), "---\ntitle: Hello, world\n---\n").unwrap();
+        std::fs::write(dir.join("broken.md"), "---\ndate: yesterday\n---\n").unwrap();
+
+        let posts = MarkdownSource::new(dir.clone()).read_posts().await;
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        let posts = posts.unwrap();
+        assert_eq!(posts.len(), 1);
+        assert_eq!(posts[0].rkey, "hello-world");
+    }
+
+    #[tokio::test]
+    async fn test_caches_posts() {
+        let dir = std::env::temp_dir()
+            .join(format!("ssh-portfolio-cached-posts-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("hello-world.md"), "---\ntitle: Hello, world\n---\n").unwrap();
+
+        let source = MarkdownSource::new(dir.clone());
+        let first = source.posts().await;
+        std::fs::write(dir.join("second.md"), "---\ntitle: Second\n---\n").unwrap();
+        let cached = source.posts().await;
+
+        // Once the cache is stale, the new post is picked up
+        if let Some((read_at, _)) = source.cache.lock().unwrap().as_mut() {
+            *read_at -= REREAD_PERIOD * 2;
+        }
+        let reread = source.posts().await;
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(first.unwrap().len(), 1);
+        assert_eq!(cached.unwrap().len(), 1);
+        assert_eq!(reread.unwrap().len(), 2);
+    }
+}
@@ -0,0 +1,157 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::ops::Range;
+use std::sync::{Arc, Mutex, PoisonError};
+
+use lazy_static::lazy_static;
+
+use crate::com::whtwnd::blog::entry::Record;
+
+lazy_static! {
+    /// The index over the posts last fetched, shared between sessions until the posts change.
+    static ref INDEX: Mutex<Option<Arc<SearchIndex>>> = Mutex::default();
+}
+
+/// An inverted index over the title, subtitle and content of posts, mapping every word to the
+/// posts it appears in.
+#[derive(Debug, Default)]
+pub struct SearchIndex {
+    words: BTreeMap<String, BTreeSet<usize>>,
+    fingerprint: u64,
+}
+
+impl SearchIndex {
+    /// Indexes the given posts, which are referred to by their position within them.
+    pub fn new(posts: &[impl AsRef<Record>]) -> Self {
+        let mut index = Self { words: BTreeMap::new(), fingerprint: fingerprint(posts) };
+        for (i, post) in posts.iter().enumerate() {
+            for (_, word) in searchable(post.as_ref()).flat_map(words) {
+                index.words.entry(word.to_lowercase()).or_default().insert(i);
+            }
+        }
+
+        index
+    }
+
+    /// Returns the index over the given posts, only building a new one if they changed since
+    /// the last call, which happens whenever the blog cache is refreshed.
+    pub fn cached(posts: &[impl AsRef<Record>]) -> Arc<Self> {
+        let mut cached = INDEX.lock().unwrap_or_else(PoisonError::into_inner);
+        match cached.as_ref() {
+            Some(index) if index.fingerprint == fingerprint(posts) => Arc::clone(index),
+            _ => Arc::clone(cached.insert(Arc::new(Self::new(posts)))),
+        }
+    }
+
+    /// The positions of the posts matching every term in the query, in order. Terms match
+    /// words they are a prefix of, so that results narrow down as the query is typed.
+    pub fn search(&self, query: &str) -> Vec<usize> {
+        let mut matches: Option<BTreeSet<usize>> = None;
+        for term in terms(query) {
+            let term_matches = self
+                .words
+                .range(term.clone()..)
+                .take_while(|(word, _)| word.starts_with(&term))
+                .flat_map(|(_, posts)| posts.iter().copied())
+                .collect::<BTreeSet<_>>();
+
+            matches = Some(match matches {
+                Some(matches) => matches.intersection(&term_matches).copied().collect(),
+                None => term_matches,
+            });
+        }
+
+        matches.map_or_else(Vec::new, |matches| matches.into_iter().collect())
+    }
+}
+
+/// A hash of everything that is indexed, telling whether posts changed.
+fn fingerprint(posts: &[impl AsRef<Record>]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    for post in posts {
+        searchable(post.as_ref()).for_each(|text| text.hash(&mut hasher));
+    }
+
+    hasher.finish()
+}
+
+/// The text of a post which is searched through.
+fn searchable(post: &Record) -> impl Iterator<Item = &str> {
+    [post.title.as_deref(), post.subtitle.as_deref(), Some(post.content.as_str())]
+        .into_iter()
+        .flatten()
+}
+
+/// Splits text into words, along with where each one starts.
+fn words(text: &str) -> impl Iterator<Item = (usize, &str)> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(move |word| (word.as_ptr() as usize - text.as_ptr() as usize, word))
+}
+
+/// Splits a query into the lowercase terms to search for.
+pub fn terms(query: &str) -> Vec<String> {
+    words(query).map(|(_, word)| word.to_lowercase()).collect()
+}
+
+/// The byte ranges of the words within text which are matched by any of the given terms.
+pub fn highlights(text: &str, terms: &[String]) -> Vec<Range<usize>> {
+    if terms.is_empty() {
+        return Vec::new();
+    }
+
+    words(text)
+        .filter_map(|(start, word)| {
+            let word = word.to_lowercase();
+            let term = terms
+                .iter()
+                .filter(|term| word.starts_with(term.as_str()))
+                .max_by_key(|term| term.len())?;
+
+            // Only the matched prefix is highlighted, mapped back onto the original text in
+            // case lowercasing changed its length
+            let len = text[start..]
+                .char_indices()
+                .nth(term.chars().count())
+                .map_or(text.len() - start, |(len, _)| len);
+            Some(start..start + len)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::blog::PostData;
+
+    fn post(title: &str, content: &str) -> Arc<Record> {
+        let post = PostData {
+            title: Some(title.to_string()),
+            content: content.to_string(),
+            ..Default::default()
+        };
+
+        Arc::new(post.into_post(String::new()).unwrap().record)
+    }
+
+    #[test]
+    fn test_search() {
+        let posts = [
+            post("Hello, world", "This blog is served over SSH."),
+            post("Writing a TUI", "Some notes on building terminal interfaces."),
+        ];
+        let index = SearchIndex::new(&posts);
+        assert!(Arc::ptr_eq(&SearchIndex::cached(&posts), &SearchIndex::cached(&posts)));
+
+        assert_eq!(index.search("hello"), vec![0]);
+        assert_eq!(index.search("TER"), vec![1]);
+        assert_eq!(index.search("s"), vec![0, 1]);
+        assert_eq!(index.search("ssh notes"), Vec::<usize>::new());
+        assert_eq!(index.search(""), Vec::<usize>::new());
+
+        assert_eq!(highlights("Writing a TUI", &terms("tu wri")), vec![0..3, 10..12]);
+        assert_eq!(highlights("Writing a TUI", &[]), Vec::<Range<usize>>::new());
+    }
+}
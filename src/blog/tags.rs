@@ -0,0 +1,77 @@
+use std::collections::BTreeSet;
+
+use ipld_core::ipld::Ipld;
+
+use crate::com::whtwnd::blog::entry::Record;
+
+/// The tags of a post, which are either given by its source (e.g. in front matter) or written
+/// as hashtags within its content. Tags are lowercase, and without the leading `#`.
+pub fn tags(post: &Record) -> BTreeSet<String> {
+    let mut tags = hashtags(&post.content);
+
+    // WhiteWind entries have no tags of their own, other sources keep theirs alongside the
+    // fields it does know about
+    if let Ipld::Map(fields) = &post.extra_data {
+        if let Some(Ipld::List(given)) = fields.get("tags") {
+            tags.extend(given.iter().filter_map(|tag| match tag {
+                Ipld::String(tag) => normalize(tag),
+                _ => None,
+            }));
+        }
+    }
+
+    tags
+}
+
+/// Finds hashtags such as `#rust` within markdown, skipping over headings and code.
+fn hashtags(markdown: &str) -> BTreeSet<String> {
+    let mut tags = BTreeSet::new();
+    let mut in_fence = false;
+    for line in markdown.lines() {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            in_fence = !in_fence;
+        }
+        if in_fence {
+            continue;
+        }
+
+        // Inline code spans are every other chunk between backticks
+        for text in line.split('`').step_by(2) {
+            let words = text.split_whitespace();
+            tags.extend(words.filter_map(|word| word.strip_prefix('#')).filter_map(normalize));
+        }
+    }
+
+    tags
+}
+
+/// Lowercases a tag, returning nothing if it isn't one. Tags start with a letter, and are
+/// made up of letters, digits, `-` and `_`, with any trailing punctuation dropped.
+fn normalize(tag: &str) -> Option<String> {
+    let tag = tag.trim().trim_start_matches('#');
+    let end = tag
+        .find(|c: char| !(c.is_alphanumeric() || c == '-' || c == '_'))
+        .unwrap_or(tag.len());
+    let tag = tag[..end].trim_end_matches(['-', '_']);
+
+    tag.starts_with(char::is_alphabetic).then(|| tag.to_lowercase())
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_hashtags() {
+        let markdown = "# Heading\n\nWritten in #Rust, with \
+                        #ratatui.\n\n```sh\n#comment\n```\nNot `#code` or #1, but #tui.";
+
+        assert_eq!(
+            hashtags(markdown),
+            BTreeSet::from(["ratatui".to_string(), "rust".to_string(), "tui".to_string()])
+        );
+    }
+}
@@ -1,4 +1,8 @@
+use std::net::SocketAddr;
+
 use clap::Parser;
+#[cfg(feature = "guestbook")]
+use clap::Subcommand;
 use indoc::formatdoc;
 
 use crate::config::{get_config_dir, get_data_dir};
@@ -10,7 +14,7 @@ pub struct Cli {
     #[arg(short, long, value_name = "FLOAT", default_value_t = 4.0)]
     pub tick_rate: f64,
 
-    /// Frame rate, i.e. number of frames per second
+    /// Maximum frame rate, i.e. number of frames per second, lowered for slow connections
     #[arg(short, long, value_name = "FLOAT", default_value_t = 60.0)]
     pub frame_rate: f64,
 
@@ -23,6 +27,51 @@ pub struct Cli {
     /// The port to start the web server on
     #[arg(short = 'p', long, value_name = "PORT", default_value_t = 80)]
     pub web_port: u16,
+    /// The address to serve Prometheus metrics on, such as `127.0.0.1:9100`, they aren't
+    /// served at all otherwise
+    #[arg(long, value_name = "ADDRESS")]
+    pub metrics_addr: Option<SocketAddr>,
+
+    /// How long to wait for sessions to end after being told to shut down, in seconds
+    #[arg(long, value_name = "FLOAT", default_value_t = 10.0)]
+    pub drain_timeout: f64,
+
+    /// How long a suspended session may stay idle before it is disconnected, in seconds
+    #[arg(long, value_name = "FLOAT", default_value_t = 300.0)]
+    pub idle_timeout: f64,
+
+    #[cfg(feature = "guestbook")]
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[cfg(feature = "guestbook")]
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Moderate the guestbook, which works while the server is running too
+    #[command(subcommand)]
+    Guestbook(GuestbookCommand),
+}
+
+#[cfg(feature = "guestbook")]
+#[derive(Subcommand, Debug)]
+pub enum GuestbookCommand {
+    /// List the entries, newest first
+    List {
+        /// Include entries which are hidden, or signed with a banned key
+        #[arg(short, long)]
+        all: bool,
+    },
+    /// Hide an entry from visitors
+    Hide { id: i64 },
+    /// Show a hidden entry to visitors again
+    Unhide { id: i64 },
+    /// Delete an entry for good
+    Delete { id: i64 },
+    /// Stop a key from signing the guestbook, hiding the entries it signed
+    Ban { fingerprint: String },
+    /// Let a banned key sign the guestbook again
+    Unban { fingerprint: String },
 }
 
 pub fn version() -> String {
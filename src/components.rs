@@ -19,21 +19,43 @@ use crate::tui::Event;
 mod blog;
 mod cards;
 mod cat;
+#[cfg(feature = "contact")]
+mod contact;
 mod content;
+#[cfg(feature = "guestbook")]
+mod guestbook;
+#[cfg(feature = "blog")]
+mod images;
+mod input;
+#[cfg(feature = "blog")]
+mod markdown;
+mod palette;
 #[cfg(feature = "blog")]
 mod selection_list;
 mod version_info;
 mod tabs;
+mod which_key;
 
 #[cfg(feature = "blog")]
 pub use blog::*;
 pub use cards::*;
 pub use cat::*;
+#[cfg(feature = "contact")]
+pub use contact::*;
 pub use content::*;
+#[cfg(feature = "guestbook")]
+pub use guestbook::*;
+#[cfg(feature = "blog")]
+pub use images::*;
+pub use input::*;
+#[cfg(feature = "blog")]
+pub use markdown::*;
+pub use palette::*;
 #[cfg(feature = "blog")]
 pub use selection_list::*;
 pub use version_info::*;
 pub use tabs::*;
+pub use which_key::*;
 
 /// `Component` is a trait that represents a visual and interactive element of the user interface.
 ///
@@ -122,6 +144,27 @@ pub trait Component: Send {
         let _ = mouse; // to appease clippy
         Ok(None)
     }
+    /// Whether the component has focus, such as while something is being typed into it.
+    ///
+    /// # Returns
+    ///
+    /// * `bool` - Whether key and paste events go to the component before the keybindings.
+    fn is_focused(&self) -> bool {
+        false
+    }
+    /// Handle key and paste events while focused, before keys are looked up in keybindings.
+    ///
+    /// # Arguments
+    ///
+    /// * `event` - An event to be processed.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<bool>` - Whether the event was used, unused keys trigger their bindings.
+    fn handle_focused_event(&mut self, event: &Event) -> Result<bool> {
+        let _ = event; // to appease clippy
+        Ok(false)
+    }
     /// Update the state of the component based on a received action. (REQUIRED)
     ///
     /// # Arguments
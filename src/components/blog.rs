@@ -1,102 +1,362 @@
-use std::io::{BufReader, Cursor};
+use std::cmp::Reverse;
+use std::collections::BTreeSet;
 use std::sync::Arc;
 
+use chrono::Datelike;
 use color_eyre::eyre::eyre;
 use color_eyre::Result;
-use image::{ImageReader, Rgba};
+use image::Rgba;
 use ratatui::layout::{Constraint, Flex, Layout, Rect, Size};
 use ratatui::prelude::*;
 use ratatui::widgets::*;
 use ratatui_image::picker::{Picker, ProtocolType};
 use ratatui_image::protocol::StatefulProtocol;
 use ratatui_image::{Resize, StatefulImage};
+use strum::Display;
 use tokio::sync::mpsc::UnboundedSender;
 use tokio::sync::RwLock;
 
 use crate::action::Action;
-use crate::com;
-use crate::com::whtwnd::blog::defs::Ogp;
-use crate::components::{Component, SelectionList};
-use crate::tui::terminal::{TerminalInfo, TerminalKind, UnsupportedReason, DEFAULT_FONT_SIZE};
+use crate::app::Mode;
+use crate::blog::search::{self, SearchIndex};
+use crate::components::{
+    fetch_image, image_picker, Component, ListRow, MarkdownView, SelectionList, TextInput,
+};
+use crate::config::{key_event_to_string, Config};
+use crate::tui::terminal::{TerminalInfo, DEFAULT_FONT_SIZE};
+use crate::tui::Event;
+use crate::{blog, com};
 
 pub type Post = Arc<com::whtwnd::blog::entry::Record>;
+
+/// The order blog posts are listed in.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Display)]
+pub enum SortOrder {
+    #[default]
+    #[strum(serialize = "newest first")]
+    Newest,
+    #[strum(serialize = "oldest first")]
+    Oldest,
+    #[strum(serialize = "title")]
+    Title,
+}
+
+impl SortOrder {
+    /// The next order, wrapping around to newest first after sorting by title.
+    pub fn next(self) -> Self {
+        match self {
+            Self::Newest => Self::Oldest,
+            Self::Oldest => Self::Title,
+            Self::Title => Self::Newest,
+        }
+    }
+}
+
 pub struct BlogPosts {
-    list: SelectionList<Post>,
+    config: Config,
+    list: SelectionList<ListRow>,
     posts: Vec<Post>,
     image_renderer: Option<Picker>,
     in_post: (Option<StatefulProtocol>, Option<usize>),
+    text_sizing: bool,
+
+    index: Arc<SearchIndex>,
+    query: TextInput,
+    /// Whether the query is being typed, rather than just filtering the list.
+    searching: bool,
+    /// Positions of the posts listed, which are only those matching the query and tag if
+    /// there are any, in the order they are listed in.
+    visible: Vec<usize>,
+
+    sort: SortOrder,
+    /// The tags of every post, along with all tags used by any of them.
+    tags: Vec<BTreeSet<String>>,
+    all_tags: Vec<String>,
+    /// The tag posts are filtered by, if any.
+    tag: Option<String>,
+    /// Years whose posts are hidden under their header.
+    collapsed: BTreeSet<i32>,
+    /// How many words every post has.
+    words: Vec<usize>,
+
+    /// How many rows the open post is scrolled down by.
+    scroll: u16,
+    /// The selected entry of the table of contents, if it is open.
+    toc: Option<ListState>,
+    /// The heading to scroll to on the next draw, once it is known where it ends up.
+    jump_to: Option<usize>,
 }
 
 impl BlogPosts {
     pub fn new(posts: Vec<Post>) -> Self {
-        let posts_ref = posts.to_vec();
-        Self {
-            list: SelectionList::new(posts),
+        let tags = posts.iter().map(|post| blog::tags::tags(post)).collect::<Vec<_>>();
+        let all_tags = tags.iter().flatten().cloned().collect::<BTreeSet<_>>();
+        let words = posts.iter().map(|post| blog::word_count(&post.content)).collect();
+
+        let mut blog_posts = Self {
+            config: Config::default(),
+            index: SearchIndex::cached(&posts),
+            query: TextInput::new(),
+            searching: false,
+            visible: Vec::new(),
+            sort: SortOrder::default(),
+            tags,
+            all_tags: all_tags.into_iter().collect(),
+            tag: None,
+            collapsed: BTreeSet::new(),
+            words,
+            scroll: 0,
+            toc: None,
+            jump_to: None,
+            list: SelectionList::new(Vec::new()),
             image_renderer: Some(Picker {
                 font_size: DEFAULT_FONT_SIZE,
                 protocol_type: ProtocolType::Halfblocks,
                 background_color: Rgba([0, 0, 0, 0]),
-                // NOTE: Multiplexers such as tmux are currently unsupported, we ensure that we have an
-                // xterm based terminal emulator in ssh.rs, if not, we reject the conection to begin with
+                // Set on init, if the client turns out to be running within tmux
                 is_tmux: false,
                 capabilities: vec![],
             }),
-            posts: posts_ref,
+            posts,
             in_post: (None, None),
-        }
+            text_sizing: false,
+        };
+
+        blog_posts.filter();
+        blog_posts
     }
 
     pub fn is_in_post(&self) -> bool {
         self.in_post.1.is_some()
     }
 
-    async fn header_image(&self, img: Ogp) -> Result<StatefulProtocol> {
-        if let Some(picker) = &self.image_renderer {
-            let img_blob = reqwest::get(img.url.clone())
-                .await?
-                .bytes()
-                .await?
-                .iter()
-                .cloned()
-                .collect::<Vec<u8>>();
+    /// Whether the list is filtered down to the posts matching a search.
+    pub fn is_filtered(&self) -> bool {
+        !self.list.highlight.is_empty()
+    }
 
-            let dyn_img = ImageReader::new(BufReader::new(Cursor::new(img_blob)))
-                .with_guessed_format()?
-                .decode()?;
-            let sized_img = picker.new_resize_protocol(dyn_img);
+    /// Filters the list down to the posts matching the query and tag, sorts them, and
+    /// selects the first one.
+    fn filter(&mut self) {
+        self.list.highlight = search::terms(self.query.value());
+        let mut visible = if self.is_filtered() {
+            self.index.search(self.query.value())
+        } else {
+            (0..self.posts.len()).collect()
+        };
 
-            return Ok(sized_img);
+        if let Some(tag) = &self.tag {
+            visible.retain(|&i| self.tags[i].contains(tag));
         }
 
-        Err(eyre!("No image supported renderer initialized"))
+        match self.sort {
+            SortOrder::Newest => {
+                visible.sort_by_key(|&i| Reverse(blog::created_at(&self.posts[i])))
+            }
+            SortOrder::Oldest => visible.sort_by_key(|&i| blog::created_at(&self.posts[i])),
+            SortOrder::Title => visible.sort_by_cached_key(|&i| {
+                self.posts[i].title.as_deref().map(str::to_lowercase)
+            }),
+        }
+
+        self.visible = visible;
+        self.list_rows();
+
+        let first_post = self.post_rows().next();
+        self.list.list_state.select(first_post.or(Some(0)));
     }
-}
 
-impl Component for BlogPosts {
-    fn init(&mut self, term_info: Arc<RwLock<TerminalInfo>>, _: Size) -> Result<()> {
-        let locked_info = term_info.blocking_read().clone();
+    /// Lists the visible posts, under a header for every year unless they're sorted by title.
+    fn list_rows(&mut self) {
+        let post_row = |&index: &usize| ListRow::Post {
+            index,
+            post: Arc::clone(&self.posts[index]),
+            words: self.words[index],
+        };
+        if self.sort == SortOrder::Title {
+            self.list.options = self.visible.iter().map(post_row).collect();
+            return;
+        }
+
+        let mut rows = Vec::new();
+        let same_year = |&a: &usize, &b: &usize| year(&self.posts[a]) == year(&self.posts[b]);
+        for posts in self.visible.chunk_by(same_year) {
+            let year = year(&self.posts[posts[0]]);
+            let collapsed = self.collapsed.contains(&year);
+            rows.push(ListRow::Year { year, posts: posts.len(), collapsed });
+            if !collapsed {
+                rows.extend(posts.iter().map(post_row));
+            }
+        }
 
-        if matches!(locked_info.kind(), TerminalKind::Unsupported(UnsupportedReason::Unsized))
+        self.list.options = rows;
+    }
+
+    /// The positions of the rows within the list which are posts, rather than year headers.
+    fn post_rows(&self) -> impl DoubleEndedIterator<Item = usize> + Clone + '_ {
+        self.list
+            .options
+            .iter()
+            .enumerate()
+            .filter(|(_, row)| matches!(row, ListRow::Post { .. }))
+            .map(|(i, _)| i)
+    }
+
+    /// Collapses or expands the posts of a year, keeping its header selected.
+    fn toggle_year(&mut self, year: i32) {
+        if !self.collapsed.remove(&year) {
+            self.collapsed.insert(year);
+        }
+
+        self.list_rows();
+        let header = self.list.options.iter().position(|row| {
+            matches!(row, ListRow::Year { year: header_year, .. } if *header_year == year)
+        });
+        self.list.list_state.select(header);
+    }
+
+    /// Filters the list by the next tag, or by none after the last one.
+    fn cycle_tag(&mut self) {
+        self.tag = match &self.tag {
+            Some(tag) => self.all_tags.iter().find(|next| *next > tag).cloned(),
+            None => self.all_tags.first().cloned(),
+        };
+
+        self.filter();
+    }
+
+    /// Selects the next or previous post matching the search, wrapping around at either end
+    /// of the list, and switches to it if a post is open.
+    fn step_match(&mut self, forward: bool) {
+        if !self.is_filtered() {
+            return;
+        }
+
+        let selected = self.list.list_state.selected().unwrap_or_default();
+        let mut rows = self.post_rows();
+        let row = if forward {
+            let first = rows.clone().next();
+            rows.find(|&row| row > selected).or(first)
+        } else {
+            let last = rows.clone().next_back();
+            rows.rev().find(|&row| row < selected).or(last)
+        };
+
+        let Some(row) = row else {
+            return;
+        };
+
+        self.list.list_state.select(Some(row));
+        if let (true, Some(&ListRow::Post { index, .. })) =
+            (self.is_in_post(), self.list.options.get(row))
         {
-            self.image_renderer = None;
+            self.open_post(index);
+        }
+    }
+
+    /// Opens a post from the top.
+    fn open_post(&mut self, index: usize) {
+        self.in_post = (None, Some(index));
+        self.scroll = 0;
+        if let Some(toc) = &mut self.toc {
+            toc.select_first();
+        }
+    }
+
+    /// Opens a post from outside of the list, selecting it in the list if it is listed.
+    fn jump_to_post(&mut self, index: usize) {
+        let row = self.list.options.iter().position(
+            |row| matches!(row, ListRow::Post { index: post, .. } if *post == index),
+        );
+        if row.is_some() {
+            self.list.list_state.select(row);
         }
 
-        if let Some(picker) = &mut self.image_renderer {
-            picker.capabilities = locked_info.kind().capabilities();
-            picker.protocol_type = locked_info.kind().as_protocol();
-            picker.font_size = locked_info.font_size();
+        self.open_post(index);
+    }
 
-            tracing::info!(
-                "Using {:?} rendering protocol for blog image renderer, font size: {:?}",
-                picker.protocol_type(),
-                picker.font_size(),
-            );
+    /// Scrolls the open post or moves through its table of contents if it is open, returning
+    /// whether the action was used up.
+    fn navigate_post(&mut self, action: &Action) -> bool {
+        match (action, &mut self.toc) {
+            (Action::SelectNext, Some(toc)) => toc.select_next(),
+            (Action::SelectPrev, Some(toc)) => toc.select_previous(),
+            (Action::Continue(None), Some(toc)) => self.jump_to = toc.selected(),
+            (Action::SelectNext, None) => self.scroll = self.scroll.saturating_add(1),
+            (Action::SelectPrev, None) => self.scroll = self.scroll.saturating_sub(1),
+            (Action::Continue(None), None) => {}
+            _ => return false,
         }
 
+        true
+    }
+
+    /// The key bound to an action outside of any other mode, to hint at it with.
+    fn key_hint(&self, action: &Action) -> Option<String> {
+        let keymap = self.config.keybindings.get(&Mode::Home)?;
+        let keys = keymap.iter().filter(|(keys, bound)| keys.len() == 1 && *bound == action);
+        keys.map(|(keys, _)| key_event_to_string(&keys[0])).min()
+    }
+
+    /// Renders the sorting and filtering state above the list.
+    fn draw_header(&self, frame: &mut Frame, area: Rect) {
+        let accent_style =
+            Style::default().add_modifier(Modifier::BOLD).fg(Color::LightMagenta);
+        let dim_style = Style::default().add_modifier(Modifier::DIM);
+        let hint = |action| {
+            self.key_hint(&action).map_or_else(String::new, |key| format!(" ({key})"))
+        };
+
+        let posts = match self.visible.len() {
+            1 => String::from(" · 1 post"),
+            n => format!(" · {n} posts"),
+        };
+
+        let line = Line::from(vec![
+            Span::raw(format!("{:>3}", " ")),
+            Span::styled("sort: ", dim_style),
+            Span::styled(self.sort.to_string(), accent_style),
+            Span::styled(hint(Action::CycleSort), dim_style),
+            Span::styled(" · tag: ", dim_style),
+            Span::styled(
+                self.tag.as_deref().map_or("all".to_string(), |tag| format!("#{tag}")),
+                accent_style,
+            ),
+            Span::styled(hint(Action::CycleTag), dim_style),
+            Span::styled(posts, dim_style),
+        ]);
+
+        frame.render_widget(line, area);
+    }
+
+    /// Renders the search line at the bottom of the list.
+    fn draw_search(&self, frame: &mut Frame, area: Rect) {
+        let accent_style =
+            Style::default().add_modifier(Modifier::BOLD).fg(Color::LightMagenta);
+        let mut spans = vec![Span::styled("/", accent_style)];
+        spans.extend(self.query.spans(self.searching));
+
+        let matches = match self.visible.len() {
+            1 => String::from("  1 match"),
+            n => format!("  {n} matches"),
+        };
+        spans.push(Span::styled(matches, Style::default().add_modifier(Modifier::DIM)));
+
+        frame.render_widget(Line::from(spans), area);
+    }
+}
+
+impl Component for BlogPosts {
+    fn init(&mut self, term_info: Arc<RwLock<TerminalInfo>>, _: Size) -> Result<()> {
+        let locked_info = term_info.blocking_read();
+        self.text_sizing = locked_info.supports_text_sizing();
+        self.image_renderer = image_picker(&locked_info);
+
         Ok(())
     }
 
-    fn register_config_handler(&mut self, config: crate::config::Config) -> Result<()> {
+    fn register_config_handler(&mut self, config: Config) -> Result<()> {
+        self.config = config.clone();
         self.list.register_config_handler(config)
     }
 
@@ -104,16 +364,79 @@ impl Component for BlogPosts {
         self.list.register_action_handler(tx)
     }
 
+    fn is_focused(&self) -> bool {
+        self.searching
+    }
+
+    fn handle_focused_event(&mut self, event: &Event) -> Result<bool> {
+        // The list is filtered as the query is typed, but not as the cursor moves
+        let query = self.query.value().to_string();
+        let handled = match event {
+            Event::Key(key) => self.query.handle_key(key),
+            Event::Paste(text) => {
+                self.query.paste(text);
+                true
+            }
+            _ => false,
+        };
+
+        if self.query.value() != query {
+            self.filter();
+        }
+        Ok(handled)
+    }
+
     fn update(&mut self, action: Action) -> Result<Option<Action>> {
+        if self.is_in_post() && self.navigate_post(&action) {
+            return Ok(None);
+        }
+
         match self.list.update(action.clone())?.unwrap() {
             // safe to unwrap, guaranteed to not be `None`
             Action::Tick => {}
             Action::Render => {}
-            Action::Quit | Action::PrevTab | Action::NextTab => self.in_post = (None, None),
+            Action::Quit if !self.is_in_post() && self.is_filtered() => {
+                self.query.clear();
+                self.filter();
+            }
+            Action::Quit | Action::PrevTab | Action::NextTab | Action::SelectTab(_) => {
+                self.in_post = (None, None)
+            }
+            Action::OpenPost(index) if index < self.posts.len() => self.jump_to_post(index),
 
-            // FIXME: This makes it possible to scroll through the list with arrow keys even
-            // when it is not rendered, which is not ideal; should probably fix later, minor bug
-            Action::Continue(post_id) => self.in_post.1 = post_id,
+            Action::Continue(row) => match row.and_then(|i| self.list.options.get(i)) {
+                Some(&ListRow::Year { year, .. }) => self.toggle_year(year),
+                Some(&ListRow::Post { index, .. }) => self.open_post(index),
+                None => self.in_post.1 = None,
+            },
+            Action::ToggleToc if self.is_in_post() => {
+                self.toc = match self.toc {
+                    Some(_) => None,
+                    None => Some(ListState::default().with_selected(Some(0))),
+                }
+            }
+
+            Action::Search => {
+                self.searching = true;
+                self.in_post = (None, None);
+            }
+            // Queries can be brought back from the history later on
+            Action::ConfirmSearch => {
+                self.searching = false;
+                self.query.push_history();
+            }
+            Action::CancelSearch => {
+                self.searching = false;
+                self.query.clear();
+                self.filter();
+            }
+            Action::NextMatch => self.step_match(true),
+            Action::PrevMatch => self.step_match(false),
+            Action::CycleSort if !self.is_in_post() => {
+                self.sort = self.sort.next();
+                self.filter();
+            }
+            Action::CycleTag if !self.is_in_post() => self.cycle_tag(),
             _ => {}
         };
 
@@ -135,8 +458,54 @@ impl Component for BlogPosts {
                 format!("# {}\n\n{}", title, post.content)
             });
 
-            let post_body_widget =
-                Paragraph::new(tui_markdown::from_str(&post_body)).wrap(Wrap { trim: true });
+            let post_body_widget = MarkdownView::new(&post_body)
+                .text_sizing(self.text_sizing)
+                .highlight(&self.list.highlight);
+
+            // The table of contents takes up a column to the right of the post
+            let (area, toc_area) = match self.toc {
+                Some(_) => {
+                    let [area, toc_area] = Layout::horizontal([
+                        Constraint::Min(0),
+                        Constraint::Length((area.width / 3).min(32)),
+                    ])
+                    .areas(area);
+                    (area, Some(toc_area))
+                }
+                None => (area, None),
+            };
+
+            if toc_area.is_some() || self.jump_to.is_some() {
+                let headings = post_body_widget.headings(area.width);
+                if let Some(heading) = self.jump_to.take().and_then(|i| headings.get(i)) {
+                    self.scroll = heading.row;
+                }
+
+                if let (Some(toc), Some(toc_area)) = (&mut self.toc, toc_area) {
+                    let items = headings.iter().map(|heading| {
+                        let indent = "  ".repeat(heading.level.saturating_sub(1).into());
+                        ListItem::new(format!("{indent}{}", heading.text))
+                    });
+
+                    frame.render_stateful_widget(
+                        List::new(items)
+                            .block(Block::default().borders(Borders::LEFT).title(" contents "))
+                            .highlight_style(
+                                Style::default()
+                                    .add_modifier(Modifier::BOLD)
+                                    .fg(Color::LightMagenta),
+                            )
+                            .highlight_symbol("▶ "),
+                        toc_area,
+                        toc,
+                    );
+                }
+            }
+
+            // Keep at least the last row of the post in view
+            self.scroll =
+                self.scroll.min(post_body_widget.height(area.width).saturating_sub(1));
+            let post_body_widget = post_body_widget.scroll(self.scroll);
 
             // FIXME: content in the body often overlaps with the `Cat` component and gets
             // formatted weirdly. maybe deal with that at some point? real solution is probably a
@@ -156,12 +525,11 @@ impl Component for BlogPosts {
 
                 frame.render_stateful_widget(StatefulImage::default(), image_area, img);
                 frame.render_widget(post_body_widget, text_area);
-            } else if self.image_renderer.is_some() {
+            } else if let Some(picker) = &self.image_renderer {
                 // Image not cached, load image and skip rendering for current draw call
                 if let Some(ref post_ogp) = post.ogp {
                     let rt = tokio::runtime::Handle::current();
-                    let img =
-                        rt.block_on(async { self.header_image(post_ogp.clone()).await })?;
+                    let img = rt.block_on(fetch_image(picker, &post_ogp.url))?;
                     self.in_post.0 = Some(img);
                 } else {
                     frame.render_widget(
@@ -189,9 +557,172 @@ impl Component for BlogPosts {
                 );
             }
         } else {
-            self.list.draw(frame, area)?;
+            // The search line is only shown while there is a search
+            let search_height = u16::from(self.searching || self.is_filtered());
+            let [header_area, list_area, search_area] = Layout::vertical([
+                Constraint::Length(2),
+                Constraint::Min(0),
+                Constraint::Length(search_height),
+            ])
+            .areas(area);
+
+            self.draw_header(frame, header_area);
+            self.list.draw(frame, list_area)?;
+            if search_height > 0 {
+                self.draw_search(frame, search_area);
+            }
         }
 
         Ok(())
     }
 }
+
+/// The year a post was published in, which posts without a date are counted as 1970 for.
+fn year(post: &Post) -> i32 {
+    blog::created_at(post).map_or(1970, |date| date.year())
+}
+
+#[cfg(test)]
+mod tests {
+    use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+    use super::*;
+    use crate::harness::{dispatch, fixtures, press, render, type_text};
+
+    fn blog_posts() -> Result<BlogPosts> {
+        let mut blog_posts = BlogPosts::new(fixtures::posts());
+        blog_posts.register_config_handler(Config::new()?)?;
+        Ok(blog_posts)
+    }
+
+    fn control(blog_posts: &mut BlogPosts, c: char) -> Result<()> {
+        let key = KeyEvent::new(KeyCode::Char(c), KeyModifiers::CONTROL);
+        blog_posts.handle_focused_event(&Event::Key(key))?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_sort_and_filter() -> Result<()> {
+        let mut blog_posts = blog_posts()?;
+        let screen = render(&mut blog_posts, 100, 20)?;
+        assert!(screen.contains("sort: newest first (s) · tag: all (t) · 2 posts"));
+        assert!(screen.contains("▶  2025-03-14 • Writing a TUI"));
+        assert!(screen.contains("▾ 2024 (1 post)"));
+
+        // Headers are skipped over by default, but can be selected to collapse their year
+        dispatch(&mut blog_posts, [Action::SelectNext])?;
+        assert!(render(&mut blog_posts, 100, 20)?.contains("▶  ▾ 2024 (1 post)"));
+        dispatch(&mut blog_posts, [Action::Continue(None)])?;
+        let screen = render(&mut blog_posts, 100, 20)?;
+        assert!(screen.contains("▶  ▸ 2024 (1 post)") && !screen.contains("Hello, world"));
+        dispatch(&mut blog_posts, [Action::Continue(None), Action::SelectNext])?;
+        let screen = render(&mut blog_posts, 100, 20)?;
+        assert!(screen.contains("▶  2024-11-02 • Hello, world"));
+        assert!(screen.contains("The obligatory first post"));
+
+        // Sorting by title drops the headers
+        dispatch(&mut blog_posts, [Action::CycleSort, Action::CycleSort])?;
+        let screen = render(&mut blog_posts, 100, 20)?;
+        assert!(screen.contains("sort: title") && !screen.contains("2024 (1 post)"));
+        assert!(screen.contains("▶  2024-11-02 • Hello, world"));
+
+        // Tags come from hashtags within posts
+        dispatch(&mut blog_posts, [Action::CycleTag])?;
+        let screen = render(&mut blog_posts, 100, 20)?;
+        assert!(
+            screen.contains("tag: #ssh (t) · 1 post") && !screen.contains("Writing a TUI")
+        );
+        dispatch(&mut blog_posts, [Action::CycleTag, Action::CycleSort])?;
+        assert!(render(&mut blog_posts, 100, 20)?.contains("tag: all (t) · 2 posts"));
+
+        dispatch(&mut blog_posts, [Action::Continue(None)])?;
+        let screen = render(&mut blog_posts, 100, 20)?;
+        assert!(screen.contains("Some notes on building terminal interfaces"));
+
+        // Quitting from a post goes back to the list
+        dispatch(&mut blog_posts, [Action::Quit])?;
+        assert!(render(&mut blog_posts, 100, 20)?.contains("Hello, world"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_table_of_contents() -> Result<()> {
+        let mut blog_posts = blog_posts()?;
+        let screen = render(&mut blog_posts, 100, 20)?;
+        assert!(screen.contains("Writing a TUI · 1 min read · 8 words"));
+
+        // The second post down, past the header of its year
+        let open = [Action::SelectNext, Action::SelectNext, Action::Continue(None)];
+        dispatch(&mut blog_posts, open)?;
+        dispatch(&mut blog_posts, [Action::ToggleToc])?;
+        let screen = render(&mut blog_posts, 100, 20)?;
+        assert!(screen.contains("contents") && screen.contains("▶ Hello, world"));
+        assert!(screen.contains("This blog is served over #SSH."));
+
+        // Jumping to a section scrolls the post down to it
+        dispatch(&mut blog_posts, [Action::SelectNext, Action::Continue(None)])?;
+        let screen = render(&mut blog_posts, 100, 20)?;
+        assert!(screen.contains("Because it's fun") && !screen.contains("served over"));
+
+        // Without the table of contents, moving the selection scrolls the post instead
+        dispatch(&mut blog_posts, [Action::ToggleToc])?;
+        dispatch(&mut blog_posts, vec![Action::SelectPrev; 4])?;
+        let screen = render(&mut blog_posts, 100, 20)?;
+        assert!(screen.contains("served over") && !screen.contains("contents"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_search() -> Result<()> {
+        let mut blog_posts = blog_posts()?;
+        dispatch(&mut blog_posts, [Action::Search])?;
+        assert!(blog_posts.is_focused());
+
+        // The list is filtered as the query is typed
+        type_text(&mut blog_posts, "q")?;
+        press(&mut blog_posts, &[KeyCode::Backspace])?;
+        type_text(&mut blog_posts, "tui")?;
+        let screen = render(&mut blog_posts, 100, 20)?;
+        assert!(screen.contains("/tui█  1 match"));
+        assert!(screen.contains("Writing a TUI") && !screen.contains("Hello, world"));
+
+        dispatch(&mut blog_posts, [Action::ConfirmSearch, Action::Continue(None)])?;
+        let screen = render(&mut blog_posts, 100, 20)?;
+        assert!(screen.contains("Some notes on building terminal interfaces"));
+
+        // Quitting leaves the post, then clears the search
+        dispatch(&mut blog_posts, [Action::Quit])?;
+        assert!(render(&mut blog_posts, 100, 20)?.contains("/tui  1 match"));
+        dispatch(&mut blog_posts, [Action::Quit])?;
+        let screen = render(&mut blog_posts, 100, 20)?;
+        assert!(screen.contains("Hello, world") && !screen.contains("match"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_search_input() -> Result<()> {
+        let mut blog_posts = blog_posts()?;
+        dispatch(&mut blog_posts, [Action::Search])?;
+
+        // Pasted text arrives all at once, and can be edited with the cursor anywhere in it
+        blog_posts.handle_focused_event(&Event::Paste(String::from("writing")))?;
+        assert!(render(&mut blog_posts, 100, 20)?.contains("/writing█"));
+        press(&mut blog_posts, &[KeyCode::Home, KeyCode::Char('x')])?;
+        control(&mut blog_posts, 'e')?;
+        assert!(render(&mut blog_posts, 100, 20)?.contains("/xwriting█"));
+        control(&mut blog_posts, 'w')?;
+        assert!(render(&mut blog_posts, 100, 20)?.contains("/█"));
+
+        // Confirmed queries can be brought back once the search is cleared
+        type_text(&mut blog_posts, "tui")?;
+        dispatch(&mut blog_posts, [Action::ConfirmSearch, Action::Quit, Action::Search])?;
+        assert!(render(&mut blog_posts, 100, 20)?.contains("/█"));
+        control(&mut blog_posts, 'p')?;
+        assert!(render(&mut blog_posts, 100, 20)?.contains("/tui█  1 match"));
+
+        Ok(())
+    }
+}
@@ -1,14 +1,97 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+#[cfg(feature = "blog")]
+use color_eyre::eyre::eyre;
 use color_eyre::Result;
+use crossterm::event::{MouseButton, MouseEvent, MouseEventKind};
+#[cfg(feature = "blog")]
+use ratatui::layout::Flex;
 use ratatui::prelude::*;
 use ratatui::widgets::*;
+#[cfg(feature = "blog")]
+use ratatui_image::picker::Picker;
+#[cfg(feature = "blog")]
+use ratatui_image::protocol::StatefulProtocol;
+#[cfg(feature = "blog")]
+use ratatui_image::StatefulImage;
+use tokio::sync::mpsc::UnboundedSender;
+use tokio::sync::RwLock;
+#[cfg(feature = "blog")]
+use tokio::task::JoinHandle;
 
 use super::Component;
 use crate::action::Action;
+use crate::forge::RepoMetadata;
+use crate::tui::terminal::TerminalInfo;
+
+/// The narrowest a card may get before the grid drops down to fewer columns.
+const MIN_CARD_WIDTH: u16 = 36;
+/// The most columns of cards shown side by side, no matter how wide the terminal is.
+const MAX_COLUMNS: u16 = 4;
+/// The Nerd Font repository icon, shown before the name of each card.
+const REPO_ICON: char = '\u{ea62}';
 
 #[derive(Debug, Clone)]
 pub struct Card<'a> {
-    pub title: &'a str,
+    /// The project's repository, as `owner/name`.
+    pub repository: &'a str,
     pub description: &'a str,
+    /// A longer description, shown once the card is opened.
+    pub details: &'a str,
+    /// Languages and tools the project is built with.
+    pub stack: &'a [&'a str],
+    /// Links other than the repository, along with what they lead to.
+    pub links: &'a [(&'a str, &'a str)],
+    /// A screenshot of the project, shown once the card is opened.
+    pub screenshot: Option<&'a str>,
+}
+
+impl Card<'_> {
+    /// The heading of the card, which is the repository along with an icon.
+    fn title(&self) -> String {
+        format!("{REPO_ICON} {}", self.repository)
+    }
+
+    /// The description of the project, preferring the one on its forge as it's kept up to date.
+    fn description<'a>(&'a self, metadata: Option<&'a RepoMetadata>) -> &'a str {
+        metadata
+            .and_then(|metadata| metadata.description.as_deref())
+            .unwrap_or(self.description)
+    }
+
+    /// The lines shown within the card in the grid.
+    fn lines<'a>(&'a self, metadata: Option<&'a RepoMetadata>) -> Vec<Line<'a>> {
+        let mut lines = vec![
+            Line::styled(
+                self.title(),
+                Style::default().fg(Color::Blue).add_modifier(Modifier::UNDERLINED),
+            ),
+            Line::raw(self.description(metadata)),
+        ];
+
+        if let Some(metadata) = metadata {
+            let stats = [metadata.stars.map(|stars| format!("★ {stars}"))]
+                .into_iter()
+                .chain([metadata.language.clone(), metadata.releases.first().cloned()])
+                .flatten()
+                .collect::<Vec<_>>();
+            lines.push(Line::styled(
+                stats.join(" · "),
+                Style::default().add_modifier(Modifier::DIM),
+            ));
+        }
+
+        lines
+    }
+}
+
+/// How many rows a card with the given lines takes up when drawn at the given width.
+fn card_height(lines: &[Line], width: u16) -> u16 {
+    let paragraph = Paragraph::new(lines.to_vec()).wrap(Wrap { trim: true });
+
+    // The borders take up two rows
+    paragraph.line_count(width.saturating_sub(4)) as u16 + 2
 }
 
 // FIXME: Redundant border drawing logic, see `Tabs` component
@@ -46,68 +129,464 @@ fn draw_custom_border(buf: &mut Buffer, rect: Rect, style: Style, clip: Rect) {
     }
 }
 
-impl<'a> Component for Vec<Card<'a>> {
-    fn update(&mut self, action: Action) -> Result<Option<Action>> {
-        match action {
-            Action::Tick => {}
-            Action::Render => {}
-            _ => {}
+/// The screenshot of the open card, which is fetched in the background once it's opened.
+#[cfg(feature = "blog")]
+#[derive(Default)]
+enum Screenshot {
+    #[default]
+    Loading,
+    Loaded(StatefulProtocol),
+    Failed,
+}
+
+/// Fetches a screenshot in the background, asking for a redraw once it's in.
+#[cfg(feature = "blog")]
+fn fetch_screenshot(
+    picker: Picker,
+    url: String,
+    screenshot: Arc<std::sync::Mutex<Screenshot>>,
+    action_tx: Option<UnboundedSender<Action>>,
+) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let fetched = match super::fetch_image(&picker, &url).await {
+            Ok(image) => Screenshot::Loaded(image),
+            Err(err) => {
+                tracing::warn!("Failed to fetch screenshot: {err}");
+                Screenshot::Failed
+            }
+        };
+
+        if let Ok(mut screenshot) = screenshot.lock() {
+            *screenshot = fetched;
         }
-        Ok(None)
+        if let Some(action_tx) = action_tx {
+            let _ = action_tx.send(Action::ImageLoaded);
+        }
+    })
+}
+
+/// How many columns of cards fit side by side within the given width.
+fn columns(width: u16) -> usize {
+    (width / MIN_CARD_WIDTH).clamp(1, MAX_COLUMNS).into()
+}
+
+/// A grid of project cards, which can be opened to show more details about a project.
+#[derive(Default)]
+pub struct Projects {
+    cards: Vec<Card<'static>>,
+    selected: usize,
+    /// Whether the details of the selected card are shown in place of the grid.
+    open: bool,
+    /// The first row of cards shown, which follows the selection once it goes out of view.
+    offset: usize,
+    /// Where each card was last drawn, to tell which one is clicked. Cards which are out of
+    /// view have an empty area.
+    areas: Vec<Rect>,
+    /// Details about each card's repository fetched from the forge, keyed by `owner/name`.
+    metadata: HashMap<String, RepoMetadata>,
+
+    #[cfg(feature = "blog")]
+    action_tx: Option<UnboundedSender<Action>>,
+    #[cfg(feature = "blog")]
+    image_renderer: Option<Picker>,
+    /// The screenshot of the open card, shared with the task fetching it.
+    #[cfg(feature = "blog")]
+    screenshot: Arc<std::sync::Mutex<Screenshot>>,
+    /// The task fetching the screenshot of the open card, if it was started.
+    #[cfg(feature = "blog")]
+    screenshot_task: Option<JoinHandle<()>>,
+}
+
+impl Projects {
+    pub fn new(cards: Vec<Card<'static>>) -> Self {
+        Self { cards, ..Default::default() }
     }
 
-    fn draw(&mut self, frame: &mut Frame, area: Rect) -> Result<()> {
-        let num_rows = (self.len() as f32 / 3.0).ceil() as usize;
-        let row_constraints = vec![Constraint::Length(6); num_rows];
-
-        let rows = Layout::default()
-            .direction(Direction::Vertical)
-            .constraints(row_constraints)
-            .split(area);
-
-        for (i, row) in rows.iter().enumerate() {
-            let cols = Layout::default()
-                .direction(Direction::Horizontal)
-                .constraints([
-                    Constraint::Percentage(33),
-                    Constraint::Percentage(33),
-                    Constraint::Percentage(34),
-                ])
-                .split(*row);
-
-            for (j, col) in cols.iter().enumerate() {
-                let index = i * 3 + j;
-                if let Some(card) = self.get(index) {
-                    let border_style = Style::default().add_modifier(Modifier::DIM);
-                    let buf = frame.buffer_mut();
-
-                    draw_custom_border(buf, *col, border_style, area);
-
-                    let paragraph = Paragraph::new(vec![
-                        Line::styled(
-                            card.title,
-                            Style::default()
-                                .fg(Color::Blue)
-                                .add_modifier(Modifier::UNDERLINED),
-                        ),
-                        Line::raw(card.description),
-                    ])
+    /// Whether the details of a card are being shown.
+    pub fn is_open(&self) -> bool {
+        self.open
+    }
+
+    fn select(&mut self, index: usize) {
+        self.selected = index.min(self.cards.len().saturating_sub(1));
+    }
+
+    fn open(&mut self) {
+        self.open = !self.cards.is_empty();
+
+        // A screenshot still being fetched for the card opened before is of no use anymore
+        #[cfg(feature = "blog")]
+        {
+            if let Some(task) = self.screenshot_task.take() {
+                task.abort();
+            }
+            self.screenshot = Arc::default();
+        }
+    }
+
+    /// Draws the cards in as many columns as fit, each row as tall as its tallest card.
+    fn draw_grid(&mut self, frame: &mut Frame, area: Rect) {
+        let columns = columns(area.width);
+        let col_width = area.width / columns as u16;
+        let heights = self
+            .cards
+            .chunks(columns)
+            .map(|row| {
+                row.iter()
+                    .map(|card| {
+                        card_height(&card.lines(self.metadata.get(card.repository)), col_width)
+                    })
+                    .max()
+                    .unwrap_or_default()
+            })
+            .collect::<Vec<_>>();
+
+        // Scroll just far enough for the selected card to be in view
+        let selected_row = self.selected / columns;
+        self.offset = self.offset.min(selected_row);
+        while self.offset < selected_row
+            && heights[self.offset..=selected_row].iter().sum::<u16>() > area.height
+        {
+            self.offset += 1;
+        }
+
+        self.areas = vec![Rect::default(); self.cards.len()];
+        let mut y = area.y;
+        for (i, row) in self.cards.chunks(columns).enumerate().skip(self.offset) {
+            if y >= area.bottom() {
+                break;
+            }
+
+            let row_area = Rect::new(area.x, y, area.width, heights[i].min(area.bottom() - y));
+            let cols = Layout::horizontal(vec![Constraint::Ratio(1, columns as u32); columns])
+                .split(row_area);
+
+            for (j, (card, col)) in row.iter().zip(cols.iter()).enumerate() {
+                let index = i * columns + j;
+                self.areas[index] = *col;
+
+                let border_style = if index == self.selected {
+                    Style::default().fg(Color::LightMagenta).add_modifier(Modifier::BOLD)
+                } else {
+                    Style::default().add_modifier(Modifier::DIM)
+                };
+
+                draw_custom_border(frame.buffer_mut(), *col, border_style, area);
+
+                let paragraph = Paragraph::new(card.lines(self.metadata.get(card.repository)))
                     .style(Style::default())
                     .wrap(Wrap { trim: true });
 
+                frame.render_widget(
+                    paragraph,
+                    Rect {
+                        x: col.x + 2,
+                        y: col.y + 1,
+                        width: col.width.saturating_sub(4),
+                        height: col.height.saturating_sub(2),
+                    },
+                );
+            }
+
+            y = y.saturating_add(heights[i]);
+        }
+    }
+
+    /// Draws the details of the selected card, with its screenshot beside them if it has one.
+    fn draw_details(&mut self, frame: &mut Frame, area: Rect) -> Result<()> {
+        let card = &self.cards[self.selected];
+        let metadata = self.metadata.get(card.repository);
+        let accent_style =
+            Style::default().fg(Color::LightMagenta).add_modifier(Modifier::BOLD);
+        let dim_style = Style::default().add_modifier(Modifier::DIM);
+        draw_custom_border(frame.buffer_mut(), area, accent_style, area);
+
+        let inner = Rect {
+            x: area.x + 2,
+            y: area.y + 1,
+            width: area.width.saturating_sub(4),
+            height: area.height.saturating_sub(2),
+        };
+
+        let mut lines = vec![
+            Line::styled(
+                card.title(),
+                Style::default().fg(Color::Blue).add_modifier(Modifier::UNDERLINED),
+            ),
+            Line::styled(
+                card.description(metadata),
+                Style::default().add_modifier(Modifier::ITALIC),
+            ),
+            Line::default(),
+        ];
+        lines.extend(card.details.lines().map(Line::raw));
+
+        if !card.stack.is_empty() {
+            lines.push(Line::default());
+            lines.push(Line::from(vec![
+                Span::styled("built with ", dim_style),
+                Span::styled(card.stack.join(" · "), accent_style),
+            ]));
+        }
+
+        if let Some(metadata) = metadata {
+            lines.push(Line::default());
+            let stats = [
+                ("stars", metadata.stars.map(|stars| stars.to_string())),
+                ("language", metadata.language.clone()),
+                ("last commit", metadata.last_commit_date().map(String::from)),
+                (
+                    "releases",
+                    Some(metadata.releases.join(" · ")).filter(|tags| !tags.is_empty()),
+                ),
+            ];
+            for (label, value) in stats {
+                if let Some(value) = value {
+                    lines.push(Line::from(vec![
+                        Span::styled(format!("{label:<12}"), dim_style),
+                        Span::raw(value),
+                    ]));
+                }
+            }
+        }
+
+        lines.push(Line::default());
+        let repository = crate::forge::repository_url(card.repository);
+        let links = [("repository", repository.as_str())]
+            .into_iter()
+            .chain(card.links.iter().copied());
+        for (label, url) in links {
+            lines.push(Line::from(vec![
+                Span::styled(format!("{label:<12}"), dim_style),
+                Span::styled(url.to_string(), Style::default().fg(Color::Blue)),
+            ]));
+        }
+
+        let text_area = match card.screenshot {
+            #[cfg(feature = "blog")]
+            Some(url) if self.image_renderer.is_some() => {
+                let [text_area, image_area] = Layout::horizontal([
+                    Constraint::Percentage(55),
+                    Constraint::Percentage(45),
+                ])
+                .spacing(2)
+                .areas(inner);
+
+                // Fetched on the first draw after opening, so that only screenshots which are
+                // looked at are fetched
+                if self.screenshot_task.is_none() {
+                    self.screenshot_task = Some(fetch_screenshot(
+                        self.image_renderer.clone().unwrap(),
+                        url.to_string(),
+                        Arc::clone(&self.screenshot),
+                        self.action_tx.clone(),
+                    ));
+                }
+
+                let mut screenshot =
+                    self.screenshot.lock().map_err(|_| eyre!("Screenshot lock poisoned"))?;
+                let placeholder = match &mut *screenshot {
+                    Screenshot::Loaded(screenshot) => {
+                        frame.render_stateful_widget(
+                            StatefulImage::default(),
+                            image_area,
+                            screenshot,
+                        );
+                        None
+                    }
+                    Screenshot::Loading => Some("loading screenshot..."),
+                    Screenshot::Failed => Some("screenshot unavailable"),
+                };
+
+                if let Some(placeholder) = placeholder {
+                    let [placeholder_area] = Layout::vertical([Constraint::Length(1)])
+                        .flex(Flex::Center)
+                        .areas(image_area);
                     frame.render_widget(
-                        paragraph,
-                        Rect {
-                            x: col.x + 2,
-                            y: col.y + 1,
-                            width: col.width.saturating_sub(4),
-                            height: col.height.saturating_sub(2),
-                        },
+                        Line::styled(placeholder, dim_style).centered(),
+                        placeholder_area,
                     );
                 }
+
+                text_area
+            }
+            Some(url) => {
+                // Without any way to display images, link to the screenshot instead
+                lines.push(Line::from(vec![
+                    Span::styled(format!("{:<12}", "screenshot"), dim_style),
+                    Span::styled(url, Style::default().fg(Color::Blue)),
+                ]));
+                inner
+            }
+            None => inner,
+        };
+
+        frame.render_widget(Paragraph::new(lines).wrap(Wrap { trim: true }), text_area);
+        Ok(())
+    }
+}
+
+impl Component for Projects {
+    #[cfg_attr(not(feature = "blog"), allow(unused_variables))]
+    fn register_action_handler(&mut self, tx: UnboundedSender<Action>) -> Result<()> {
+        // Only used to redraw once a screenshot has been fetched
+        #[cfg(feature = "blog")]
+        {
+            self.action_tx = Some(tx);
+        }
+
+        Ok(())
+    }
+
+    #[cfg_attr(not(feature = "blog"), allow(unused_variables))]
+    fn init(&mut self, term_info: Arc<RwLock<TerminalInfo>>, _: Size) -> Result<()> {
+        #[cfg(feature = "blog")]
+        {
+            self.image_renderer = super::image_picker(&term_info.blocking_read());
+        }
+
+        #[cfg(feature = "forge")]
+        if let Some(forge) = &*crate::forge::FORGE {
+            // Kept fresh in the background by `forge::warm_cache`, so this never waits on it
+            let repos = self.cards.iter().map(|card| card.repository).collect::<Vec<_>>();
+            self.metadata = forge.cached(&repos);
+        }
+
+        Ok(())
+    }
+
+    fn handle_mouse_event(&mut self, mouse: MouseEvent) -> Result<Option<Action>> {
+        let position = Position::new(mouse.column, mouse.row);
+        match mouse.kind {
+            // Clicking a card selects it, and clicking it again opens it
+            MouseEventKind::Down(MouseButton::Left) if !self.open => {
+                if let Some(index) = self.areas.iter().position(|area| area.contains(position))
+                {
+                    if index == self.selected {
+                        self.open();
+                    } else {
+                        self.select(index);
+                    }
+                }
+            }
+            MouseEventKind::Down(MouseButton::Right) => self.open = false,
+            MouseEventKind::ScrollDown if !self.open => self.select(self.selected + 1),
+            MouseEventKind::ScrollUp if !self.open => {
+                self.select(self.selected.saturating_sub(1))
+            }
+            _ => {}
+        }
+
+        Ok(None)
+    }
+
+    fn update(&mut self, action: Action) -> Result<Option<Action>> {
+        match action {
+            Action::Tick => {}
+            Action::Render => {}
+            Action::SelectNext if !self.open => self.select(self.selected + 1),
+            Action::SelectPrev if !self.open => self.select(self.selected.saturating_sub(1)),
+            Action::Continue(None) if !self.open => self.open(),
+            Action::OpenProject(index) => {
+                self.select(index);
+                self.open();
             }
+            Action::Quit | Action::PrevTab | Action::NextTab | Action::SelectTab(_) => {
+                self.open = false
+            }
+            _ => {}
         }
 
+        Ok(None)
+    }
+
+    fn draw(&mut self, frame: &mut Frame, area: Rect) -> Result<()> {
+        if self.open {
+            self.draw_details(frame, area)?;
+        } else {
+            self.draw_grid(frame, area);
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crossterm::event::KeyModifiers;
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::components::Content;
+    use crate::harness::{dispatch, render};
+
+    fn click(
+        projects: &mut Projects,
+        button: MouseButton,
+        column: u16,
+        row: u16,
+    ) -> Result<()> {
+        let kind = MouseEventKind::Down(button);
+        let modifiers = KeyModifiers::empty();
+        projects.handle_mouse_event(MouseEvent { kind, column, row, modifiers })?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_columns() {
+        assert_eq!(columns(20), 1);
+        assert_eq!(columns(114), 3);
+        assert_eq!(columns(400), 4);
+    }
+
+    #[test]
+    fn test_card_lines() {
+        let card = Card {
+            repository: "erica/project",
+            description: "Written into the portfolio",
+            details: "",
+            stack: &[],
+            links: &[],
+            screenshot: None,
+        };
+        assert_eq!(card.lines(None).len(), 2);
+        assert_eq!(card.lines(None)[0].to_string(), "\u{ea62} erica/project");
+
+        let metadata = RepoMetadata {
+            description: Some(String::from("Fresh from the forge")),
+            stars: Some(12),
+            language: Some(String::from("Rust")),
+            last_commit: None,
+            releases: vec![String::from("v1.2.0"), String::from("v1.1.0")],
+        };
+        let lines = card.lines(Some(&metadata));
+        assert_eq!(lines[1].to_string(), "Fresh from the forge");
+        assert_eq!(lines[2].to_string(), "★ 12 · Rust · v1.2.0");
+    }
+
+    #[test]
+    fn test_opening_cards() -> Result<()> {
+        let mut projects = Projects::new(Content::projects_content());
+        assert!(render(&mut projects, 100, 24)?.contains("0x5eal/luau-unzip"));
+
+        dispatch(&mut projects, [Action::SelectNext, Action::Continue(None)])?;
+        let screen = render(&mut projects, 100, 24)?;
+        assert!(screen.contains("built with GitHub Actions · Lanyard"));
+        assert!(screen.contains("https://github.com/CompeyDev/discord-status-action"));
+
+        // Quitting closes the project, rather than being passed on
+        dispatch(&mut projects, [Action::Quit])?;
+        assert!(!projects.is_open());
+        assert!(render(&mut projects, 100, 24)?.contains("0x5eal/luau-unzip"));
+
+        // Clicking a card selects it, and clicking it again opens it
+        click(&mut projects, MouseButton::Left, 2, 1)?;
+        assert!(!projects.is_open());
+        click(&mut projects, MouseButton::Left, 2, 1)?;
+        assert!(render(&mut projects, 100, 24)?.contains("built with Luau"));
+        click(&mut projects, MouseButton::Right, 2, 1)?;
+        assert!(!render(&mut projects, 100, 24)?.contains("built with"));
+
         Ok(())
     }
 }
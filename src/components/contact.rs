@@ -0,0 +1,402 @@
+use std::sync::Arc;
+use std::time::Instant;
+
+use color_eyre::Result;
+use ratatui::prelude::*;
+use ratatui::widgets::*;
+use tokio::sync::RwLock;
+
+use super::{Component, TextInput};
+use crate::action::Action;
+use crate::contact::{
+    Contact, Message, Rejection, MAX_MESSAGE_LENGTH, MAX_NAME_LENGTH, MAX_REPLY_TO_LENGTH,
+};
+use crate::ssh::Visitor;
+use crate::tui::Event;
+
+/// How many rows of the message are shown at once, longer ones scroll to keep the cursor in
+/// view.
+const MESSAGE_HEIGHT: u16 = 8;
+/// The widest the form gets.
+const FORM_WIDTH: u16 = 64;
+
+/// The part of the contact form being typed into.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Field {
+    Name,
+    ReplyTo,
+    Message,
+    Send,
+}
+
+impl Field {
+    fn next(self) -> Self {
+        match self {
+            Self::Name => Self::ReplyTo,
+            Self::ReplyTo => Self::Message,
+            Self::Message => Self::Send,
+            Self::Send => Self::Name,
+        }
+    }
+}
+
+/// A message being written, along with when the form was opened to tell people from bots.
+#[derive(Debug)]
+struct Form {
+    field: Field,
+    name: TextInput,
+    reply_to: TextInput,
+    body: TextInput,
+    opened_at: Instant,
+    /// Whether the message went through, the form only thanks the visitor then.
+    sent: bool,
+}
+
+impl Form {
+    fn new(name: &str) -> Self {
+        let mut form = Self {
+            field: Field::Name,
+            name: TextInput::new().max_length(MAX_NAME_LENGTH),
+            reply_to: TextInput::new().max_length(MAX_REPLY_TO_LENGTH),
+            body: TextInput::new().multiline(true).max_length(MAX_MESSAGE_LENGTH),
+            opened_at: Instant::now(),
+            sent: false,
+        };
+
+        form.name.paste(name);
+        form
+    }
+
+    /// The text field being typed into along with how many characters it may have, if any.
+    fn field(&self) -> Option<(&TextInput, usize)> {
+        match self.field {
+            Field::Name => Some((&self.name, MAX_NAME_LENGTH)),
+            Field::ReplyTo => Some((&self.reply_to, MAX_REPLY_TO_LENGTH)),
+            Field::Message => Some((&self.body, MAX_MESSAGE_LENGTH)),
+            Field::Send => None,
+        }
+    }
+
+    fn field_mut(&mut self) -> Option<&mut TextInput> {
+        match self.field {
+            Field::Name => Some(&mut self.name),
+            Field::ReplyTo => Some(&mut self.reply_to),
+            Field::Message => Some(&mut self.body),
+            Field::Send => None,
+        }
+    }
+
+    fn message(&self) -> Message {
+        Message {
+            name: self.name.value().to_string(),
+            reply_to: self.reply_to.value().to_string(),
+            body: self.body.value().to_string(),
+        }
+    }
+}
+
+/// A form for sending a message, shown in a popup over the current tab.
+#[derive(Default)]
+pub struct ContactForm {
+    contact: Option<Arc<Contact>>,
+    visitor: Arc<RwLock<Visitor>>,
+    form: Option<Form>,
+    /// Why the last attempt at sending the message didn't go through.
+    status: Option<String>,
+}
+
+impl ContactForm {
+    /// Sets where messages are delivered to, the form can't be opened without it.
+    pub fn set_contact(&mut self, contact: Arc<Contact>) {
+        self.contact = Some(contact);
+    }
+
+    /// Sets who is sending messages, which is only known once they authenticated.
+    pub fn set_visitor(&mut self, visitor: Arc<RwLock<Visitor>>) {
+        self.visitor = visitor;
+    }
+
+    /// Whether the form is shown.
+    pub fn is_open(&self) -> bool {
+        self.form.is_some()
+    }
+
+    fn open(&mut self) {
+        // The username is a good guess at a name, but it can be changed
+        self.form = Some(Form::new(&self.visitor.blocking_read().user));
+        self.status = None;
+    }
+
+    fn confirm(&mut self) {
+        let Some(form) = &mut self.form else {
+            return;
+        };
+
+        match form.field {
+            _ if form.sent => self.form = None,
+            // The message spans multiple lines, so it is sent from its own button instead, and
+            // enter starts a new line before it gets here
+            Field::Message => {}
+            Field::Name | Field::ReplyTo => form.field = form.field.next(),
+            Field::Send => self.send(),
+        }
+    }
+
+    fn send(&mut self) {
+        let (Some(form), Some(contact)) = (&mut self.form, &self.contact) else {
+            return;
+        };
+
+        let visitor = self.visitor.blocking_read();
+        match contact.send(&visitor, &form.message(), form.opened_at) {
+            Ok(()) => {
+                form.sent = true;
+                self.status = None;
+            }
+            // The message is kept around, so that it can be fixed up
+            Err(err) => {
+                self.status = Some(match err.downcast_ref::<Rejection>() {
+                    Some(rejection) => rejection.to_string(),
+                    None => {
+                        tracing::error!("Failed to send contact message: {err}");
+                        String::from("something went wrong, try again in a bit")
+                    }
+                });
+            }
+        }
+    }
+
+    /// A single line field, with a placeholder shown while it's empty.
+    fn draw_field(
+        frame: &mut Frame,
+        area: Rect,
+        label: &'static str,
+        input: &TextInput,
+        placeholder: &'static str,
+        focused: bool,
+    ) {
+        let accent_style =
+            Style::default().add_modifier(Modifier::BOLD).fg(Color::LightMagenta);
+        let dim_style = Style::default().add_modifier(Modifier::DIM);
+
+        let [label_area, input_area] =
+            Layout::horizontal([Constraint::Length(10), Constraint::Min(0)]).areas(area);
+        frame.render_widget(
+            Span::styled(label, if focused { accent_style } else { dim_style }),
+            label_area,
+        );
+
+        if input.is_empty() && !focused {
+            frame.render_widget(Span::styled(placeholder, dim_style), input_area);
+        } else {
+            input.render(frame, input_area, focused);
+        }
+    }
+
+    fn draw_form(&self, frame: &mut Frame, area: Rect, form: &Form) {
+        let accent_style =
+            Style::default().add_modifier(Modifier::BOLD).fg(Color::LightMagenta);
+        let dim_style = Style::default().add_modifier(Modifier::DIM);
+
+        let [name_row, reply_to_row, _, label_row, message_area, _, status_row, send_row] =
+            Layout::vertical([
+                Constraint::Length(1),
+                Constraint::Length(1),
+                Constraint::Length(1),
+                Constraint::Length(1),
+                Constraint::Length(MESSAGE_HEIGHT),
+                Constraint::Length(1),
+                Constraint::Length(1),
+                Constraint::Length(1),
+            ])
+            .areas(area);
+
+        Self::draw_field(frame, name_row, "name", &form.name, "", form.field == Field::Name);
+        Self::draw_field(
+            frame,
+            reply_to_row,
+            "reply to",
+            &form.reply_to,
+            "an email address, if you'd like a reply",
+            form.field == Field::ReplyTo,
+        );
+
+        let focused = form.field == Field::Message;
+        frame.render_widget(
+            Line::styled("message", if focused { accent_style } else { dim_style }),
+            label_row,
+        );
+        form.body.render(frame, message_area, focused);
+
+        if let Some(status) = &self.status {
+            frame.render_widget(
+                Line::styled(status.as_str(), Style::default().fg(Color::Red)),
+                status_row,
+            );
+        }
+
+        let send_style = if form.field == Field::Send {
+            accent_style.add_modifier(Modifier::REVERSED)
+        } else {
+            accent_style
+        };
+        frame.render_widget(Line::styled("[ send ]", send_style).right_aligned(), send_row);
+    }
+}
+
+impl Component for ContactForm {
+    fn is_focused(&self) -> bool {
+        self.form.as_ref().is_some_and(|form| !form.sent)
+    }
+
+    fn handle_focused_event(&mut self, event: &Event) -> Result<bool> {
+        let Some(input) = self.form.as_mut().and_then(Form::field_mut) else {
+            return Ok(false);
+        };
+
+        Ok(match event {
+            Event::Key(key) => input.handle_key(key),
+            Event::Paste(text) => {
+                input.paste(text);
+                true
+            }
+            _ => false,
+        })
+    }
+
+    fn update(&mut self, action: Action) -> Result<Option<Action>> {
+        match action {
+            Action::Contact if self.form.is_none() && self.contact.is_some() => self.open(),
+            Action::NextField => {
+                if let Some(form) = &mut self.form {
+                    form.field = form.field.next();
+                }
+            }
+            Action::ConfirmCompose => self.confirm(),
+            Action::CancelCompose => {
+                self.form = None;
+                self.status = None;
+            }
+            _ => {}
+        }
+
+        Ok(None)
+    }
+
+    fn draw(&mut self, frame: &mut Frame, area: Rect) -> Result<()> {
+        let Some(form) = &self.form else {
+            return Ok(());
+        };
+
+        let accent_style =
+            Style::default().add_modifier(Modifier::BOLD).fg(Color::LightMagenta);
+        let dim_style = Style::default().add_modifier(Modifier::DIM);
+
+        // The fields and send button take up eight rows besides the message
+        let (hint, count, height) = match form.field() {
+            _ if form.sent => (" enter to close ", None, 3),
+            Some((input, max)) => (
+                " tab for next field · esc to cancel ",
+                Some(format!(" {}/{max} ", input.len())),
+                MESSAGE_HEIGHT + 7,
+            ),
+            None => (" enter to send · esc to cancel ", None, MESSAGE_HEIGHT + 7),
+        };
+
+        // Centered over the tab, with room for the borders
+        let width = FORM_WIDTH.min(area.width.saturating_sub(4));
+        let height = (height + 2).min(area.height);
+        let popup = Rect::new(
+            area.x + area.width.saturating_sub(width) / 2,
+            area.y + area.height.saturating_sub(height) / 2,
+            width,
+            height,
+        );
+
+        let mut block = Block::bordered()
+            .border_type(BorderType::Rounded)
+            .border_style(accent_style)
+            .title(" send me a message ")
+            .title_bottom(Line::styled(hint, dim_style))
+            .padding(Padding::horizontal(1));
+        if let Some(count) = count {
+            block = block.title_bottom(Line::styled(count, dim_style).right_aligned());
+        }
+
+        let inner = block.inner(popup);
+        frame.render_widget(Clear, popup);
+        frame.render_widget(block, popup);
+
+        if form.sent {
+            let thanks = Line::styled(
+                "message sent, thanks for reaching out!",
+                Style::default().fg(Color::Green),
+            );
+            frame.render_widget(thanks.centered(), inner.inner(Margin::new(0, 1)));
+        } else {
+            self.draw_form(frame, inner, form);
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crossterm::event::KeyCode;
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::config::ContactConfig;
+    use crate::harness::{dispatch, press, render, type_text};
+
+    #[test]
+    fn test_sending() -> Result<()> {
+        let data_dir = std::env::temp_dir()
+            .join(format!("ssh-portfolio-contact-form-{}", std::process::id()));
+        let mut form = ContactForm::default();
+        form.set_contact(Arc::new(Contact::new(ContactConfig::default(), &data_dir)));
+        form.set_visitor(Arc::new(RwLock::new(Visitor {
+            user: String::from("visitor"),
+            ..Visitor::default()
+        })));
+
+        dispatch(&mut form, [Action::Contact])?;
+        let screen = render(&mut form, 100, 24)?;
+        assert!(screen.contains("send me a message"));
+        assert!(screen.contains("name      visitor█"));
+        assert!(screen.contains("reply to  an email address, if you'd like a reply"));
+
+        // Enter goes on to the next field, and starts a new line within the message
+        dispatch(&mut form, [Action::ConfirmCompose, Action::ConfirmCompose])?;
+        type_text(&mut form, "hi")?;
+        press(&mut form, &[KeyCode::Enter])?;
+        type_text(&mut form, "q!")?;
+        let screen = render(&mut form, 100, 24)?;
+        assert!(screen.contains("│ hi ") && screen.contains("│ q!█"));
+        assert!(screen.contains(" 5/2000 "));
+
+        // Messages sent right after opening the form are held back
+        dispatch(&mut form, [Action::NextField, Action::ConfirmCompose])?;
+        let screen = render(&mut form, 100, 24)?;
+        assert!(screen.contains("that was quick! take a moment before sending"));
+
+        if let Some(form) = &mut form.form {
+            form.opened_at = Instant::now() - Duration::from_secs(10);
+        }
+        dispatch(&mut form, [Action::ConfirmCompose])?;
+        let screen = render(&mut form, 100, 24)?;
+        assert!(screen.contains("message sent, thanks for reaching out!"));
+        assert!(!form.is_focused());
+        let delivered = std::fs::read_dir(data_dir.join("contact").join("new"))?.count();
+        assert_eq!(delivered, 1);
+
+        dispatch(&mut form, [Action::ConfirmCompose])?;
+        assert!(!form.is_open());
+
+        let _ = std::fs::remove_dir_all(data_dir);
+        Ok(())
+    }
+}
@@ -3,17 +3,23 @@ use std::sync::Arc;
 
 use color_eyre::eyre::eyre;
 use color_eyre::Result;
+use crossterm::event::MouseEvent;
 use figlet_rs::FIGfont;
 use ratatui::prelude::*;
 use ratatui::widgets::*;
 use tokio::sync::mpsc::UnboundedSender;
+use tokio::sync::RwLock;
 
 use super::Component;
 use crate::action::Action;
-use crate::components::Card;
 #[cfg(feature = "blog")]
 use crate::components::Post;
+use crate::components::{Card, Projects};
 use crate::config::Config;
+use crate::tui::terminal::TerminalInfo;
+use crate::tui::text_sizing::ScaledText;
+
+const GREETING: &str = "hiya!";
 
 #[allow(dead_code)]
 pub(super) fn truncate(s: &str, max: usize) -> String {
@@ -27,27 +33,58 @@ pub struct Content {
     command_tx: Option<UnboundedSender<Action>>,
     config: Config,
     selected_tab: Arc<AtomicUsize>,
+    text_sizing: bool,
+    projects: Projects,
 }
 
 // TODO: Use layouts and make this ugly
 
 impl Content {
     pub fn new(selected_tab: Arc<AtomicUsize>) -> Self {
-        Self { selected_tab, ..Default::default() }
+        Self {
+            selected_tab,
+            projects: Projects::new(Self::projects_content()),
+            ..Default::default()
+        }
     }
 
-    /// Generate the content for the "About" tab
-    fn about_content(&self, area: Rect) -> Result<Vec<Line<'static>>> {
+    /// Whether the details of a project are being shown.
+    pub fn is_viewing_project(&self) -> bool {
+        self.projects.is_open()
+    }
+
+    /// Generate the header for the "About" tab, either as figlet text or text scaled up to the
+    /// height of the figlet text if the terminal supports the text sizing protocol
+    fn about_header(&self) -> Result<(Vec<String>, Option<ScaledText<'static>>)> {
         let greetings_header =
             FIGfont::from_content(include_str!("../../assets/drpepper.flf"))
                 .map_err(|err| eyre!(err))?
-                .convert("hiya!")
+                .convert(GREETING)
                 .ok_or(eyre!("Failed to create figlet header for about page"))?
                 .to_string();
 
         let lines: Vec<String> =
             greetings_header.trim_end_matches('\n').split('\n').map(String::from).collect();
 
+        if !self.text_sizing {
+            return Ok((lines, None));
+        }
+
+        // Blank out the figlet text while keeping the layout of the lines beside it intact, the
+        // scaled text gets drawn over the blanked out area
+        let scaled = ScaledText::new(GREETING, lines.len() as u8);
+        let width = lines
+            .iter()
+            .map(|line| line.chars().count())
+            .max()
+            .unwrap_or_default()
+            .max(scaled.width().into());
+
+        Ok((vec![" ".repeat(width); lines.len()], Some(scaled)))
+    }
+
+    /// Generate the content for the "About" tab
+    fn about_content(&self, area: Rect, lines: Vec<String>) -> Result<Vec<Line<'static>>> {
         let mut content = lines
             .iter()
             .enumerate()
@@ -212,49 +249,139 @@ impl Content {
 
     /// Generate the content for the "Projects" tab
     #[rustfmt::skip]
-    fn projects_content(&self) -> Vec<Card<'static>> {
+    pub(crate) fn projects_content() -> Vec<Card<'static>> {
         vec![
-            Card { title: " 0x5eal/luau-unzip", description: "Unzip implementation in pure Luau" },
             Card {
-                title: " CompeyDev/discord-status-action",
+                repository: "0x5eal/luau-unzip",
+                description: "Unzip implementation in pure Luau",
+                details: "Reads and extracts zip archives using nothing but Luau, so it works anywhere Luau runs.",
+                stack: &["Luau"],
+                links: &[],
+                screenshot: None,
+            },
+            Card {
+                repository: "CompeyDev/discord-status-action",
                 description: "GitHub action to update your discord status in a file using the Lanyard API",
+                details: "Keeps a file in your repository up to date with your Discord status, as reported by the Lanyard API.",
+                stack: &["GitHub Actions", "Lanyard"],
+                links: &[],
+                screenshot: None,
+            },
+            Card {
+                repository: "CompeyDev/bad-apple-efi",
+                description: "An EFI application to play the silly video",
+                details: "Plays Bad Apple!! straight from the firmware, without booting into an operating system.",
+                stack: &["UEFI"],
+                links: &[],
+                screenshot: None,
+            },
+            Card {
+                repository: "CompeyDev/lei",
+                description: "🌸 A collection of Go bindings to Luau",
+                details: "Lets Go programs embed and run Luau.",
+                stack: &["Go", "Luau"],
+                links: &[],
+                screenshot: None,
             },
-            Card { title: " CompeyDev/bad-apple-efi", description: "An EFI application to play the silly video" },
-            Card { title: " CompeyDev/lei", description: "🌸 A collection of Go bindings to Luau" },
-            Card { title: " 0x5eal/wg-lua", description: "A Lua implementation of the wireguard keygen algorithm" },
-            Card { title: " 0x5eal/semver-luau", description: "Strongly typed semver parser for Luau" },
-            Card { title: " CompeyDev/elytra-lock-fabric", description: "Client-side fabric mod to lock elytra usage using a keybind" },
             Card {
-                title: " CompeyDev/touch-grass-reminder",
-                description: "Client-side quilt mod which warns players when they have been excessively playing Minecraft"
+                repository: "0x5eal/wg-lua",
+                description: "A Lua implementation of the wireguard keygen algorithm",
+                details: "Generates WireGuard keys in Lua.",
+                stack: &["Lua", "WireGuard"],
+                links: &[],
+                screenshot: None,
             },
             Card {
-                title: " CompeyDev/stinky-mod",
+                repository: "0x5eal/semver-luau",
+                description: "Strongly typed semver parser for Luau",
+                details: "Parses semantic versions, with types for everything.",
+                stack: &["Luau"],
+                links: &[],
+                screenshot: None,
+            },
+            Card {
+                repository: "CompeyDev/elytra-lock-fabric",
+                description: "Client-side fabric mod to lock elytra usage using a keybind",
+                details: "Keeps the elytra from being used until it is unlocked with a keybind.",
+                stack: &["Minecraft", "Fabric"],
+                links: &[],
+                screenshot: None,
+            },
+            Card {
+                repository: "CompeyDev/touch-grass-reminder",
+                description: "Client-side quilt mod which warns players when they have been excessively playing Minecraft",
+                details: "Warns you once you have been playing Minecraft for too long.",
+                stack: &["Minecraft", "Quilt"],
+                links: &[],
+                screenshot: None,
+            },
+            Card {
+                repository: "CompeyDev/stinky-mod",
                 description: "Server-side fabric mod featuring (mostly) customizable randomized join, leave, death, and MOTD messages",
+                details: "Picks random join, leave, death and MOTD messages, most of which can be customized.",
+                stack: &["Minecraft", "Fabric"],
+                links: &[],
+                screenshot: None,
+            },
+            Card {
+                repository: "CompeyDev/lune-luau-template",
+                description: "A simple template for initializing Luau projects with Lune",
+                details: "A starting point for new Luau projects which run on Lune.",
+                stack: &["Luau", "Lune"],
+                links: &[],
+                screenshot: None,
+            },
+            Card {
+                repository: "CompeyDev/frktest-pesde",
+                description: "A basic test framework for Lune (now with pesde support!)",
+                details: "A basic test framework for Lune, which can be installed with pesde.",
+                stack: &["Luau", "Lune", "pesde"],
+                links: &[],
+                screenshot: None,
+            },
+            Card {
+                repository: "CompeyDev/cull-less-leaves",
+                description: "1.21 release fork | Cull leaves while looking hot!",
+                details: "Culls leaves for better performance while keeping trees looking good, forked for Minecraft 1.21.",
+                stack: &["Minecraft", "Fabric"],
+                links: &[],
+                screenshot: None,
+            },
+            Card {
+                repository: "CompeyDev/setup-rokit",
+                description: "GitHub action to install and run rokit; a toolchain manager",
+                details: "Installs rokit in GitHub Actions workflows, and runs it to install a project's tools.",
+                stack: &["GitHub Actions", "rokit"],
+                links: &[],
+                screenshot: None,
+            },
+            Card {
+                repository: "CompeyDev/fxtwitter-docker",
+                description: "Dockerified fork of fxtwitter | Fix broken Twitter/X embeds!",
+                details: "A fork of fxtwitter which runs in Docker, fixing Twitter/X embeds.",
+                stack: &["Docker"],
+                links: &[],
+                screenshot: None,
             },
-            Card { title: " CompeyDev/lune-luau-template", description: "A simple template for initializing Luau projects with Lune" },
-            Card { title: " CompeyDev/frktest-pesde", description: "A basic test framework for Lune (now with pesde support!)" },
-            Card { title: " CompeyDev/cull-less-leaves", description: "1.21 release fork | Cull leaves while looking hot!" },
-            Card { title: " CompeyDev/setup-rokit", description: "GitHub action to install and run rokit; a toolchain manager" },
-            Card { title: " CompeyDev/fxtwitter-docker", description: "Dockerified fork of fxtwitter | Fix broken Twitter/X embeds!" },
         ]
     }
 
     /// Generate the content for the "Blog" tab
     #[cfg(feature = "blog")]
-    pub async fn blog_content(&self) -> Result<Vec<Post>> {
-        Ok(crate::atproto::blog::get_all_posts()
+    pub async fn blog_content() -> Result<Vec<Post>> {
+        Ok(crate::blog::SOURCE
+            .posts()
             .await?
-            .iter()
-            .map(|post| Arc::new(post.clone()))
+            .into_iter()
+            .map(|post| Arc::new(post.record))
             .collect())
     }
 }
 
 impl Component for Content {
     fn register_action_handler(&mut self, tx: UnboundedSender<Action>) -> Result<()> {
-        self.command_tx = Some(tx);
-        Ok(())
+        self.command_tx = Some(tx.clone());
+        self.projects.register_action_handler(tx)
     }
 
     fn register_config_handler(&mut self, config: Config) -> Result<()> {
@@ -262,7 +389,27 @@ impl Component for Content {
         Ok(())
     }
 
+    fn init(&mut self, term_info: Arc<RwLock<TerminalInfo>>, size: Size) -> Result<()> {
+        self.text_sizing = term_info.blocking_read().supports_text_sizing();
+        self.projects.init(term_info, size)
+    }
+
+    fn handle_mouse_event(&mut self, mouse: MouseEvent) -> Result<Option<Action>> {
+        match self.selected_tab.load(Ordering::Relaxed) {
+            1 => self.projects.handle_mouse_event(mouse),
+            _ => Ok(None),
+        }
+    }
+
     fn update(&mut self, action: Action) -> Result<Option<Action>> {
+        // Projects can only be interacted with while they're shown, but switching tabs is what
+        // closes them, by which point the tab was already switched
+        if self.selected_tab.load(Ordering::Relaxed) == 1
+            || matches!(action, Action::PrevTab | Action::NextTab | Action::SelectTab(_))
+        {
+            return self.projects.update(action);
+        }
+
         match action {
             Action::Tick => {}
             Action::Render => {}
@@ -284,7 +431,7 @@ impl Component for Content {
             Style::default().fg(Color::DarkGray),
         ));
 
-        let tabs = ["about", "projects", "blog"];
+        let tabs = ["about", "projects", "blog", "guestbook"];
         let mut current_pos = 1 + devcomp_width;
 
         for (i, &tab) in tabs.iter().enumerate() {
@@ -329,12 +476,27 @@ impl Component for Content {
         };
 
         if selected_tab == 0 {
-            let widget = Paragraph::new(self.about_content(area)?)
+            let (header_lines, scaled_header) = self.about_header()?;
+            let header_height = header_lines.len() as u16;
+            let widget = Paragraph::new(self.about_content(area, header_lines)?)
                 .block(Block::default().borders(Borders::NONE))
                 .wrap(Wrap { trim: false });
             frame.render_widget(widget, content_area);
+
+            if let Some(header) = scaled_header {
+                // Account for the leading space before each line of the header
+                frame.render_widget(
+                    header,
+                    Rect {
+                        x: content_area.x + 1,
+                        y: content_area.y,
+                        width: content_area.width.saturating_sub(1),
+                        height: header_height,
+                    },
+                );
+            }
         } else if selected_tab == 1 {
-            self.projects_content().draw(frame, content_area)?;
+            self.projects.draw(frame, content_area)?;
         } // FIXME: Blog tab handled in `App::render`
 
         // Render the borders
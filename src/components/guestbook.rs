@@ -0,0 +1,463 @@
+use std::sync::Arc;
+
+use color_eyre::Result;
+use ratatui::prelude::*;
+use ratatui::widgets::*;
+use tokio::sync::RwLock;
+
+use super::{Component, TextInput};
+use crate::action::Action;
+use crate::app::Mode;
+use crate::config::{key_event_to_string, Config};
+use crate::guestbook::{Entry, Guestbook, Rejection, MAX_MESSAGE_LENGTH, MAX_NAME_LENGTH};
+use crate::ssh::Visitor;
+use crate::tui::terminal::TerminalInfo;
+use crate::tui::Event;
+
+/// The field being typed into while signing the guestbook.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Field {
+    Name,
+    Message,
+}
+
+/// An entry being written, which is only signed once the message is confirmed.
+#[derive(Debug)]
+struct Draft {
+    field: Field,
+    name: TextInput,
+    message: TextInput,
+}
+
+impl Draft {
+    fn new(name: &str) -> Self {
+        let mut draft = Self {
+            field: Field::Name,
+            name: TextInput::new().max_length(MAX_NAME_LENGTH),
+            message: TextInput::new().max_length(MAX_MESSAGE_LENGTH),
+        };
+
+        draft.name.paste(name);
+        draft
+    }
+
+    /// The field being typed into.
+    fn field_mut(&mut self) -> &mut TextInput {
+        match self.field {
+            Field::Name => &mut self.name,
+            Field::Message => &mut self.message,
+        }
+    }
+}
+
+/// The entries of the guestbook, newest first, along with a form for signing it.
+#[derive(Default)]
+pub struct GuestbookEntries {
+    config: Config,
+    guestbook: Option<Arc<Guestbook>>,
+    visitor: Arc<RwLock<Visitor>>,
+    entries: Vec<Entry>,
+    selected: usize,
+    /// The first entry shown, which follows the selection once it goes out of view.
+    offset: usize,
+    draft: Option<Draft>,
+    /// The outcome of the last attempt at signing, and whether it went through.
+    status: Option<(String, bool)>,
+}
+
+impl GuestbookEntries {
+    /// Sets the guestbook to show the entries of, there are none to show without one.
+    pub fn set_guestbook(&mut self, guestbook: Option<Arc<Guestbook>>) {
+        self.guestbook = guestbook;
+    }
+
+    /// Sets who is signing the guestbook, which is only known once they authenticated.
+    pub fn set_visitor(&mut self, visitor: Arc<RwLock<Visitor>>) {
+        self.visitor = visitor;
+    }
+
+    /// Whether an entry is being written.
+    pub fn is_composing(&self) -> bool {
+        self.draft.is_some()
+    }
+
+    fn refresh(&mut self) {
+        let Some(guestbook) = &self.guestbook else {
+            return;
+        };
+
+        match guestbook.entries(false) {
+            Ok(entries) => self.entries = entries,
+            Err(err) => tracing::error!("Failed to read guestbook: {err}"),
+        }
+
+        self.selected = self.selected.min(self.entries.len().saturating_sub(1));
+    }
+
+    fn start_draft(&mut self) {
+        let visitor = self.visitor.blocking_read();
+        self.status = match (&self.guestbook, &visitor.fingerprint) {
+            (None, _) => Some((String::from("the guestbook is unavailable right now"), false)),
+            (_, None) => Some((Rejection::Anonymous.to_string(), false)),
+            (Some(_), Some(_)) => {
+                // The username is a good guess at a name, but it can be changed
+                self.draft = Some(Draft::new(&visitor.user));
+                None
+            }
+        };
+    }
+
+    fn confirm_draft(&mut self) {
+        let (Some(draft), Some(guestbook)) = (&mut self.draft, &self.guestbook) else {
+            return;
+        };
+
+        if draft.field == Field::Name {
+            if draft.name.value().trim().is_empty() {
+                self.status = Some((Rejection::EmptyName.to_string(), false));
+            } else {
+                draft.field = Field::Message;
+                self.status = None;
+            }
+
+            return;
+        }
+
+        let fingerprint = self.visitor.blocking_read().fingerprint.clone();
+        let (name, message) = (draft.name.value(), draft.message.value());
+        match guestbook.sign(fingerprint.as_deref(), name, message) {
+            Ok(_) => {
+                self.draft = None;
+                self.status = Some((String::from("signed, thanks for stopping by!"), true));
+                self.selected = 0;
+                self.refresh();
+            }
+            // The draft is kept around, so that it can be fixed up
+            Err(err) => {
+                let message = match err.downcast_ref::<Rejection>() {
+                    Some(rejection) => rejection.to_string(),
+                    None => {
+                        tracing::error!("Failed to sign guestbook: {err}");
+                        String::from("something went wrong, try again in a bit")
+                    }
+                };
+
+                self.status = Some((message, false));
+            }
+        }
+    }
+
+    fn key_hint(&self, action: &Action) -> Option<String> {
+        let keymap = self.config.keybindings.get(&Mode::Home)?;
+        let keys = keymap.iter().filter(|(keys, bound)| keys.len() == 1 && *bound == action);
+        keys.map(|(keys, _)| key_event_to_string(&keys[0])).min()
+    }
+
+    /// Renders the number of entries, and how to sign the guestbook, above the entries.
+    fn draw_header(&self, frame: &mut Frame, area: Rect) {
+        let accent_style =
+            Style::default().add_modifier(Modifier::BOLD).fg(Color::LightMagenta);
+        let dim_style = Style::default().add_modifier(Modifier::DIM);
+
+        let entries = match self.entries.len() {
+            1 => String::from(" · 1 entry"),
+            n => format!(" · {n} entries"),
+        };
+        let hint =
+            self.key_hint(&Action::Sign).map_or_else(String::new, |key| format!(" ({key})"));
+
+        let line = Line::from(vec![
+            Span::raw(format!("{:>3}", " ")),
+            Span::styled("guestbook", accent_style),
+            Span::styled(entries, dim_style),
+            Span::styled(" · sign it", dim_style),
+            Span::styled(hint, dim_style),
+        ]);
+
+        frame.render_widget(line, area);
+    }
+
+    /// Renders the entries, scrolled just far enough for the selected one to be in view.
+    fn draw_entries(&mut self, frame: &mut Frame, area: Rect) {
+        let dim_style = Style::default().add_modifier(Modifier::DIM);
+        if self.entries.is_empty() {
+            let message = match self.guestbook {
+                Some(_) => "no entries yet, be the first to sign!",
+                None => "the guestbook is unavailable right now",
+            };
+
+            frame.render_widget(Line::styled(format!("   {message}"), dim_style), area);
+            return;
+        }
+
+        // Messages are indented to line up with the names, and followed by a blank row
+        let width = area.width.saturating_sub(3);
+        let messages = self
+            .entries
+            .iter()
+            .map(|entry| Paragraph::new(entry.message.as_str()).wrap(Wrap { trim: true }))
+            .collect::<Vec<_>>();
+        let heights = messages
+            .iter()
+            .map(|message| message.line_count(width) as u16 + 2)
+            .collect::<Vec<_>>();
+
+        self.offset = self.offset.min(self.selected);
+        while self.offset < self.selected
+            && heights[self.offset..=self.selected].iter().sum::<u16>() > area.height
+        {
+            self.offset += 1;
+        }
+
+        let mut y = area.y;
+        let rows = self.entries.iter().zip(messages).zip(heights).enumerate();
+        for (i, ((entry, message), height)) in rows.skip(self.offset) {
+            if y >= area.bottom() {
+                break;
+            }
+
+            let (marker, name_style) = if i == self.selected {
+                ("▶  ", Style::default().add_modifier(Modifier::BOLD).fg(Color::LightMagenta))
+            } else {
+                ("   ", Style::default().add_modifier(Modifier::BOLD))
+            };
+
+            let header = Line::from(vec![
+                Span::styled(marker, name_style),
+                Span::styled(entry.name.as_str(), name_style),
+                Span::styled(format!(" · {}", entry.date), dim_style),
+                Span::styled(
+                    format!(" · {}", short_fingerprint(&entry.fingerprint)),
+                    dim_style,
+                ),
+            ]);
+            frame.render_widget(header, Rect::new(area.x, y, area.width, 1));
+
+            let message_height = height.saturating_sub(2).min(area.bottom() - y - 1);
+            frame.render_widget(message, Rect::new(area.x + 3, y + 1, width, message_height));
+            y = y.saturating_add(height);
+        }
+    }
+
+    /// The fields of the entry being written, as shown in the box at the bottom.
+    fn draft_lines<'a>(draft: &'a Draft) -> Vec<Line<'a>> {
+        let accent_style =
+            Style::default().add_modifier(Modifier::BOLD).fg(Color::LightMagenta);
+        let dim_style = Style::default().add_modifier(Modifier::DIM);
+
+        let field = |label: &'static str, input: &'a TextInput, focused: bool| {
+            let mut spans = vec![Span::styled(
+                format!("{label:<9}"),
+                if focused { accent_style } else { dim_style },
+            )];
+            spans.extend(input.spans(focused));
+
+            Line::from(spans)
+        };
+
+        vec![
+            field("name", &draft.name, draft.field == Field::Name),
+            field("message", &draft.message, draft.field == Field::Message),
+        ]
+    }
+
+    /// Renders the entry being written, in a box at the bottom.
+    fn draw_draft(&self, frame: &mut Frame, area: Rect, draft: &Draft) {
+        let accent_style =
+            Style::default().add_modifier(Modifier::BOLD).fg(Color::LightMagenta);
+        let dim_style = Style::default().add_modifier(Modifier::DIM);
+        let (hint, count) = match draft.field {
+            Field::Name => (
+                " enter to continue · esc to cancel ",
+                format!(" {}/{MAX_NAME_LENGTH} ", draft.name.len()),
+            ),
+            Field::Message => (
+                " enter to sign · esc to cancel ",
+                format!(" {}/{MAX_MESSAGE_LENGTH} ", draft.message.len()),
+            ),
+        };
+
+        let fingerprint = self.visitor.blocking_read().fingerprint.clone().unwrap_or_default();
+        let block = Block::bordered()
+            .border_type(BorderType::Rounded)
+            .border_style(accent_style)
+            .title(format!(" signing as {} ", short_fingerprint(&fingerprint)))
+            .title_bottom(Line::styled(hint, dim_style))
+            .title_bottom(Line::styled(count, dim_style).right_aligned())
+            .padding(Padding::horizontal(1));
+
+        let paragraph = Paragraph::new(Self::draft_lines(draft)).wrap(Wrap { trim: false });
+        frame.render_widget(Clear, area);
+        frame.render_widget(paragraph.block(block), area);
+    }
+}
+
+/// Enough of a fingerprint to tell keys apart at a glance.
+fn short_fingerprint(fingerprint: &str) -> &str {
+    let end = fingerprint
+        .char_indices()
+        .nth("SHA256:".len() + 8)
+        .map_or(fingerprint.len(), |(i, _)| i);
+    &fingerprint[..end]
+}
+
+impl Component for GuestbookEntries {
+    fn register_config_handler(&mut self, config: Config) -> Result<()> {
+        self.config = config;
+        Ok(())
+    }
+
+    fn init(&mut self, _: Arc<RwLock<TerminalInfo>>, _: Size) -> Result<()> {
+        self.refresh();
+        Ok(())
+    }
+
+    fn is_focused(&self) -> bool {
+        self.draft.is_some()
+    }
+
+    fn handle_focused_event(&mut self, event: &Event) -> Result<bool> {
+        let Some(draft) = &mut self.draft else {
+            return Ok(false);
+        };
+
+        Ok(match event {
+            Event::Key(key) => draft.field_mut().handle_key(key),
+            Event::Paste(text) => {
+                draft.field_mut().paste(text);
+                true
+            }
+            _ => false,
+        })
+    }
+
+    fn update(&mut self, action: Action) -> Result<Option<Action>> {
+        match action {
+            Action::Sign if self.draft.is_none() => self.start_draft(),
+            Action::ConfirmCompose => self.confirm_draft(),
+            Action::NextField => {
+                if let Some(draft) = &mut self.draft {
+                    draft.field = match draft.field {
+                        Field::Name => Field::Message,
+                        Field::Message => Field::Name,
+                    };
+                }
+            }
+            Action::CancelCompose => {
+                self.draft = None;
+                self.status = None;
+            }
+            Action::SelectNext if self.draft.is_none() => {
+                self.selected = (self.selected + 1).min(self.entries.len().saturating_sub(1))
+            }
+            Action::SelectPrev if self.draft.is_none() => {
+                self.selected = self.selected.saturating_sub(1)
+            }
+            _ => {}
+        }
+
+        Ok(None)
+    }
+
+    fn draw(&mut self, frame: &mut Frame, area: Rect) -> Result<()> {
+        let status_height = u16::from(self.status.is_some());
+        // The draft is boxed, with the message wrapping as it gets longer
+        let draft_height = self.draft.as_ref().map_or(0, |draft| {
+            let paragraph =
+                Paragraph::new(Self::draft_lines(draft)).wrap(Wrap { trim: false });
+            paragraph.line_count(area.width.saturating_sub(4)) as u16 + 2
+        });
+        let [header_area, entries_area, status_area, draft_area] = Layout::vertical([
+            Constraint::Length(2),
+            Constraint::Min(0),
+            Constraint::Length(status_height),
+            Constraint::Length(draft_height),
+        ])
+        .areas(area);
+
+        self.draw_header(frame, header_area);
+        self.draw_entries(frame, entries_area);
+
+        if let Some((status, ok)) = &self.status {
+            let color = if *ok { Color::Green } else { Color::Red };
+            frame.render_widget(
+                Line::styled(format!("   {status}"), Style::default().fg(color)),
+                status_area,
+            );
+        }
+
+        if let Some(draft) = &self.draft {
+            self.draw_draft(frame, draft_area, draft);
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crossterm::event::KeyCode;
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::harness::{dispatch, press, render, type_text};
+
+    #[test]
+    fn test_short_fingerprint() {
+        assert_eq!(short_fingerprint("SHA256:abcdefghijklmnop"), "SHA256:abcdefgh");
+        assert_eq!(short_fingerprint("SHA256:abc"), "SHA256:abc");
+    }
+
+    #[test]
+    fn test_signing() -> Result<()> {
+        let mut entries = GuestbookEntries::default();
+        entries.register_config_handler(Config::new()?)?;
+        entries.set_guestbook(Some(Arc::new(Guestbook::open_in_memory()?)));
+        entries.set_visitor(Arc::new(RwLock::new(Visitor {
+            user: String::from("visitor"),
+            fingerprint: Some(String::from("SHA256:visitorkey")),
+            address: None,
+        })));
+        entries.init(Arc::default(), Size::default())?;
+
+        let screen = render(&mut entries, 100, 20)?;
+        assert!(screen.contains("guestbook · 0 entries · sign it (w)"));
+        assert!(screen.contains("no entries yet, be the first to sign!"));
+
+        // The name starts out as the username
+        dispatch(&mut entries, [Action::Sign])?;
+        assert!(entries.is_focused());
+        let screen = render(&mut entries, 100, 20)?;
+        assert!(screen.contains("signing as SHA256:visitork"));
+        assert!(screen.contains("name     visitor█"));
+        press(&mut entries, &[KeyCode::Backspace; 4])?;
+        type_text(&mut entries, "q")?;
+        dispatch(&mut entries, [Action::ConfirmCompose])?;
+        type_text(&mut entries, "hi!")?;
+        dispatch(&mut entries, [Action::ConfirmCompose])?;
+        let screen = render(&mut entries, 100, 20)?;
+        assert!(screen.contains("▶  visq ·") && screen.contains("hi!"));
+        assert!(screen.contains("signed, thanks for stopping by!"));
+
+        // Rejected entries are kept around to be fixed up
+        dispatch(&mut entries, [Action::Sign, Action::ConfirmCompose])?;
+        type_text(&mut entries, "sh1t")?;
+        dispatch(&mut entries, [Action::ConfirmCompose])?;
+        let screen = render(&mut entries, 100, 20)?;
+        assert!(
+            screen.contains("let's keep it friendly") && screen.contains("message  sh1t█")
+        );
+        press(&mut entries, &[KeyCode::Backspace; 4])?;
+        type_text(&mut entries, "yo")?;
+        dispatch(&mut entries, [Action::ConfirmCompose])?;
+        let screen = render(&mut entries, 100, 20)?;
+        assert!(screen.contains("slow down! try again in 10 minute(s)"));
+
+        dispatch(&mut entries, [Action::CancelCompose])?;
+        let screen = render(&mut entries, 100, 20)?;
+        assert!(screen.contains("guestbook · 1 entry") && !screen.contains("slow down"));
+        assert_eq!(entries.entries.len(), 1);
+
+        Ok(())
+    }
+}
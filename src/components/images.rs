@@ -0,0 +1,50 @@
+use std::io::{BufReader, Cursor};
+
+use color_eyre::Result;
+use image::ImageReader;
+use ratatui_image::picker::Picker;
+use ratatui_image::protocol::StatefulProtocol;
+
+use crate::tui::terminal::{TerminalInfo, TerminalKind, UnsupportedReason};
+use crate::tui::terminfo::{Glyphs, Multiplexer};
+
+/// Creates a picker for drawing images in the client's terminal, using the best graphics
+/// protocol it supports, or nothing if it can't display images at all.
+pub fn image_picker(info: &TerminalInfo) -> Option<Picker> {
+    // Consoles with limited glyphs can't even display halfblocks properly, so we show the
+    // image URLs instead
+    if matches!(info.kind(), TerminalKind::Unsupported(UnsupportedReason::Unsized))
+        || info.glyphs() == Glyphs::Limited
+    {
+        return None;
+    }
+
+    let picker = Picker {
+        capabilities: info.capabilities(),
+        protocol_type: info.protocol_type(),
+        font_size: info.font_size(),
+        background_color: image::Rgba([0, 0, 0, 0]),
+        // Image sequences need to be wrapped in tmux's passthrough sequence to reach the
+        // terminal, which `ratatui_image` takes care of
+        is_tmux: info.multiplexer() == Some(Multiplexer::Tmux),
+    };
+
+    tracing::info!(
+        "Using {:?} rendering protocol for image renderer, font size: {:?}, tmux: {}",
+        picker.protocol_type(),
+        picker.font_size(),
+        picker.is_tmux,
+    );
+
+    Some(picker)
+}
+
+/// Fetches the image at the given URL, resized to fit wherever it gets drawn.
+pub async fn fetch_image(picker: &Picker, url: &str) -> Result<StatefulProtocol> {
+    let img_blob = reqwest::get(url).await?.bytes().await?;
+    let dyn_img = ImageReader::new(BufReader::new(Cursor::new(img_blob)))
+        .with_guessed_format()?
+        .decode()?;
+
+    Ok(picker.new_resize_protocol(dyn_img))
+}
@@ -0,0 +1,437 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use ratatui::prelude::*;
+use ratatui::widgets::Paragraph;
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+/// How many previous entries are remembered by an input.
+const HISTORY_LENGTH: usize = 50;
+
+/// Text being typed, on a single line or wrapping over several, with a cursor which moves
+/// over whole graphemes so that accented characters and emoji are edited as one.
+///
+/// Besides typing and moving the cursor around, the usual line editing shortcuts work:
+/// `Ctrl-a`/`Ctrl-e` for the start and end of the line, `Ctrl-u`/`Ctrl-k` to delete before
+/// and after the cursor, `Ctrl-w` to delete a word, and `Ctrl-p`/`Ctrl-n` to go through
+/// previous entries.
+#[derive(Debug, Default, Clone)]
+pub struct TextInput {
+    value: String,
+    /// Byte offset of the cursor, which is always on a grapheme boundary.
+    cursor: usize,
+    multiline: bool,
+    /// How many characters may be typed, if limited.
+    max_length: Option<usize>,
+    history: Vec<String>,
+    /// The history entry being shown, along with what was typed before going through them.
+    browsing: Option<(usize, String)>,
+}
+
+// Not every part of the input is used by every combination of features
+#[allow(dead_code)]
+impl TextInput {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Lets enter start a new line, and the up and down arrows move between lines.
+    pub fn multiline(mut self, multiline: bool) -> Self {
+        self.multiline = multiline;
+        self
+    }
+
+    /// Limits how many characters may be typed or pasted.
+    pub fn max_length(mut self, max_length: usize) -> Self {
+        self.max_length = Some(max_length);
+        self
+    }
+
+    pub fn value(&self) -> &str {
+        &self.value
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.value.is_empty()
+    }
+
+    /// How many characters have been typed, which is what the maximum length counts.
+    pub fn len(&self) -> usize {
+        self.value.chars().count()
+    }
+
+    /// Replaces the text, moving the cursor to its end.
+    pub fn set_value(&mut self, value: impl Into<String>) {
+        self.value = value.into();
+        self.cursor = self.value.len();
+        self.browsing = None;
+    }
+
+    pub fn clear(&mut self) {
+        self.set_value(String::new());
+    }
+
+    /// Remembers the text, so that it can be brought back with `Ctrl-p` later on.
+    pub fn push_history(&mut self) {
+        self.browsing = None;
+        if self.value.trim().is_empty() || self.history.last() == Some(&self.value) {
+            return;
+        }
+
+        self.history.push(self.value.clone());
+        if self.history.len() > HISTORY_LENGTH {
+            self.history.remove(0);
+        }
+    }
+
+    /// Edits the text or moves the cursor as the key says.
+    ///
+    /// # Returns
+    ///
+    /// * `bool` - Whether the key was used, keys which weren't are left for the keybindings.
+    pub fn handle_key(&mut self, key: &KeyEvent) -> bool {
+        if key.modifiers.contains(KeyModifiers::ALT) {
+            return false;
+        }
+
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        match key.code {
+            KeyCode::Char(c) if !ctrl => self.insert(&c.to_string()),
+            KeyCode::Char('a') => self.cursor = self.line_start(),
+            KeyCode::Char('e') => self.cursor = self.line_end(),
+            KeyCode::Char('u') => self.delete(self.line_start(), self.cursor),
+            KeyCode::Char('k') => self.delete(self.cursor, self.line_end()),
+            KeyCode::Char('w') => self.delete(self.word_start(), self.cursor),
+            KeyCode::Char('p') => self.browse_history(true),
+            KeyCode::Char('n') => self.browse_history(false),
+            KeyCode::Backspace => self.delete(self.prev_boundary(), self.cursor),
+            KeyCode::Delete => self.delete(self.cursor, self.next_boundary()),
+            KeyCode::Left => self.cursor = self.prev_boundary(),
+            KeyCode::Right => self.cursor = self.next_boundary(),
+            KeyCode::Home => self.cursor = self.line_start(),
+            KeyCode::End => self.cursor = self.line_end(),
+            KeyCode::Up if self.multiline => self.move_line(false),
+            KeyCode::Down if self.multiline => self.move_line(true),
+            KeyCode::Enter if self.multiline => self.insert("\n"),
+            _ => return false,
+        }
+
+        true
+    }
+
+    /// Inserts pasted text at the cursor, as much of it as fits. Line breaks become spaces
+    /// unless the input is multiline, and any other control characters are left out.
+    pub fn paste(&mut self, text: &str) {
+        let text = text
+            .replace("\r\n", "\n")
+            .chars()
+            .map(|c| match c {
+                '\r' | '\n' if self.multiline => '\n',
+                '\r' | '\n' | '\t' => ' ',
+                c => c,
+            })
+            .filter(|&c| c == '\n' || !c.is_control())
+            .collect::<String>();
+
+        self.insert(&text);
+    }
+
+    fn insert(&mut self, text: &str) {
+        let room = self.max_length.map_or(usize::MAX, |max| max.saturating_sub(self.len()));
+        let end = text.char_indices().nth(room).map_or(text.len(), |(i, _)| i);
+
+        self.value.insert_str(self.cursor, &text[..end]);
+        self.cursor += end;
+        self.browsing = None;
+    }
+
+    fn delete(&mut self, start: usize, end: usize) {
+        self.value.replace_range(start..end, "");
+        self.cursor = start;
+        self.browsing = None;
+    }
+
+    fn prev_boundary(&self) -> usize {
+        let before = &self.value[..self.cursor];
+        before.grapheme_indices(true).next_back().map_or(0, |(i, _)| i)
+    }
+
+    fn next_boundary(&self) -> usize {
+        let after = &self.value[self.cursor..];
+        self.cursor + after.graphemes(true).next().map_or(0, str::len)
+    }
+
+    fn line_start(&self) -> usize {
+        self.value[..self.cursor].rfind('\n').map_or(0, |i| i + 1)
+    }
+
+    fn line_end(&self) -> usize {
+        let after = &self.value[self.cursor..];
+        self.cursor + after.find('\n').unwrap_or(after.len())
+    }
+
+    /// Where the word before the cursor starts, skipping any whitespace right before it.
+    fn word_start(&self) -> usize {
+        let before = self.value[..self.cursor].trim_end();
+        before.trim_end_matches(|c: char| !c.is_whitespace()).len()
+    }
+
+    /// Moves the cursor to the line above or below, keeping it in the same column where
+    /// possible.
+    fn move_line(&mut self, down: bool) {
+        let start = self.line_start();
+        let column = self.value[start..self.cursor].width();
+
+        let target = if down {
+            match self.value[self.cursor..].find('\n') {
+                Some(i) => self.cursor + i + 1,
+                None => return,
+            }
+        } else if start == 0 {
+            return;
+        } else {
+            self.value[..start - 1].rfind('\n').map_or(0, |i| i + 1)
+        };
+
+        let line = self.value[target..].split('\n').next().unwrap_or_default();
+        let (mut offset, mut width) = (0, 0);
+        for grapheme in line.graphemes(true) {
+            width += grapheme.width();
+            if width > column {
+                break;
+            }
+            offset += grapheme.len();
+        }
+
+        self.cursor = target + offset;
+    }
+
+    fn browse_history(&mut self, older: bool) {
+        let index = match (&self.browsing, older) {
+            (None, true) => self.history.len().checked_sub(1),
+            (None, false) => return,
+            (Some((index, _)), true) => Some(index.saturating_sub(1)),
+            (Some((index, _)), false) => Some(index + 1).filter(|&i| i < self.history.len()),
+        };
+
+        // Going past the newest entry brings back what was being typed
+        let browsing = self.browsing.take();
+        match index {
+            Some(index) => {
+                let draft = browsing.map_or_else(|| self.value.clone(), |(_, draft)| draft);
+                self.value = self.history[index].clone();
+                self.browsing = Some((index, draft));
+            }
+            None => self.value = browsing.map(|(_, draft)| draft).unwrap_or_default(),
+        }
+
+        self.cursor = self.value.len();
+    }
+
+    /// The text with one span per grapheme, so that it can be laid out around the cursor.
+    /// Line breaks are kept as spans of their own.
+    fn cells(&self, focused: bool) -> Vec<Span<'_>> {
+        let mut cells = Vec::new();
+        for (i, grapheme) in self.value.grapheme_indices(true) {
+            let on_cursor = focused && i == self.cursor;
+            if grapheme == "\n" {
+                if on_cursor {
+                    cells.push(cursor());
+                }
+                cells.push(Span::raw(grapheme));
+            } else if on_cursor {
+                cells.push(Span::styled(
+                    grapheme,
+                    Style::new().add_modifier(Modifier::REVERSED),
+                ));
+            } else {
+                cells.push(Span::raw(grapheme));
+            }
+        }
+
+        if focused && self.cursor == self.value.len() {
+            cells.push(cursor());
+        }
+
+        cells
+    }
+
+    /// The text on a single line with the cursor shown, if focused, for placing into a line
+    /// of its own.
+    pub fn spans(&self, focused: bool) -> Vec<Span<'_>> {
+        let mut spans = self.cells(focused);
+        for span in &mut spans {
+            if span.content == "\n" {
+                span.content = " ".into();
+            }
+        }
+
+        spans
+    }
+
+    /// Draws the text, scrolled to keep the cursor in view. Multiline inputs wrap at the
+    /// edge of the area, while single line ones scroll sideways.
+    pub fn render(&self, frame: &mut Frame, area: Rect, focused: bool) {
+        if area.is_empty() {
+            return;
+        }
+
+        if !self.multiline {
+            let spans = self.spans(focused);
+            let cursor_column = match spans.iter().position(|span| span.style != Style::new())
+            {
+                Some(position) => spans[..=position].iter().map(Span::width).sum(),
+                None => 0,
+            };
+
+            // Drops graphemes from the start until the cursor fits
+            let overflow = cursor_column.saturating_sub(area.width as usize);
+            let (mut skipped, mut start) = (0, 0);
+            while skipped < overflow && start < spans.len() {
+                skipped += spans[start].width();
+                start += 1;
+            }
+
+            let line = Line::from(spans.into_iter().skip(start).collect::<Vec<_>>());
+            frame.render_widget(line, area);
+            return;
+        }
+
+        let width = area.width as usize;
+        let (mut rows, mut row, mut row_width) = (Vec::new(), Vec::new(), 0);
+        let mut cursor_row = 0;
+        for cell in self.cells(focused) {
+            if cell.content == "\n" {
+                rows.push(Line::from(std::mem::take(&mut row)));
+                row_width = 0;
+                continue;
+            }
+
+            let cell_width = cell.width();
+            if row_width + cell_width > width && row_width > 0 {
+                rows.push(Line::from(std::mem::take(&mut row)));
+                row_width = 0;
+            }
+            if cell.style != Style::new() {
+                cursor_row = rows.len();
+            }
+
+            row_width += cell_width;
+            row.push(cell);
+        }
+        rows.push(Line::from(row));
+
+        let scroll = (cursor_row + 1).saturating_sub(area.height as usize) as u16;
+        frame.render_widget(Paragraph::new(rows).scroll((scroll, 0)), area);
+    }
+}
+
+fn cursor() -> Span<'static> {
+    Span::styled("█", Style::new().add_modifier(Modifier::SLOW_BLINK))
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use ratatui::backend::TestBackend;
+    use ratatui::Terminal;
+
+    use super::*;
+
+    fn key(code: KeyCode, modifiers: KeyModifiers) -> KeyEvent {
+        KeyEvent::new(code, modifiers)
+    }
+
+    fn type_str(input: &mut TextInput, text: &str) {
+        for c in text.chars() {
+            assert!(input.handle_key(&key(KeyCode::Char(c), KeyModifiers::empty())));
+        }
+    }
+
+    #[test]
+    fn test_editing() {
+        let mut input = TextInput::new().max_length(12);
+        type_str(&mut input, "hello wörld");
+        assert_eq!(input.value(), "hello wörld");
+
+        // Typing in the middle, past the limit
+        input.handle_key(&key(KeyCode::Left, KeyModifiers::empty()));
+        input.handle_key(&key(KeyCode::Left, KeyModifiers::empty()));
+        type_str(&mut input, "xy");
+        assert_eq!(input.value(), "hello wörxld");
+
+        input.handle_key(&key(KeyCode::Char('w'), KeyModifiers::CONTROL));
+        assert_eq!(input.value(), "hello ld");
+        input.handle_key(&key(KeyCode::Char('k'), KeyModifiers::CONTROL));
+        assert_eq!(input.value(), "hello ");
+        input.handle_key(&key(KeyCode::Home, KeyModifiers::empty()));
+        input.handle_key(&key(KeyCode::Delete, KeyModifiers::empty()));
+        assert_eq!(input.value(), "ello ");
+
+        // Graphemes are deleted as a whole
+        input.set_value("e\u{301}🏳️‍🌈");
+        input.handle_key(&key(KeyCode::Backspace, KeyModifiers::empty()));
+        assert_eq!(input.value(), "e\u{301}");
+        input.handle_key(&key(KeyCode::Backspace, KeyModifiers::empty()));
+        assert_eq!(input.value(), "");
+
+        // Keys meant for the keybindings are left alone
+        assert!(!input.handle_key(&key(KeyCode::Enter, KeyModifiers::empty())));
+        assert!(!input.handle_key(&key(KeyCode::Esc, KeyModifiers::empty())));
+        assert!(!input.handle_key(&key(KeyCode::Up, KeyModifiers::empty())));
+        assert!(!input.handle_key(&key(KeyCode::Char('c'), KeyModifiers::CONTROL)));
+    }
+
+    #[test]
+    fn test_multiline() {
+        let mut input = TextInput::new().multiline(true);
+        type_str(&mut input, "first");
+        input.handle_key(&key(KeyCode::Enter, KeyModifiers::empty()));
+        type_str(&mut input, "2nd");
+        input.handle_key(&key(KeyCode::Up, KeyModifiers::empty()));
+        type_str(&mut input, "!");
+        assert_eq!(input.value(), "fir!st\n2nd");
+
+        input.paste("a\r\nb\tc\x07");
+        assert_eq!(input.value(), "fir!a\nb cst\n2nd");
+
+        let mut input = TextInput::new();
+        input.paste("a\r\nb");
+        assert_eq!(input.value(), "a b");
+    }
+
+    #[test]
+    fn test_history() {
+        let mut input = TextInput::new();
+        for entry in ["first", "second"] {
+            input.set_value(entry);
+            input.push_history();
+        }
+
+        input.set_value("draft");
+        input.handle_key(&key(KeyCode::Char('p'), KeyModifiers::CONTROL));
+        assert_eq!(input.value(), "second");
+        input.handle_key(&key(KeyCode::Char('p'), KeyModifiers::CONTROL));
+        input.handle_key(&key(KeyCode::Char('p'), KeyModifiers::CONTROL));
+        assert_eq!(input.value(), "first");
+        input.handle_key(&key(KeyCode::Char('n'), KeyModifiers::CONTROL));
+        input.handle_key(&key(KeyCode::Char('n'), KeyModifiers::CONTROL));
+        assert_eq!(input.value(), "draft");
+    }
+
+    #[test]
+    fn test_render_scrolls_to_cursor() {
+        let mut terminal = Terminal::new(TestBackend::new(6, 2)).unwrap();
+
+        let mut input = TextInput::new();
+        input.set_value("日本語のテキスト");
+        terminal.draw(|frame| input.render(frame, Rect::new(0, 0, 6, 1), true)).unwrap();
+        assert_eq!(terminal.backend().buffer().content[2].symbol(), "ト");
+        assert_eq!(terminal.backend().buffer().content[4].symbol(), "█");
+
+        let mut input = TextInput::new().multiline(true);
+        input.set_value("one\ntwo\nthree");
+        terminal.draw(|frame| input.render(frame, Rect::new(0, 0, 6, 2), true)).unwrap();
+        let symbols = terminal.backend().buffer().content.iter().map(|cell| cell.symbol());
+        assert_eq!(symbols.collect::<String>(), "two   three█");
+    }
+}
@@ -0,0 +1,375 @@
+use std::borrow::Cow;
+
+use ratatui::buffer::Buffer;
+use ratatui::layout::Rect;
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span, Text};
+use ratatui::widgets::{Paragraph, Widget, Wrap};
+
+use crate::blog::search;
+use crate::tui::text_sizing::ScaledText;
+
+/// The deepest heading level which gets scaled up, anything deeper is left to `tui_markdown`.
+const MAX_SCALED_HEADING_LEVEL: u8 = 2;
+
+/// Style patched onto words matching a search.
+pub const HIGHLIGHT_STYLE: Style = Style::new().fg(Color::Black).bg(Color::Yellow);
+
+/// A top level chunk of a markdown document.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MarkdownBlock<'a> {
+    Heading { level: u8, text: &'a str },
+    Body(String),
+}
+
+/// Splits a markdown document at ATX headings up to `max_level`, skipping over anything which
+/// looks like a heading within fenced code blocks.
+pub fn split_headings(src: &str, max_level: u8) -> Vec<MarkdownBlock<'_>> {
+    let mut blocks = Vec::new();
+    let mut body = String::new();
+    let mut in_fence = false;
+
+    for line in src.lines() {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            in_fence = !in_fence;
+        }
+
+        match (!in_fence).then(|| parse_heading(line)).flatten() {
+            Some((level, text)) if level <= max_level => {
+                flush_body(&mut body, &mut blocks);
+                blocks.push(MarkdownBlock::Heading { level, text });
+            }
+            _ => {
+                body.push_str(line);
+                body.push('\n');
+            }
+        }
+    }
+
+    flush_body(&mut body, &mut blocks);
+    blocks
+}
+
+fn flush_body(body: &mut String, blocks: &mut Vec<MarkdownBlock<'_>>) {
+    let trimmed = body.trim_matches('\n');
+    if !trimmed.trim().is_empty() {
+        blocks.push(MarkdownBlock::Body(trimmed.to_string()));
+    }
+
+    body.clear();
+}
+
+/// Parses an ATX heading line, returning its level and text.
+fn parse_heading(line: &str) -> Option<(u8, &str)> {
+    // Up to three spaces of indentation are permitted before the opening sequence
+    let indent = line.len() - line.trim_start_matches(' ').len();
+    if indent > 3 {
+        return None;
+    }
+
+    let line = &line[indent..];
+    let level = line.chars().take_while(|&ch| ch == '#').count();
+    if !(1..=6).contains(&level) {
+        return None;
+    }
+
+    let rest = &line[level..];
+    if !rest.is_empty() && !rest.starts_with([' ', '\t']) {
+        return None;
+    }
+
+    // Strip the optional closing sequence, which must be preceded by whitespace
+    let text = rest.trim();
+    let unclosed = text.trim_end_matches('#');
+    let text = if unclosed.is_empty() {
+        unclosed
+    } else if unclosed.ends_with([' ', '\t']) {
+        unclosed.trim_end()
+    } else {
+        text
+    };
+
+    Some((level as u8, text))
+}
+
+/// Highlights the words within a line matched by any of the given search terms.
+pub fn highlight_line<'a>(mut line: Line<'a>, terms: &[String]) -> Line<'a> {
+    if terms.is_empty() {
+        return line;
+    }
+
+    line.spans = line
+        .spans
+        .into_iter()
+        .flat_map(|span| {
+            let mut spans = Vec::new();
+            let mut end = 0;
+            for range in search::highlights(&span.content, terms) {
+                if range.start > end {
+                    spans.push(Span::styled(
+                        span.content[end..range.start].to_string(),
+                        span.style,
+                    ));
+                }
+
+                spans.push(Span::styled(
+                    span.content[range.clone()].to_string(),
+                    span.style.patch(HIGHLIGHT_STYLE),
+                ));
+                end = range.end;
+            }
+
+            match end {
+                0 => vec![span],
+                end if end < span.content.len() => {
+                    spans.push(Span::styled(span.content[end..].to_string(), span.style));
+                    spans
+                }
+                _ => spans,
+            }
+        })
+        .collect();
+
+    line
+}
+
+/// Renders markdown to text, highlighting the words matched by any of the given search terms.
+fn markdown_text<'a>(src: &'a str, highlight: &[String]) -> Text<'a> {
+    let mut text = tui_markdown::from_str(src);
+    text.lines = text.lines.into_iter().map(|line| highlight_line(line, highlight)).collect();
+    text
+}
+
+/// The scale factor for headings of a given level.
+pub fn heading_scale(level: u8) -> u8 {
+    match level {
+        1 => 3,
+        2 => 2,
+        _ => 1,
+    }
+}
+
+/// A heading within a rendered markdown document.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Heading {
+    pub level: u8,
+    pub text: String,
+    /// The row the heading starts at, relative to the top of the document.
+    pub row: u16,
+}
+
+/// Renders a markdown document, scaling up headings using the text sizing protocol if the
+/// terminal supports it.
+pub struct MarkdownView<'a> {
+    source: &'a str,
+    text_sizing: bool,
+    highlight: &'a [String],
+    scroll: u16,
+}
+
+impl<'a> MarkdownView<'a> {
+    pub fn new(source: &'a str) -> Self {
+        Self { source, text_sizing: false, highlight: &[], scroll: 0 }
+    }
+
+    pub fn text_sizing(mut self, text_sizing: bool) -> Self {
+        self.text_sizing = text_sizing;
+        self
+    }
+
+    /// Highlights the words matched by any of the given search terms.
+    pub fn highlight(mut self, terms: &'a [String]) -> Self {
+        self.highlight = terms;
+        self
+    }
+
+    /// Skips the given number of rows from the top of the document.
+    pub fn scroll(mut self, scroll: u16) -> Self {
+        self.scroll = scroll;
+        self
+    }
+
+    /// The headings of the document, along with where they end up when rendered at the
+    /// given width.
+    pub fn headings(&self, width: u16) -> Vec<Heading> {
+        self.layout(width)
+            .into_iter()
+            .filter_map(|(block, row, _)| match block {
+                MarkdownBlock::Heading { level, text } => {
+                    Some(Heading { level, text: text.to_string(), row })
+                }
+                MarkdownBlock::Body(_) => None,
+            })
+            .collect()
+    }
+
+    /// How many rows the document takes up when rendered at the given width.
+    pub fn height(&self, width: u16) -> u16 {
+        self.layout(width).last().map_or(0, |&(_, row, height)| row.saturating_add(height))
+    }
+
+    /// Splits the document into blocks, along with the row each one starts at and how many
+    /// rows it takes up when rendered at the given width.
+    fn layout(&self, width: u16) -> Vec<(MarkdownBlock<'a>, u16, u16)> {
+        let mut row = 0u16;
+        split_headings(self.source, 6)
+            .into_iter()
+            .map(|block| {
+                let height = match &block {
+                    MarkdownBlock::Heading { level, text } if self.is_scaled(*level) => {
+                        ScaledText::new(text, heading_scale(*level)).height()
+                    }
+                    block => {
+                        let source = block_source(block);
+                        self.paragraph(&source).line_count(width) as u16
+                    }
+                };
+
+                // Blocks are separated by a blank row, like `tui_markdown` does
+                let start = row;
+                row = row.saturating_add(height + 1);
+                (block, start, height)
+            })
+            .collect()
+    }
+
+    fn is_scaled(&self, level: u8) -> bool {
+        self.text_sizing && level <= MAX_SCALED_HEADING_LEVEL
+    }
+
+    fn paragraph<'b>(&self, source: &'b str) -> Paragraph<'b> {
+        Paragraph::new(markdown_text(source, self.highlight)).wrap(Wrap { trim: true })
+    }
+}
+
+/// The markdown a block is rendered from, restoring the marker of headings.
+fn block_source<'b>(block: &'b MarkdownBlock<'_>) -> Cow<'b, str> {
+    match block {
+        MarkdownBlock::Heading { level, text } => {
+            Cow::Owned(format!("{} {text}", "#".repeat(*level as usize)))
+        }
+        MarkdownBlock::Body(src) => Cow::Borrowed(src),
+    }
+}
+
+impl Widget for MarkdownView<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        // Lay out each block one after another, as scaled headings take up more than one row
+        for (block, row, height) in self.layout(area.width) {
+            if row.saturating_add(height) <= self.scroll {
+                continue;
+            }
+
+            let y = area.y.saturating_add(row.saturating_sub(self.scroll));
+            if y >= area.bottom() {
+                break;
+            }
+
+            // Blocks scrolled partway out of view are cut off at the top
+            let skipped = self.scroll.saturating_sub(row);
+            let visible = (height - skipped).min(area.bottom() - y);
+            let block_area = Rect::new(area.x, y, area.width, visible);
+            match block {
+                MarkdownBlock::Heading { level, text } if self.is_scaled(level) => {
+                    // Scaled text can't be cut off, so it is only shown once entirely in view
+                    if skipped == 0 {
+                        ScaledText::new(text, heading_scale(level))
+                            .style(
+                                Style::default()
+                                    .fg(Color::LightMagenta)
+                                    .add_modifier(Modifier::BOLD),
+                            )
+                            .render(block_area, buf);
+                    }
+                }
+                block => {
+                    let source = block_source(&block);
+                    self.paragraph(&source).scroll((skipped, 0)).render(block_area, buf);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_parse_heading() {
+        assert_eq!(parse_heading("# Hello"), Some((1, "Hello")));
+        assert_eq!(parse_heading("  ## Hello ##"), Some((2, "Hello")));
+        assert_eq!(parse_heading("### Hello#"), Some((3, "Hello#")));
+        assert_eq!(parse_heading("#"), Some((1, "")));
+        assert_eq!(parse_heading("#Hello"), None);
+        assert_eq!(parse_heading("    # Hello"), None);
+        assert_eq!(parse_heading("####### Hello"), None);
+    }
+
+    #[test]
+    fn test_split_headings() {
+        let src = "# Title\n\nSome text\n\n### Small\nMore\n\n## Section\nEnd\n";
+        assert_eq!(
+            split_headings(src, 2),
+            vec![
+                MarkdownBlock::Heading { level: 1, text: "Title" },
+                MarkdownBlock::Body("Some text\n\n### Small\nMore".to_string()),
+                MarkdownBlock::Heading { level: 2, text: "Section" },
+                MarkdownBlock::Body("End".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_highlight_line() {
+        let bold = Style::default().add_modifier(Modifier::BOLD);
+        let line = Line::from(vec![Span::raw("Writing a "), Span::styled("TUI", bold)]);
+
+        assert_eq!(
+            highlight_line(line, &search::terms("tu writ")).spans,
+            vec![
+                Span::styled("Writ", HIGHLIGHT_STYLE),
+                Span::raw("ing a "),
+                Span::styled("TU", bold.patch(HIGHLIGHT_STYLE)),
+                Span::styled("I", bold),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_markdown_view_layout() {
+        let view = MarkdownView::new("# Title\n\nSome text\n\n## Section\n\nMore text");
+        assert_eq!(
+            view.headings(80),
+            vec![
+                Heading { level: 1, text: "Title".to_string(), row: 0 },
+                Heading { level: 2, text: "Section".to_string(), row: 4 },
+            ]
+        );
+        assert_eq!(view.height(80), 7);
+
+        let mut buf = Buffer::empty(Rect::new(0, 0, 20, 3));
+        view.scroll(4).render(buf.area, &mut buf);
+        assert!(buf
+            .content
+            .iter()
+            .map(|cell| cell.symbol())
+            .collect::<String>()
+            .contains("Section"));
+    }
+
+    #[test]
+    fn test_split_headings_skips_code_fences() {
+        let src = "```sh\n# not a heading\n```\n# Heading";
+        assert_eq!(
+            split_headings(src, 2),
+            vec![
+                MarkdownBlock::Body("```sh\n# not a heading\n```".to_string()),
+                MarkdownBlock::Heading { level: 1, text: "Heading" },
+            ]
+        );
+    }
+}
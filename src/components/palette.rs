@@ -0,0 +1,516 @@
+use std::cmp::Reverse;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use color_eyre::Result;
+use ratatui::prelude::*;
+use ratatui::widgets::*;
+use tokio::sync::mpsc::UnboundedSender;
+
+use super::{Component, TextInput};
+use crate::action::Action;
+use crate::app::Mode;
+use crate::config::{key_event_to_string, Config};
+use crate::tui::Event;
+
+/// How many items are listed at once, the rest scroll into view.
+const MAX_RESULTS: usize = 10;
+/// The widest the palette gets.
+const PALETTE_WIDTH: u16 = 72;
+/// How many characters may be typed to match with.
+const MAX_QUERY_LENGTH: usize = 64;
+
+/// Actions which can be run from the palette, what they are listed as, and the tab they are
+/// run in if they only do anything there.
+const COMMANDS: &[(Action, &str, Option<usize>)] = &[
+    (Action::NextTab, "next tab", None),
+    (Action::PrevTab, "previous tab", None),
+    #[cfg(feature = "blog")]
+    (Action::Search, "search blog posts", Some(2)),
+    #[cfg(feature = "blog")]
+    (Action::NextMatch, "next matching post", Some(2)),
+    #[cfg(feature = "blog")]
+    (Action::PrevMatch, "previous matching post", Some(2)),
+    #[cfg(feature = "blog")]
+    (Action::CycleSort, "sort blog posts", Some(2)),
+    #[cfg(feature = "blog")]
+    (Action::CycleTag, "filter blog posts by tag", Some(2)),
+    #[cfg(feature = "blog")]
+    (Action::ToggleToc, "toggle table of contents", Some(2)),
+    #[cfg(feature = "guestbook")]
+    (Action::Sign, "sign the guestbook", Some(3)),
+    #[cfg(feature = "contact")]
+    (Action::Contact, "send me a message", None),
+    (Action::CycleColorDepth, "cycle color depth", None),
+    (Action::ToggleMouse, "toggle mouse support", None),
+    (Action::ClearScreen, "redraw the screen", None),
+    (Action::Suspend, "pause", None),
+    (Action::Quit, "quit", None),
+];
+
+/// What running an item of the palette does.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Target {
+    Tab(usize),
+    Project(usize),
+    #[cfg(feature = "blog")]
+    Post(usize),
+    /// One of the [`COMMANDS`].
+    Command(usize),
+}
+
+impl Target {
+    fn kind(self) -> &'static str {
+        match self {
+            Self::Tab(_) => "tab",
+            Self::Project(_) => "project",
+            #[cfg(feature = "blog")]
+            Self::Post(_) => "post",
+            Self::Command(_) => "action",
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Item {
+    target: Target,
+    label: String,
+    /// The keys the item is bound to, for commands.
+    keys: Option<String>,
+}
+
+/// An overlay for jumping to tabs, projects and blog posts, and running actions, by typing
+/// part of their name.
+#[derive(Default)]
+pub struct CommandPalette {
+    command_tx: Option<UnboundedSender<Action>>,
+    selected_tab: Arc<AtomicUsize>,
+    items: Vec<Item>,
+    query: TextInput,
+    open: bool,
+    /// The items matching the query, best first, along with which of their characters
+    /// matched.
+    matches: Vec<(usize, Vec<usize>)>,
+    selected: usize,
+    /// Items which were run this session, most recent first.
+    recent: Vec<Target>,
+}
+
+impl CommandPalette {
+    pub fn new(selected_tab: Arc<AtomicUsize>, tabs: &[&str]) -> Self {
+        let tabs = tabs.iter().enumerate().map(|(i, tab)| (Target::Tab(i), tab.to_string()));
+        let commands = COMMANDS
+            .iter()
+            .enumerate()
+            .map(|(i, (_, label, _))| (Target::Command(i), label.to_string()));
+
+        Self {
+            selected_tab,
+            query: TextInput::new().max_length(MAX_QUERY_LENGTH),
+            items: tabs
+                .chain(commands)
+                .map(|(target, label)| Item { target, label, keys: None })
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    /// Lists projects by their names, in the order their cards are in.
+    pub fn projects<'a>(mut self, names: impl IntoIterator<Item = &'a str>) -> Self {
+        self.items.extend(names.into_iter().enumerate().map(|(i, name)| Item {
+            target: Target::Project(i),
+            label: name.to_string(),
+            keys: None,
+        }));
+
+        self
+    }
+
+    /// Lists blog posts by their titles, in the order they were given to the blog in.
+    #[cfg(feature = "blog")]
+    pub fn posts(mut self, titles: impl IntoIterator<Item = String>) -> Self {
+        self.items.extend(titles.into_iter().enumerate().map(|(i, title)| Item {
+            target: Target::Post(i),
+            label: title,
+            keys: None,
+        }));
+
+        self
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.open
+    }
+
+    /// Lists the items matching the query, ranked by how well they match, then by how
+    /// recently they were run.
+    fn refresh(&mut self) {
+        let recency = |target| self.recent.iter().position(|&recent| recent == target);
+        let mut matches = self
+            .items
+            .iter()
+            .enumerate()
+            .filter_map(|(i, item)| {
+                let (score, positions) = fuzzy_match(self.query.value(), &item.label)?;
+                let recency = recency(item.target).unwrap_or(usize::MAX);
+                Some(((Reverse(score), recency, i), positions))
+            })
+            .collect::<Vec<_>>();
+
+        matches.sort_by_key(|(rank, _)| *rank);
+        self.matches =
+            matches.into_iter().map(|((_, _, i), positions)| (i, positions)).collect();
+        self.selected = 0;
+    }
+
+    fn run(&mut self) -> Result<()> {
+        let Some(target) = self.matches.get(self.selected).map(|&(i, _)| self.items[i].target)
+        else {
+            return Ok(());
+        };
+
+        // What was typed can be brought back the next time with `Ctrl-p`
+        self.open = false;
+        self.query.push_history();
+        self.recent.retain(|&recent| recent != target);
+        self.recent.insert(0, target);
+
+        let (tab, action) = match target {
+            Target::Tab(tab) => (Some(tab), None),
+            Target::Project(index) => (Some(1), Some(Action::OpenProject(index))),
+            #[cfg(feature = "blog")]
+            Target::Post(index) => (Some(2), Some(Action::OpenPost(index))),
+            Target::Command(index) => {
+                let (action, _, tab) = &COMMANDS[index];
+                (*tab, Some(action.clone()))
+            }
+        };
+
+        let Some(command_tx) = &self.command_tx else {
+            return Ok(());
+        };
+
+        // Switching to the tab the palette was opened in would close whatever is open in it
+        if let Some(tab) = tab.filter(|&tab| tab != self.selected_tab.load(Ordering::Relaxed))
+        {
+            command_tx.send(Action::SelectTab(tab))?;
+        }
+        if let Some(action) = action {
+            command_tx.send(action)?;
+        }
+
+        Ok(())
+    }
+
+    /// A row listing an item, with the characters matching the query highlighted.
+    fn item_row<'a>(item: &'a Item, positions: &[usize], selected: bool) -> Line<'a> {
+        let accent_style =
+            Style::default().add_modifier(Modifier::BOLD).fg(Color::LightMagenta);
+        let dim_style = Style::default().add_modifier(Modifier::DIM);
+        let label_style = if selected {
+            Style::default().add_modifier(Modifier::BOLD)
+        } else {
+            Style::default()
+        };
+
+        let mut spans = vec![
+            Span::styled(if selected { "▶ " } else { "  " }, accent_style),
+            Span::styled(format!("{:<9}", item.target.kind()), dim_style),
+        ];
+
+        let mut positions = positions.iter().peekable();
+        for (i, c) in item.label.chars().enumerate() {
+            let matched = positions.next_if_eq(&&i).is_some();
+            let style = if matched { accent_style } else { label_style };
+            match spans.last_mut() {
+                // Runs of characters styled the same way share a span
+                Some(span) if spans.len() > 2 && span.style == style => {
+                    span.content.to_mut().push(c)
+                }
+                _ => spans.push(Span::styled(c.to_string(), style)),
+            }
+        }
+
+        Line::from(spans)
+    }
+}
+
+/// Scores how well the query matches the text, if all of its characters appear in it in order
+/// ignoring case, along with which characters of the text they matched. Matches at the start
+/// of words and runs of consecutive characters score higher, while gaps between them and
+/// longer texts score lower.
+fn fuzzy_match(query: &str, text: &str) -> Option<(i64, Vec<usize>)> {
+    const MATCH: i64 = 16;
+    const WORD_START: i64 = 8;
+    const CONSECUTIVE: i64 = 8;
+    const MAX_GAP_PENALTY: i64 = 12;
+
+    let lowercase = |c: char| c.to_lowercase().next().unwrap_or(c);
+    let query =
+        query.chars().filter(|c| !c.is_whitespace()).map(lowercase).collect::<Vec<_>>();
+    let text = text.chars().collect::<Vec<_>>();
+    if query.is_empty() {
+        return Some((0, Vec::new()));
+    }
+
+    let bonus = |j: usize| match j.checked_sub(1).map(|prev| text[prev]) {
+        None => WORD_START,
+        Some(prev) if !prev.is_alphanumeric() => WORD_START,
+        Some(prev) if prev.is_lowercase() && text[j].is_uppercase() => WORD_START,
+        _ => 0,
+    };
+
+    // The best score for matching the query up to its `i`th character with that character at
+    // `j` in the text, along with where the character before it matched
+    let mut scores = vec![vec![None::<(i64, usize)>; text.len()]; query.len()];
+    for (i, &c) in query.iter().enumerate() {
+        for j in i..text.len() {
+            if lowercase(text[j]) != c {
+                continue;
+            }
+
+            let score = MATCH + bonus(j);
+            scores[i][j] = if i == 0 {
+                Some((score - (j as i64).min(MAX_GAP_PENALTY), 0))
+            } else {
+                (i - 1..j)
+                    .filter_map(|k| {
+                        let (prev, _) = scores[i - 1][k]?;
+                        let gap = (j - k - 1) as i64;
+                        let step = if gap == 0 {
+                            CONSECUTIVE
+                        } else {
+                            -(2 + gap).min(MAX_GAP_PENALTY)
+                        };
+                        Some((prev + step + score, k))
+                    })
+                    .max_by_key(|&(score, _)| score)
+            };
+        }
+    }
+
+    let last = query.len() - 1;
+    let (mut j, (score, _)) = scores[last]
+        .iter()
+        .enumerate()
+        .filter_map(|(j, score)| Some((j, (*score)?)))
+        .max_by_key(|&(_, (score, _))| score)?;
+
+    let mut positions = vec![j];
+    for i in (1..=last).rev() {
+        j = scores[i][j]?.1;
+        positions.push(j);
+    }
+    positions.reverse();
+
+    Some((score - text.len() as i64 / 4, positions))
+}
+
+impl Component for CommandPalette {
+    fn register_action_handler(&mut self, tx: UnboundedSender<Action>) -> Result<()> {
+        self.command_tx = Some(tx);
+        Ok(())
+    }
+
+    fn register_config_handler(&mut self, config: Config) -> Result<()> {
+        // Commands are listed along with the shortest keys they are bound to
+        let Some(keymap) = config.keybindings.get(&Mode::Home) else {
+            return Ok(());
+        };
+
+        for item in &mut self.items {
+            let Target::Command(index) = item.target else {
+                continue;
+            };
+
+            item.keys = keymap
+                .iter()
+                .filter(|(_, action)| **action == COMMANDS[index].0)
+                .map(|(keys, _)| keys.iter().map(key_event_to_string).collect::<String>())
+                .min_by_key(|keys| (keys.len(), keys.clone()));
+        }
+
+        Ok(())
+    }
+
+    fn is_focused(&self) -> bool {
+        self.open
+    }
+
+    fn handle_focused_event(&mut self, event: &Event) -> Result<bool> {
+        let query = self.query.value().to_string();
+        let handled = match event {
+            Event::Key(key) => self.query.handle_key(key),
+            Event::Paste(text) => {
+                self.query.paste(text);
+                true
+            }
+            _ => false,
+        };
+
+        if self.query.value() != query {
+            self.refresh();
+        }
+        Ok(handled)
+    }
+
+    fn update(&mut self, action: Action) -> Result<Option<Action>> {
+        match action {
+            Action::CommandPalette if !self.open => {
+                self.open = true;
+                self.query.clear();
+                self.refresh();
+            }
+            Action::ClosePalette => self.open = false,
+            Action::RunCommand if self.open => self.run()?,
+            Action::SelectNext if self.open => {
+                self.selected = (self.selected + 1).min(self.matches.len().saturating_sub(1))
+            }
+            Action::SelectPrev if self.open => self.selected = self.selected.saturating_sub(1),
+            _ => {}
+        }
+
+        Ok(None)
+    }
+
+    fn draw(&mut self, frame: &mut Frame, area: Rect) -> Result<()> {
+        if !self.open {
+            return Ok(());
+        }
+
+        let accent_style =
+            Style::default().add_modifier(Modifier::BOLD).fg(Color::LightMagenta);
+        let dim_style = Style::default().add_modifier(Modifier::DIM);
+
+        // Towards the top, with room for the query, the results and the borders
+        let width = PALETTE_WIDTH.min(area.width.saturating_sub(4));
+        let height = (MAX_RESULTS as u16 + 4).min(area.height);
+        let popup = Rect::new(
+            area.x + area.width.saturating_sub(width) / 2,
+            area.y + area.height.saturating_sub(height) / 4,
+            width,
+            height,
+        );
+
+        let matches = match self.matches.len() {
+            1 => String::from(" 1 match "),
+            n => format!(" {n} matches "),
+        };
+        let block = Block::bordered()
+            .border_type(BorderType::Rounded)
+            .border_style(accent_style)
+            .title(" jump to ")
+            .title_bottom(Line::styled(" enter to run · esc to close ", dim_style))
+            .title_bottom(Line::styled(matches, dim_style).right_aligned())
+            .padding(Padding::horizontal(1));
+
+        let inner = block.inner(popup);
+        frame.render_widget(Clear, popup);
+        frame.render_widget(block, popup);
+
+        let [query_row, _, results_area] = Layout::vertical([
+            Constraint::Length(1),
+            Constraint::Length(1),
+            Constraint::Min(0),
+        ])
+        .areas(inner);
+        let [prompt_area, query_area] =
+            Layout::horizontal([Constraint::Length(2), Constraint::Min(0)]).areas(query_row);
+        frame.render_widget(Span::styled("> ", accent_style), prompt_area);
+        self.query.render(frame, query_area, true);
+        if self.query.is_empty() {
+            let placeholder = " a tab, project, post or action";
+            let area = Rect {
+                x: query_area.x + 1,
+                width: query_area.width.saturating_sub(1),
+                ..query_area
+            };
+            frame.render_widget(Span::styled(placeholder, dim_style), area);
+        }
+
+        if self.matches.is_empty() {
+            frame.render_widget(Line::styled("  nothing matches", dim_style), results_area);
+            return Ok(());
+        }
+
+        // Scrolled just far enough for the selection to be in view
+        let rows = results_area.height as usize;
+        let offset = (self.selected + 1).saturating_sub(rows);
+        for (row, (position, (i, positions))) in
+            self.matches.iter().enumerate().skip(offset).take(rows).enumerate()
+        {
+            let item = &self.items[*i];
+            let area = Rect { y: results_area.y + row as u16, height: 1, ..results_area };
+            frame.render_widget(
+                Self::item_row(item, positions, position == self.selected),
+                area,
+            );
+            if let Some(keys) = &item.keys {
+                frame.render_widget(
+                    Line::styled(keys.as_str(), dim_style).right_aligned(),
+                    area,
+                );
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_fuzzy_match() {
+        assert_eq!(fuzzy_match("", "anything"), Some((0, vec![])));
+        assert_eq!(fuzzy_match("gb", "guestbook"), Some((32, vec![0, 5])));
+        assert_eq!(fuzzy_match("xyz", "guestbook"), None);
+        assert_eq!(fuzzy_match("bg", "guestbook"), None);
+
+        // Word starts are preferred over the first occurrence
+        let (_, positions) = fuzzy_match("tt", "toggle table of contents").unwrap();
+        assert_eq!(positions, vec![0, 7]);
+        let (_, positions) = fuzzy_match("SBP", "search blog posts").unwrap();
+        assert_eq!(positions, vec![0, 7, 12]);
+    }
+
+    #[test]
+    fn test_ranking() {
+        let score = |query, text| fuzzy_match(query, text).unwrap().0;
+
+        // Exact and prefix matches beat matches scattered across longer texts
+        assert!(score("blog", "blog") > score("blog", "filter blog posts by tag"));
+        assert!(score("sign", "sign the guestbook") > score("sign", "some interesting notes"));
+        assert!(score("proj", "projects") > score("proj", "some project"));
+    }
+
+    #[test]
+    fn test_recently_used_first() {
+        let mut palette = CommandPalette::new(Arc::default(), &["about", "projects"]);
+        palette.update(Action::CommandPalette).unwrap();
+        let labels = |palette: &CommandPalette| {
+            palette
+                .matches
+                .iter()
+                .map(|&(i, _)| palette.items[i].label.clone())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(labels(&palette)[..2], ["about", "projects"]);
+
+        palette.query.set_value("ab");
+        palette.refresh();
+        assert_eq!(labels(&palette)[0], "about");
+
+        // Running an item lists it first from then on
+        palette.query.set_value("proj");
+        palette.refresh();
+        palette.update(Action::RunCommand).unwrap();
+        assert!(!palette.is_open());
+
+        palette.update(Action::CommandPalette).unwrap();
+        assert_eq!(labels(&palette)[..2], ["projects", "about"]);
+    }
+}
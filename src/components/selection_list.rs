@@ -5,7 +5,7 @@ use ratatui::widgets::*;
 use tokio::sync::mpsc::UnboundedSender;
 
 use crate::action::Action;
-use crate::components::{Component, Post};
+use crate::components::{highlight_line, Component, Post};
 use crate::config::Config;
 
 #[derive(Debug)]
@@ -13,6 +13,8 @@ pub struct SelectionList<T> {
     config: Config,
     pub(super) options: Vec<T>,
     pub(super) list_state: ListState,
+    /// Search terms to highlight the words matched by.
+    pub(super) highlight: Vec<String>,
     action_tx: Option<UnboundedSender<Action>>,
 }
 
@@ -21,11 +23,26 @@ impl<T> SelectionList<T> {
         let mut list_state = ListState::default();
         list_state.select_first();
 
-        Self { config: Config::default(), options, list_state, action_tx: None }
+        Self {
+            config: Config::default(),
+            options,
+            list_state,
+            highlight: Vec::new(),
+            action_tx: None,
+        }
     }
 }
 
-impl Component for SelectionList<Post> {
+/// A row of the blog post list.
+#[derive(Debug, Clone)]
+pub enum ListRow {
+    /// Heads the posts published within a year, which can be collapsed to hide them.
+    Year { year: i32, posts: usize, collapsed: bool },
+    /// A post, along with its position among all posts and how many words it has.
+    Post { index: usize, post: Post, words: usize },
+}
+
+impl Component for SelectionList<ListRow> {
     fn register_config_handler(&mut self, config: Config) -> Result<()> {
         self.config = config;
         Ok(())
@@ -58,19 +75,10 @@ impl Component for SelectionList<Post> {
         frame: &mut ratatui::Frame,
         area: ratatui::prelude::Rect,
     ) -> Result<()> {
-        let items = self.options.iter().enumerate().map(|(i, post)| {
+        let items = self.options.iter().enumerate().map(|(i, row)| {
             let bold_style = Style::default().add_modifier(Modifier::BOLD);
             let accent_style = bold_style.fg(Color::LightMagenta);
 
-            let post_creation_date = post
-                .created_at
-                .as_ref()
-                .map(|dt| DateTime::parse_from_rfc3339(dt.as_str()))
-                .and_then(Result::ok)
-                .map_or(DateTime::UNIX_EPOCH.date_naive().to_string(), |dt| {
-                    dt.date_naive().to_string()
-                });
-
             let arrow_or_pad =
                 if self.list_state.selected().is_some_and(|selection| selection == i) {
                     "▶ ".to_string()
@@ -78,6 +86,29 @@ impl Component for SelectionList<Post> {
                     format!("{:>2}", " ")
                 };
 
+            let (post, words) = match row {
+                ListRow::Year { year, posts, collapsed } => {
+                    let posts = match posts {
+                        1 => String::from(" (1 post)"),
+                        n => format!(" ({n} posts)"),
+                    };
+
+                    return ListItem::new(Line::from(vec![
+                        Span::styled(arrow_or_pad, accent_style),
+                        Span::raw(" "),
+                        Span::styled(if *collapsed { "▸ " } else { "▾ " }, accent_style),
+                        Span::styled(year.to_string(), bold_style),
+                        Span::styled(posts, Style::default().add_modifier(Modifier::DIM)),
+                    ]));
+                }
+                ListRow::Post { post, words, .. } => (post, words),
+            };
+
+            let post_creation_date = crate::blog::created_at(post)
+                .map_or(DateTime::UNIX_EPOCH.date_naive().to_string(), |dt| {
+                    dt.date_naive().to_string()
+                });
+
             let padded_date = format!("{post_creation_date:>10}");
 
             let title_spans = vec![
@@ -89,9 +120,17 @@ impl Component for SelectionList<Post> {
                     post.title.clone().unwrap_or("[object Object]".to_string()), // LMAOOO
                     accent_style,
                 ),
+                Span::styled(
+                    format!(
+                        " · {} min read · {words} words",
+                        crate::blog::reading_time(*words)
+                    ),
+                    Style::default().add_modifier(Modifier::DIM),
+                ),
             ];
 
-            let mut list_content = vec![Line::from(title_spans)];
+            let mut list_content =
+                vec![highlight_line(Line::from(title_spans), &self.highlight)];
 
             let line_format = [
                 Span::raw(format!("{:>14}", " ")),
@@ -108,7 +147,10 @@ impl Component for SelectionList<Post> {
                 .concat(),
             );
 
-            list_content.push(Line::from([line_format.as_slice(), &[subtitle_span]].concat()));
+            list_content.push(highlight_line(
+                Line::from([line_format.as_slice(), &[subtitle_span]].concat()),
+                &self.highlight,
+            ));
             list_content.push(Line::from([line_format.as_slice(), &[Span::raw("")]].concat()));
 
             ListItem::new(list_content)
@@ -37,6 +37,10 @@ impl Tabs {
         }
     }
 
+    pub fn select(&mut self, tab: usize) {
+        self.selected_tab.store(tab.min(self.tabs.len() - 1), Ordering::Relaxed);
+    }
+
     pub fn current_tab(&self) -> usize {
         self.selected_tab.load(Ordering::Relaxed)
     }
@@ -59,6 +63,7 @@ impl Component for Tabs {
             Action::Render => {}
             Action::NextTab => self.next(),
             Action::PrevTab => self.previous(),
+            Action::SelectTab(tab) => self.select(tab),
             _ => {}
         };
 
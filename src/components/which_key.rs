@@ -0,0 +1,124 @@
+use color_eyre::Result;
+use crossterm::event::KeyEvent;
+use ratatui::prelude::*;
+use ratatui::widgets::*;
+
+use super::Component;
+use crate::action::Action;
+use crate::config::{key_event_to_string, key_sequence_to_string};
+use crate::keymap::KeyTrie;
+
+/// Shown below the hints, for getting out of the sequence.
+const CANCEL_HINT: &str = " esc to cancel ";
+
+/// A popup listing which keys can follow those pressed so far of a key sequence, and what each
+/// of them does.
+#[derive(Debug, Default)]
+pub struct WhichKey {
+    /// The keys pressed so far, as they are written in the config.
+    prefix: String,
+    /// The keys which can be pressed next and what they do, nothing is shown while empty.
+    hints: Vec<(String, String)>,
+}
+
+impl WhichKey {
+    /// Lists what can follow the keys, which lead to the given part of the keymap.
+    pub fn show(&mut self, keys: &[KeyEvent], trie: &KeyTrie) {
+        self.prefix = key_sequence_to_string(keys);
+        self.hints = trie
+            .continuations()
+            .map(|(key, next)| {
+                let mut description = next.action().map(describe).unwrap_or_default();
+                if next.is_prefix() {
+                    description.push_str(&format!(" +{} more", next.continuations().count()));
+                }
+                (key_event_to_string(key), description.trim_start().to_string())
+            })
+            .collect();
+        self.hints.sort();
+    }
+
+    pub fn hide(&mut self) {
+        self.hints.clear();
+    }
+
+    pub fn is_shown(&self) -> bool {
+        !self.hints.is_empty()
+    }
+}
+
+/// Writes an action the way it reads, such as `NextTab` as "next tab".
+fn describe(action: &Action) -> String {
+    let mut description = String::new();
+    for c in action.to_string().chars() {
+        if c.is_uppercase() && !description.is_empty() {
+            description.push(' ');
+        }
+        description.push(c.to_ascii_lowercase());
+    }
+
+    description
+}
+
+impl Component for WhichKey {
+    fn draw(&mut self, frame: &mut Frame, area: Rect) -> Result<()> {
+        if self.hints.is_empty() {
+            return Ok(());
+        }
+
+        let accent_style =
+            Style::default().add_modifier(Modifier::BOLD).fg(Color::LightMagenta);
+        let dim_style = Style::default().add_modifier(Modifier::DIM);
+
+        let key_width = self.hints.iter().map(|(key, _)| key.len()).max().unwrap_or_default();
+        let lines = self
+            .hints
+            .iter()
+            .map(|(key, description)| {
+                Line::from(vec![
+                    Span::styled(format!("{key:<key_width$}  "), accent_style),
+                    Span::raw(description.as_str()),
+                ])
+            })
+            .collect::<Vec<_>>();
+
+        // Tucked into the bottom right corner, out of the way of what is being looked at
+        let width = lines.iter().map(Line::width).max().unwrap_or_default() as u16 + 4;
+        let width = width
+            .max(self.prefix.len() as u16 + 4)
+            .max(CANCEL_HINT.len() as u16 + 2)
+            .min(area.width);
+        let height = (lines.len() as u16 + 2).min(area.height);
+        let popup = Rect::new(
+            area.x + area.width.saturating_sub(width + 1),
+            area.y + area.height.saturating_sub(height + 1),
+            width,
+            height,
+        );
+
+        let block = Block::bordered()
+            .border_type(BorderType::Rounded)
+            .border_style(accent_style)
+            .title(format!(" {} ", self.prefix))
+            .title_bottom(Line::styled(CANCEL_HINT, dim_style).right_aligned())
+            .padding(Padding::horizontal(1));
+
+        frame.render_widget(Clear, popup);
+        frame.render_widget(Paragraph::new(lines).block(block), popup);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_describe() {
+        assert_eq!(describe(&Action::NextTab), "next tab");
+        assert_eq!(describe(&Action::CycleColorDepth), "cycle color depth");
+        assert_eq!(describe(&Action::OpenPost(2)), "open post");
+    }
+}
@@ -39,8 +39,75 @@ pub struct Config {
     pub private_keys: Vec<PrivateKey>,
     #[serde(default)]
     pub keybindings: KeyBindings,
+    /// How long to wait for the rest of a key sequence, in milliseconds.
+    #[serde(default = "default_key_timeout")]
+    pub key_timeout: u64,
     #[serde(default)]
     pub styles: Styles,
+    /// Where the landing site is publicly reachable from, such as `https://devcomp.xyz`, for
+    /// linking to it from the blog's feeds.
+    #[serde(default)]
+    pub site_url: Option<String>,
+    #[serde(default)]
+    pub blog: BlogConfig,
+    #[serde(default)]
+    pub forge: Option<ForgeConfig>,
+    #[serde(default)]
+    pub contact: ContactConfig,
+}
+
+/// Where the posts shown in the blog tab come from.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Eq)]
+#[serde(tag = "source", rename_all = "lowercase")]
+pub enum BlogConfig {
+    /// WhiteWind entries published on ATProto.
+    #[default]
+    Atproto,
+    /// A directory of markdown files with front matter.
+    Markdown { path: String },
+    /// An RSS or Atom feed.
+    Feed { url: String },
+}
+
+/// A GitHub or Gitea/Forgejo compatible API to fetch details about the projects from.
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq)]
+pub struct ForgeConfig {
+    /// The root of the API, such as `https://api.github.com` or `https://codeberg.org/api/v1`.
+    pub url: String,
+    /// An access token, to be allowed more requests.
+    #[serde(default)]
+    pub token: Option<String>,
+}
+
+impl ForgeConfig {
+    /// The root of the forge's website, which is the API's without the `api.` subdomain GitHub
+    /// uses, or the `/api/v1` path Gitea and Forgejo use.
+    pub fn web_url(&self) -> String {
+        let url = self.url.trim_end_matches('/');
+        let url = url.strip_suffix("/api/v1").unwrap_or(url);
+        url.replacen("://api.", "://", 1)
+    }
+}
+
+/// Where messages sent through the contact form are delivered to.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Eq)]
+pub struct ContactConfig {
+    #[serde(default)]
+    pub mailbox: MailboxFormat,
+    /// A URL each message is also posted to as JSON, such as a chat webhook.
+    #[serde(default)]
+    pub webhook: Option<String>,
+}
+
+/// How messages are stored in the data directory.
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum MailboxFormat {
+    /// A file for each message, in `contact/new`.
+    #[default]
+    Maildir,
+    /// Every message appended to `contact.mbox`.
+    Spool,
 }
 
 lazy_static! {
@@ -103,11 +170,18 @@ impl Config {
                 cfg.private_keys.push(key);
             }
         }
+        for conflict in cfg.keybindings.conflicts() {
+            warn!("Conflicting keybindings: {conflict}");
+        }
 
         Ok(cfg)
     }
 }
 
+fn default_key_timeout() -> u64 {
+    1000
+}
+
 pub fn get_data_dir() -> PathBuf {
     let directory = if let Some(s) = DATA_DIR.clone() {
         s
@@ -134,6 +208,21 @@ fn project_directory() -> Option<ProjectDirs> {
     ProjectDirs::from("xyz", "devcomp", env!("CARGO_PKG_NAME"))
 }
 
+/// Expands `~`, `$DATA_DIR` and `$CONFIG_DIR` in a path from the config.
+pub fn expand_path(path: &str) -> Option<PathBuf> {
+    let ud = UserDirs::new()?;
+    Some(PathBuf::from(&*shellexpand::full_with_context_no_errors(
+        path,
+        || ud.home_dir().to_str(),
+        |var| match var {
+            "DATA_DIR" => get_data_dir().to_str().map(|s| s.to_string()),
+            "CONFIG_DIR" => get_config_dir().to_str().map(|s| s.to_string()),
+            "HOME" => Some(String::from("~")),
+            _ => None,
+        },
+    )))
+}
+
 #[optimize(speed)]
 fn private_key_deserialize<'de, D>(deserializer: D) -> Result<Vec<PrivateKey>, D::Error>
 where
@@ -146,17 +235,8 @@ where
             PrivateKey::from_openssh(pem_or_path.as_bytes()).or_else(|_| {
                 debug!("Failed to parse {} PEM from string, trying as file path", pem_type);
 
-                let ud = UserDirs::new().ok_or(ssh_key::Error::Io(io::ErrorKind::NotFound))?;
-                let expanded_path = PathBuf::from(&*shellexpand::full_with_context_no_errors(
-                    &pem_or_path,
-                    || ud.home_dir().to_str(),
-                    |var| match var {
-                        "DATA_DIR" => get_data_dir().to_str().map(|s| s.to_string()),
-                        "CONFIG_DIR" => get_config_dir().to_str().map(|s| s.to_string()),
-                        "HOME" => Some(String::from("~")),
-                        _ => None,
-                    },
-                ));
+                let expanded_path = expand_path(&pem_or_path)
+                    .ok_or(ssh_key::Error::Io(io::ErrorKind::NotFound))?;
 
                 if !expanded_path.exists() {
                     let span = info_span!("host_keygen", algo = %pem_type, path = %expanded_path.display());
@@ -199,21 +279,55 @@ impl<'de> Deserialize<'de> for KeyBindings {
     {
         let parsed_map = HashMap::<Mode, HashMap<String, Action>>::deserialize(deserializer)?;
 
-        let keybindings = parsed_map
-            .into_iter()
-            .map(|(mode, inner_map)| {
-                let converted_inner_map = inner_map
-                    .into_iter()
-                    .map(|(key_str, cmd)| (parse_key_sequence(&key_str).unwrap(), cmd))
-                    .collect();
-                (mode, converted_inner_map)
-            })
-            .collect();
+        let mut keybindings = HashMap::<Mode, HashMap<Vec<KeyEvent>, Action>>::new();
+        for (mode, inner_map) in parsed_map {
+            let converted_inner_map = keybindings.entry(mode).or_default();
+            for (key_str, cmd) in inner_map {
+                let keys = parse_key_sequence(&key_str).map_err(serde::de::Error::custom)?;
+
+                // The same keys can be written in more than one way, such as `<Ctrl-k>` and
+                // `<ctrl-K>`, only one of which ends up being used
+                let other = converted_inner_map.get(&keys);
+                if let Some(other) = other.filter(|&other| *other != cmd) {
+                    warn!(
+                        "Conflicting keybindings: `{key_str}` in {mode:?} is bound to both \
+                         {other} and {cmd}, only one of which is used"
+                    );
+                }
+                converted_inner_map.insert(keys, cmd);
+            }
+        }
 
         Ok(KeyBindings(keybindings))
     }
 }
 
+impl KeyBindings {
+    /// Describes bindings which get in each other's way, where keys are bound to an action
+    /// as well as being the start of a longer sequence. Those only run once no more keys
+    /// are pressed for a while.
+    pub fn conflicts(&self) -> Vec<String> {
+        let mut conflicts = Vec::new();
+        for (mode, bindings) in self.iter() {
+            for (keys, action) in bindings {
+                for (longer, other) in bindings {
+                    if longer.len() > keys.len() && longer.starts_with(keys) {
+                        conflicts.push(format!(
+                            "`{}` ({action}) in {mode:?} is the start of `{}` ({other}), \
+                             so it only runs after a pause",
+                            key_sequence_to_string(keys),
+                            key_sequence_to_string(longer),
+                        ));
+                    }
+                }
+            }
+        }
+
+        conflicts.sort();
+        conflicts
+    }
+}
+
 fn parse_key_event(raw: &str) -> Result<KeyEvent, String> {
     let raw_lower = raw.to_ascii_lowercase();
     let (remaining, modifiers) = extract_modifiers(&raw_lower);
@@ -286,7 +400,9 @@ fn parse_key_code_with_modifiers(
         c if c.len() == 1 => {
             let mut c = c.chars().next().unwrap();
             if modifiers.contains(KeyModifiers::SHIFT) {
+                // Terminals send shifted letters as uppercase ones, without shift held
                 c = c.to_ascii_uppercase();
+                modifiers.remove(KeyModifiers::SHIFT);
             }
             KeyCode::Char(c)
         }
@@ -318,7 +434,7 @@ pub fn key_event_to_string(key_event: &KeyEvent) -> String {
         }
         KeyCode::Char(' ') => "space",
         KeyCode::Char(c) => {
-            char = c.to_string();
+            char = c.to_ascii_lowercase().to_string();
             &char
         }
         KeyCode::Esc => "esc",
@@ -340,7 +456,9 @@ pub fn key_event_to_string(key_event: &KeyEvent) -> String {
         modifiers.push("ctrl");
     }
 
-    if key_event.modifiers.intersects(KeyModifiers::SHIFT) {
+    // Shifted letters are sent as uppercase ones, but written with shift held
+    let shifted = matches!(key_event.code, KeyCode::Char(c) if c.is_ascii_uppercase());
+    if shifted || key_event.modifiers.intersects(KeyModifiers::SHIFT) {
         modifiers.push("shift");
     }
 
@@ -358,6 +476,11 @@ pub fn key_event_to_string(key_event: &KeyEvent) -> String {
     key
 }
 
+/// Writes keys the way they are written in the config, such as `<g><t>`.
+pub fn key_sequence_to_string(keys: &[KeyEvent]) -> String {
+    keys.iter().map(|key| format!("<{}>", key_event_to_string(key))).collect()
+}
+
 pub fn parse_key_sequence(raw: &str) -> Result<Vec<KeyEvent>, String> {
     if raw.chars().filter(|c| *c == '>').count() != raw.chars().filter(|c| *c == '<').count() {
         return Err(format!("Unable to parse `{raw}`"));
@@ -657,4 +780,82 @@ mod tests {
             KeyEvent::new(KeyCode::Enter, KeyModifiers::ALT)
         );
     }
+
+    #[test]
+    fn test_shifted_keys() {
+        // Terminals send uppercase letters rather than holding shift
+        let key = KeyEvent::new(KeyCode::Char('N'), KeyModifiers::empty());
+        assert_eq!(parse_key_event("shift-n").unwrap(), key);
+        assert_eq!(key_event_to_string(&key), "shift-n");
+        assert_eq!(
+            key_sequence_to_string(&parse_key_sequence("<g><Shift-t>").unwrap()),
+            "<g><shift-t>"
+        );
+    }
+
+    #[test]
+    fn test_keybinding_conflicts() {
+        let keybindings = json5::from_str::<KeyBindings>(
+            r#"{
+                "Home": { "<g>": "Search", "<g><t>": "NextTab", "<g><Shift-t>": "PrevTab" }
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(
+            keybindings.conflicts(),
+            vec![
+                "`<g>` (Search) in Home is the start of `<g><shift-t>` (PrevTab), so it only \
+                 runs after a pause",
+                "`<g>` (Search) in Home is the start of `<g><t>` (NextTab), so it only runs \
+                 after a pause",
+            ]
+        );
+
+        let keybindings = json5::from_str::<KeyBindings>(
+            r#"{ "Home": { "<g><t>": "NextTab", "<t>": "CycleTag" } }"#,
+        )
+        .unwrap();
+        assert!(keybindings.conflicts().is_empty());
+
+        let invalid = r#"{ "Home": { "<nope>": "Quit" } }"#;
+        assert!(json5::from_str::<KeyBindings>(invalid).is_err());
+    }
+
+    #[test]
+    fn test_blog_config() {
+        assert_eq!(
+            json5::from_str::<BlogConfig>(r#"{ "source": "atproto" }"#).unwrap(),
+            BlogConfig::Atproto
+        );
+        assert_eq!(
+            json5::from_str::<BlogConfig>(r#"{ "source": "markdown", "path": "~/posts" }"#)
+                .unwrap(),
+            BlogConfig::Markdown { path: String::from("~/posts") }
+        );
+        assert_eq!(
+            json5::from_str::<BlogConfig>(r#"{ "source": "feed", "url": "https://a/feed" }"#)
+                .unwrap(),
+            BlogConfig::Feed { url: String::from("https://a/feed") }
+        );
+        assert!(json5::from_str::<BlogConfig>(r#"{ "source": "markdown" }"#).is_err());
+    }
+
+    #[test]
+    fn test_forge_config() {
+        assert_eq!(
+            json5::from_str::<ForgeConfig>(r#"{ "url": "https://codeberg.org/api/v1" }"#)
+                .unwrap(),
+            ForgeConfig { url: String::from("https://codeberg.org/api/v1"), token: None }
+        );
+        assert!(json5::from_str::<ForgeConfig>(r#"{ "token": "abc" }"#).is_err());
+    }
+
+    #[test]
+    fn test_forge_web_url() {
+        let web_url =
+            |url: &str| ForgeConfig { url: String::from(url), token: None }.web_url();
+        assert_eq!(web_url("https://api.github.com"), "https://github.com");
+        assert_eq!(web_url("https://codeberg.org/api/v1/"), "https://codeberg.org");
+        assert_eq!(web_url("https://git.example.com"), "https://git.example.com");
+    }
 }
@@ -0,0 +1,504 @@
+//! Messages sent through the contact form, delivered to a mailbox within the data directory
+//! and optionally posted to a webhook.
+
+use std::collections::HashMap;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::net::IpAddr;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use chrono::{DateTime, Utc};
+use color_eyre::eyre::eyre;
+use color_eyre::Result;
+use lazy_static::lazy_static;
+use reqwest::header::CONTENT_TYPE;
+
+use crate::config::{get_data_dir, ContactConfig, MailboxFormat};
+use crate::ssh::Visitor;
+use crate::CONFIG;
+
+/// The most characters a name may have.
+pub const MAX_NAME_LENGTH: usize = 64;
+/// The most characters an address to reply to may have, which is the most an email address
+/// can have.
+pub const MAX_REPLY_TO_LENGTH: usize = 254;
+/// The most characters a message may have.
+pub const MAX_MESSAGE_LENGTH: usize = 2000;
+/// How long to wait on the webhook before giving up on it.
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(15);
+/// How long the form has to be open for before a message is sent, anything quicker is
+/// unlikely to have been typed by a person.
+const MIN_TIME_ON_FORM: Duration = Duration::from_secs(5);
+/// How many messages may be sent from an address within each period.
+const RATE_LIMITS: [(Duration, usize); 2] = [
+    (Duration::from_secs(60 * 60), 2),      // 1 hour
+    (Duration::from_secs(24 * 60 * 60), 5), // 1 day
+];
+
+lazy_static! {
+    pub(crate) static ref CONTACT: Arc<Contact> =
+        Arc::new(Contact::new(CONFIG.contact.clone(), &get_data_dir()));
+}
+
+/// A message written in the contact form.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Message {
+    pub name: String,
+    /// An email address to reply to, which may be left empty.
+    pub reply_to: String,
+    pub body: String,
+}
+
+/// Why a message was not sent, shown to the visitor who tried to send it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Rejection {
+    EmptyName,
+    NameTooLong,
+    InvalidReplyTo,
+    EmptyMessage,
+    MessageTooLong,
+    /// The form was sent too soon after it was opened.
+    TooQuick,
+    /// Too many messages were sent from the address recently.
+    RateLimited {
+        retry_after: Duration,
+    },
+}
+
+impl std::fmt::Display for Rejection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::EmptyName => write!(f, "a name is needed"),
+            Self::NameTooLong => write!(f, "names are at most {MAX_NAME_LENGTH} characters"),
+            Self::InvalidReplyTo => write!(f, "that doesn't look like an email address"),
+            Self::EmptyMessage => write!(f, "a message is needed"),
+            Self::MessageTooLong => {
+                write!(f, "messages are at most {MAX_MESSAGE_LENGTH} characters")
+            }
+            Self::TooQuick => write!(f, "that was quick! take a moment before sending"),
+            Self::RateLimited { retry_after } => {
+                let minutes = retry_after.as_secs().div_ceil(60);
+                write!(f, "slow down! try again in {minutes} minute(s)")
+            }
+        }
+    }
+}
+
+impl std::error::Error for Rejection {}
+
+/// Delivers messages to the mailbox, keeping track of how many were sent from each address.
+#[derive(Debug)]
+pub struct Contact {
+    config: ContactConfig,
+    data_dir: PathBuf,
+    client: reqwest::Client,
+    /// When messages were sent from each address, visitors whose address isn't known share
+    /// their limits.
+    sent: Mutex<HashMap<Option<IpAddr>, Vec<Instant>>>,
+}
+
+impl Contact {
+    pub fn new(config: ContactConfig, data_dir: &Path) -> Self {
+        Self {
+            config,
+            data_dir: data_dir.to_path_buf(),
+            client: reqwest::Client::builder()
+                .timeout(WEBHOOK_TIMEOUT)
+                .build()
+                .expect("Failed to build HTTP client"),
+            sent: Mutex::default(),
+        }
+    }
+
+    /// Sends a message from the visitor, if it passes the limits. Rejected messages fail with
+    /// a [`Rejection`].
+    pub fn send(
+        &self,
+        visitor: &Visitor,
+        message: &Message,
+        opened_at: Instant,
+    ) -> Result<()> {
+        self.send_at(visitor, message, opened_at, Instant::now())
+    }
+
+    fn send_at(
+        &self,
+        visitor: &Visitor,
+        message: &Message,
+        opened_at: Instant,
+        now: Instant,
+    ) -> Result<()> {
+        let message = validate(message)?;
+        if now.saturating_duration_since(opened_at) < MIN_TIME_ON_FORM {
+            return Err(Rejection::TooQuick.into());
+        }
+
+        let mut sent = self.sent.lock().map_err(|_| eyre!("Contact rate limits poisoned"))?;
+        // Forgets about addresses which haven't sent anything within the longest period, so
+        // the limits don't grow with every visitor there ever was
+        sent.retain(|_, sent| {
+            sent.retain(|at| now.saturating_duration_since(*at) < RATE_LIMITS[1].0);
+            !sent.is_empty()
+        });
+        let sent = sent.entry(visitor.address).or_default();
+        for (period, limit) in RATE_LIMITS {
+            let mut recent =
+                sent.iter().rev().filter(|at| now.saturating_duration_since(**at) < period);
+            if let Some(oldest) = recent.nth(limit - 1) {
+                // Another message may be sent once the oldest one counted leaves the period
+                let retry_after = (*oldest + period).saturating_duration_since(now);
+                let retry_after = retry_after.max(Duration::from_secs(1));
+                return Err(Rejection::RateLimited { retry_after }.into());
+            }
+        }
+
+        let date = Utc::now();
+        self.deliver(&email(visitor, &message, date), date)?;
+        sent.push(now);
+        tracing::info!(address = ?visitor.address, "Contact message sent by {}", message.name);
+
+        if let Some(webhook) = self.config.webhook.clone() {
+            let request = self
+                .client
+                .post(&webhook)
+                .header(CONTENT_TYPE, "application/json")
+                .body(webhook_payload(visitor, &message, date).to_string());
+
+            // The message is already in the mailbox, so a failing webhook isn't worth
+            // keeping the visitor waiting on
+            tokio::spawn(async move {
+                let response = request.send().await.and_then(|res| res.error_for_status());
+                if let Err(err) = response {
+                    tracing::warn!("Failed to post contact message to webhook: {err}");
+                }
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Writes an email to the mailbox configured.
+    fn deliver(&self, email: &str, date: DateTime<Utc>) -> Result<()> {
+        match self.config.mailbox {
+            MailboxFormat::Maildir => {
+                let maildir = self.data_dir.join("contact");
+                for dir in ["tmp", "new", "cur"] {
+                    fs::create_dir_all(maildir.join(dir))?;
+                }
+
+                // Written to `tmp` first and then moved, so that mail readers never see a
+                // partially written message
+                let name = maildir_name(date);
+                fs::write(maildir.join("tmp").join(&name), email)?;
+                fs::rename(maildir.join("tmp").join(&name), maildir.join("new").join(&name))?;
+            }
+            MailboxFormat::Spool => {
+                fs::create_dir_all(&self.data_dir)?;
+                let mut spool = OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(self.data_dir.join("contact.mbox"))?;
+
+                spool.write_all(mbox_entry(email, date).as_bytes())?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Checks a message against the limits, returning it trimmed and without any control
+/// characters, other than the line breaks in its body.
+fn validate(message: &Message) -> Result<Message, Rejection> {
+    let clean = |text: &str, keep_newlines: bool| {
+        text.chars()
+            .filter(|c| !c.is_control() || (keep_newlines && *c == '\n'))
+            .collect::<String>()
+            .trim()
+            .to_string()
+    };
+    let (name, reply_to, body) = (
+        clean(&message.name, false),
+        clean(&message.reply_to, false),
+        clean(&message.body, true),
+    );
+
+    if name.is_empty() {
+        return Err(Rejection::EmptyName);
+    } else if name.chars().count() > MAX_NAME_LENGTH {
+        return Err(Rejection::NameTooLong);
+    } else if !reply_to.is_empty() && !is_email_address(&reply_to) {
+        return Err(Rejection::InvalidReplyTo);
+    } else if body.is_empty() {
+        return Err(Rejection::EmptyMessage);
+    } else if body.chars().count() > MAX_MESSAGE_LENGTH {
+        return Err(Rejection::MessageTooLong);
+    }
+
+    Ok(Message { name, reply_to, body })
+}
+
+/// A loose check for whether something could be an email address, which is only meant to
+/// catch typos and keep anything odd out of the headers.
+fn is_email_address(address: &str) -> bool {
+    let Some((local, domain)) = address.rsplit_once('@') else {
+        return false;
+    };
+
+    address.chars().count() <= MAX_REPLY_TO_LENGTH
+        && !local.is_empty()
+        && domain.contains('.')
+        && !domain.starts_with('.')
+        && !domain.ends_with('.')
+        && !address.contains(|c: char| c.is_whitespace() || "<>()[]\\,;:\"".contains(c))
+}
+
+/// Formats a message as an email. Headers are written as UTF-8 rather than encoded, which
+/// mail readers have long understood.
+fn email(visitor: &Visitor, message: &Message, date: DateTime<Utc>) -> String {
+    let name = message.name.replace('\\', "\\\\").replace('"', "\\\"");
+    let mut headers = vec![
+        format!("Date: {}", date.to_rfc2822()),
+        format!("From: ssh-portfolio <{}@localhost>", env!("CARGO_PKG_NAME")),
+        format!("Subject: Message from {}", message.name),
+    ];
+    if !message.reply_to.is_empty() {
+        headers.push(format!("Reply-To: \"{name}\" <{}>", message.reply_to));
+    }
+
+    headers.push(format!("X-SSH-User: {}", visitor.user.replace(char::is_control, "")));
+    if let Some(fingerprint) = &visitor.fingerprint {
+        headers.push(format!("X-SSH-Fingerprint: {fingerprint}"));
+    }
+    if let Some(address) = &visitor.address {
+        headers.push(format!("X-SSH-Address: {address}"));
+    }
+
+    headers.push(String::from("Content-Type: text/plain; charset=utf-8"));
+    headers.push(String::from("Content-Transfer-Encoding: 8bit"));
+    format!("{}\n\n{}\n", headers.join("\n"), message.body)
+}
+
+/// A unique name for a message in a maildir.
+fn maildir_name(date: DateTime<Utc>) -> String {
+    static DELIVERIES: AtomicUsize = AtomicUsize::new(0);
+    format!(
+        "{}.M{}P{}Q{}.{}",
+        date.timestamp(),
+        date.timestamp_subsec_micros(),
+        std::process::id(),
+        DELIVERIES.fetch_add(1, Ordering::Relaxed),
+        env!("CARGO_PKG_NAME"),
+    )
+}
+
+/// Formats an email to be appended to an mbox spool, quoting lines in its body which would
+/// otherwise be mistaken for the start of another message.
+fn mbox_entry(email: &str, date: DateTime<Utc>) -> String {
+    let mut entry = format!(
+        "From {}@localhost {}\n",
+        env!("CARGO_PKG_NAME"),
+        date.format("%a %b %e %H:%M:%S %Y")
+    );
+    for line in email.lines() {
+        if line.trim_start_matches('>').starts_with("From ") {
+            entry.push('>');
+        }
+
+        entry.push_str(line);
+        entry.push('\n');
+    }
+
+    entry.push('\n');
+    entry
+}
+
+fn webhook_payload(
+    visitor: &Visitor,
+    message: &Message,
+    date: DateTime<Utc>,
+) -> serde_json::Value {
+    serde_json::json!({
+        "name": message.name,
+        "reply_to": Some(&message.reply_to).filter(|reply_to| !reply_to.is_empty()),
+        "message": message.body,
+        "user": visitor.user,
+        "fingerprint": visitor.fingerprint,
+        "address": visitor.address,
+        "date": date.to_rfc3339(),
+        // Understood by Discord and Slack, which don't look at anything else. Neither are let
+        // ping anyone, whatever the message says
+        "content": format!("**{}** sent a message:\n{}", message.name, message.body),
+        "allowed_mentions": { "parse": [] },
+        "text": format!(
+            "*{}* sent a message:\n{}",
+            slack_escape(&message.name),
+            slack_escape(&message.body)
+        ),
+    })
+}
+
+/// Escapes text for Slack, which would otherwise turn `<!channel>` into a mention and `<url>`
+/// into a link. A zero width space keeps `@` from mentioning anyone too.
+fn slack_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('@', "@\u{200b}")
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::routing::post;
+    use axum::Router;
+    use pretty_assertions::assert_eq;
+    use tokio::sync::mpsc;
+
+    use super::*;
+
+    fn message(name: &str, reply_to: &str, body: &str) -> Message {
+        Message {
+            name: name.to_string(),
+            reply_to: reply_to.to_string(),
+            body: body.to_string(),
+        }
+    }
+
+    fn rejection(result: Result<()>) -> Rejection {
+        result.unwrap_err().downcast::<Rejection>().unwrap()
+    }
+
+    fn temp_dir(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("ssh-portfolio-{name}-{}", std::process::id()))
+    }
+
+    #[test]
+    fn test_validate() {
+        assert_eq!(
+            validate(&message(" erica\x1b ", "", "hi\r\n\tthere\n\n")),
+            Ok(message("erica", "", "hi\nthere"))
+        );
+        assert_eq!(validate(&message("", "", "hi")), Err(Rejection::EmptyName));
+        assert_eq!(validate(&message(&"a".repeat(65), "", "hi")), Err(Rejection::NameTooLong));
+        assert_eq!(validate(&message("erica", "", " \n ")), Err(Rejection::EmptyMessage));
+        assert_eq!(
+            validate(&message("erica", "", &"é".repeat(2001))),
+            Err(Rejection::MessageTooLong)
+        );
+
+        for address in ["hi@devcomp.xyz", "a.b+c@mail.example.org"] {
+            assert!(validate(&message("erica", address, "hi")).is_ok(), "{address}");
+        }
+        for address in
+            ["hi", "hi@localhost", "@devcomp.xyz", "hi@devcomp.", "a b@c.d", "<a@b.c>"]
+        {
+            assert_eq!(
+                validate(&message("erica", address, "hi")),
+                Err(Rejection::InvalidReplyTo),
+                "{address}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_send_limits() -> Result<()> {
+        let data_dir = temp_dir("contact-limits");
+        let contact = Contact::new(ContactConfig::default(), &data_dir);
+        let visitor = Visitor { address: Some([127, 0, 0, 1].into()), ..Visitor::default() };
+        let other = Visitor { address: Some([127, 0, 0, 2].into()), ..Visitor::default() };
+        let message = message("erica", "", "hi");
+
+        let opened_at = Instant::now();
+        let hour = Duration::from_secs(60 * 60);
+        let send =
+            |visitor, after| contact.send_at(visitor, &message, opened_at, opened_at + after);
+
+        assert_eq!(rejection(send(&visitor, Duration::from_secs(2))), Rejection::TooQuick);
+        send(&visitor, Duration::from_secs(10))?;
+        send(&visitor, Duration::from_secs(20))?;
+        assert_eq!(
+            rejection(send(&visitor, Duration::from_secs(30))),
+            Rejection::RateLimited { retry_after: hour - Duration::from_secs(20) }
+        );
+
+        // Limits are kept per address, and go by the day after the first few messages
+        send(&other, Duration::from_secs(30))?;
+        send(&visitor, hour * 2)?;
+        send(&visitor, hour * 4)?;
+        send(&visitor, hour * 6)?;
+        assert_eq!(
+            rejection(send(&visitor, hour * 8)),
+            Rejection::RateLimited { retry_after: hour * 16 + Duration::from_secs(10) }
+        );
+
+        // Addresses are forgotten once nothing they sent counts anymore
+        send(&other, hour * 48)?;
+        assert_eq!(contact.sent.lock().unwrap().len(), 1);
+
+        let delivered = fs::read_dir(data_dir.join("contact").join("new"))?.count();
+        assert_eq!(delivered, 7);
+
+        let _ = fs::remove_dir_all(data_dir);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_send_spool_and_webhook() -> Result<()> {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let router = Router::new().route(
+            "/hook",
+            post(move |body: String| {
+                let tx = tx.clone();
+                async move {
+                    let _ = tx.send(body);
+                }
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await?;
+        let webhook = format!("http://{}/hook", listener.local_addr()?);
+        tokio::spawn(async move { axum::serve(listener, router).await });
+
+        let data_dir = temp_dir("contact-spool");
+        let config = ContactConfig { mailbox: MailboxFormat::Spool, webhook: Some(webhook) };
+        let contact = Contact::new(config, &data_dir);
+        let visitor = Visitor {
+            user: String::from("erica"),
+            fingerprint: Some(String::from("SHA256:visitor")),
+            address: None,
+        };
+
+        let opened_at = Instant::now() - Duration::from_secs(60);
+        let body = "hi <!channel> & @here!\nFrom the terminal,\n>From me";
+        contact.send(
+            &visitor,
+            &message("Erica \"CompeyDev\"", "hi@devcomp.xyz", body),
+            opened_at,
+        )?;
+
+        let spool = fs::read_to_string(data_dir.join("contact.mbox"))?;
+        assert!(spool.starts_with("From ssh-portfolio@localhost "));
+        assert!(spool.contains("Subject: Message from Erica \"CompeyDev\"\n"));
+        assert!(spool.contains("Reply-To: \"Erica \\\"CompeyDev\\\"\" <hi@devcomp.xyz>\n"));
+        assert!(spool.contains("X-SSH-Fingerprint: SHA256:visitor\n"));
+        assert!(
+            spool.ends_with("\n\nhi <!channel> & @here!\n>From the terminal,\n>>From me\n\n")
+        );
+
+        let payload: serde_json::Value = serde_json::from_str(&rx.recv().await.unwrap())?;
+        assert_eq!(payload["message"], body);
+        assert_eq!(payload["reply_to"], "hi@devcomp.xyz");
+        assert_eq!(payload["user"], "erica");
+        assert_eq!(payload["address"], serde_json::Value::Null);
+        assert_eq!(payload["allowed_mentions"], serde_json::json!({ "parse": [] }));
+        assert!(payload["text"]
+            .as_str()
+            .unwrap()
+            .contains("hi &lt;!channel&gt; &amp; @\u{200b}here!\n"));
+
+        let _ = fs::remove_dir_all(data_dir);
+        Ok(())
+    }
+}
@@ -0,0 +1,443 @@
+//! Details about projects, such as stars and releases, fetched from the forge hosting them so
+//! the project cards stay up to date on their own.
+
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "forge")]
+pub use fetch::*;
+
+use crate::CONFIG;
+
+/// What the forge knows about a project's repository.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RepoMetadata {
+    pub description: Option<String>,
+    pub stars: Option<u64>,
+    /// The language most of the repository is written in.
+    pub language: Option<String>,
+    /// When the last commit on the default branch was made, as an RFC 3339 timestamp.
+    pub last_commit: Option<String>,
+    /// Tags of the most recent releases, newest first.
+    pub releases: Vec<String>,
+}
+
+/// Where a repository, as `owner/name`, can be visited on the configured forge, or on GitHub
+/// if there isn't one.
+pub fn repository_url(repo: &str) -> String {
+    let root = CONFIG.forge.as_ref().map(|forge| forge.web_url());
+    format!("{}/{repo}", root.as_deref().unwrap_or("https://github.com"))
+}
+
+impl RepoMetadata {
+    /// The day the last commit was made on, without the time.
+    pub fn last_commit_date(&self) -> Option<&str> {
+        self.last_commit.as_deref().and_then(|date| date.split('T').next())
+    }
+}
+
+#[cfg(feature = "forge")]
+mod fetch {
+    use std::collections::HashMap;
+    use std::path::PathBuf;
+    use std::sync::Mutex;
+    use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+    use color_eyre::eyre::eyre;
+    use color_eyre::Result;
+    use lazy_static::lazy_static;
+    use reqwest::header::{ACCEPT, AUTHORIZATION};
+    use serde::de::DeserializeOwned;
+    use serde::{Deserialize, Serialize};
+    use tracing::instrument;
+
+    use super::RepoMetadata;
+    use crate::config::{get_data_dir, ForgeConfig};
+    use crate::CONFIG;
+
+    /// How long details about a repository are kept around for before being fetched again.
+    /// Longer than the blog's, since unauthenticated requests to GitHub are limited to 60 an
+    /// hour and every repository takes three.
+    pub const CACHE_INVALIDATION_PERIOD: Duration = Duration::from_secs(6 * 60 * 60); // 6 hours
+    /// How long to wait before fetching a repository again after it failed, doubled with each
+    /// failure in a row up to `CACHE_INVALIDATION_PERIOD`. Also how often the cache is checked
+    /// for anything to fetch.
+    const FAILURE_BACKOFF: Duration = Duration::from_secs(60);
+    /// How long to wait on the forge before giving up on a request.
+    const REQUEST_TIMEOUT: Duration = Duration::from_secs(15);
+    /// How many release tags are kept for each repository.
+    const MAX_RELEASES: usize = 5;
+
+    lazy_static! {
+        pub(crate) static ref FORGE: Option<Forge> = CONFIG
+            .forge
+            .clone()
+            .map(|config| Forge::new(config, get_data_dir().join("forge-cache.json")));
+    }
+
+    /// Fetches details about repositories from a GitHub or Gitea/Forgejo compatible API, which
+    /// share the endpoints used here. Results are cached to disk, so they survive restarts and
+    /// are still shown if the forge can't be reached.
+    #[derive(Debug)]
+    pub struct Forge {
+        config: ForgeConfig,
+        client: reqwest::Client,
+        cache_path: PathBuf,
+        cache: Mutex<HashMap<String, CachedMetadata>>,
+    }
+
+    #[derive(Clone, Debug, Serialize, Deserialize)]
+    struct CachedMetadata {
+        /// When the repository was last fetched, whether or not that worked, in seconds since
+        /// the unix epoch as an `Instant` can't be written to disk.
+        fetched_at: u64,
+        /// What was fetched last time it worked, kept around when fetching again fails.
+        metadata: Option<RepoMetadata>,
+        /// How many times in a row fetching failed.
+        #[serde(default)]
+        failures: u32,
+    }
+
+    impl CachedMetadata {
+        fn is_stale(&self) -> bool {
+            let period = match self.failures {
+                0 => CACHE_INVALIDATION_PERIOD,
+                failures => FAILURE_BACKOFF
+                    .saturating_mul(2u32.saturating_pow(failures - 1))
+                    .min(CACHE_INVALIDATION_PERIOD),
+            };
+
+            now().saturating_sub(self.fetched_at) > period.as_secs()
+        }
+    }
+
+    #[derive(Deserialize)]
+    struct Repository {
+        description: Option<String>,
+        // Gitea and Forgejo call it `stars_count`
+        #[serde(alias = "stars_count")]
+        stargazers_count: u64,
+        language: Option<String>,
+    }
+
+    #[derive(Deserialize)]
+    struct Commit {
+        commit: CommitDetails,
+    }
+
+    #[derive(Deserialize)]
+    struct CommitDetails {
+        committer: Signature,
+    }
+
+    #[derive(Deserialize)]
+    struct Signature {
+        date: String,
+    }
+
+    #[derive(Deserialize)]
+    struct Release {
+        tag_name: String,
+    }
+
+    impl Forge {
+        pub fn new(config: ForgeConfig, cache_path: PathBuf) -> Self {
+            let cache = std::fs::read(&cache_path)
+                .ok()
+                .and_then(|cache| serde_json::from_slice(&cache).ok())
+                .unwrap_or_default();
+
+            Self {
+                config,
+                client: reqwest::Client::builder()
+                    .timeout(REQUEST_TIMEOUT)
+                    // GitHub rejects requests without a user agent
+                    .user_agent(concat!(
+                        env!("CARGO_PKG_NAME"),
+                        "/",
+                        env!("CARGO_PKG_VERSION")
+                    ))
+                    .build()
+                    .expect("Failed to build HTTP client"),
+                cache_path,
+                cache: Mutex::new(cache),
+            }
+        }
+
+        /// Gets details about each of the given repositories, as `owner/name`, as they were
+        /// last fetched rather than waiting on the forge. Repositories which were never
+        /// fetched are left out.
+        pub fn cached(&self, repos: &[&str]) -> HashMap<String, RepoMetadata> {
+            let cache = self.cache.lock().map(|cache| cache.clone()).unwrap_or_default();
+            repos
+                .iter()
+                .filter_map(|repo| {
+                    Some((repo.to_string(), cache.get(*repo)?.metadata.clone()?))
+                })
+                .collect()
+        }
+
+        /// Fetches the given repositories which are not cached or stale, backing off from
+        /// those which failed to be fetched.
+        #[instrument(level = "debug", skip(self), fields(url = %self.config.url))]
+        async fn refresh(&self, repos: &[&str]) {
+            let cache = self.cache.lock().map(|cache| cache.clone()).unwrap_or_default();
+            let stale = repos
+                .iter()
+                .filter(|repo| cache.get(**repo).is_none_or(CachedMetadata::is_stale))
+                .collect::<Vec<_>>();
+
+            if !stale.is_empty() {
+                tracing::info!("{} repositories not cached or stale, fetching", stale.len());
+                let fetched = futures::future::join_all(stale.iter().map(|repo| async move {
+                    let result = self.fetch(repo).await;
+                    (repo, result)
+                }))
+                .await;
+
+                if let Ok(mut cache) = self.cache.lock() {
+                    for (repo, result) in fetched {
+                        let previous = cache.remove(**repo);
+                        let cached = match result {
+                            Ok(metadata) => CachedMetadata {
+                                fetched_at: now(),
+                                metadata: Some(metadata),
+                                failures: 0,
+                            },
+                            Err(err) => {
+                                tracing::warn!("Failed to fetch {repo}: {err}");
+                                let (metadata, failures) = previous
+                                    .map_or((None, 0), |cached| {
+                                        (cached.metadata, cached.failures)
+                                    });
+                                CachedMetadata {
+                                    fetched_at: now(),
+                                    metadata,
+                                    failures: failures + 1,
+                                }
+                            }
+                        };
+
+                        cache.insert(repo.to_string(), cached);
+                    }
+
+                    if let Err(err) = self.save(&cache) {
+                        tracing::warn!("Failed to write forge cache: {err}");
+                    }
+                }
+            }
+        }
+
+        /// Fetches the details about a repository, leaving out those from endpoints which
+        /// failed, such as the commits of an empty repository on Gitea. Only fails if they all
+        /// did.
+        async fn fetch(&self, repo: &str) -> Result<RepoMetadata> {
+            let limit = MAX_RELEASES.to_string();
+            let (repository, commits, releases) = tokio::join!(
+                self.get::<Repository>(&format!("repos/{repo}"), &[]),
+                // GitHub pages with `per_page`, Gitea and Forgejo with `limit`
+                self.get::<Vec<Commit>>(
+                    &format!("repos/{repo}/commits"),
+                    &[("per_page", "1"), ("limit", "1")]
+                ),
+                self.get::<Vec<Release>>(
+                    &format!("repos/{repo}/releases"),
+                    &[("per_page", &limit), ("limit", &limit)],
+                ),
+            );
+
+            if let (Err(err), Err(_), Err(_)) = (&repository, &commits, &releases) {
+                return Err(eyre!("{err}"));
+            }
+
+            let mut metadata = RepoMetadata::default();
+            match repository {
+                Ok(repository) => {
+                    metadata.description =
+                        repository.description.filter(|desc| !desc.is_empty());
+                    metadata.stars = Some(repository.stargazers_count);
+                    metadata.language = repository.language.filter(|lang| !lang.is_empty());
+                }
+                Err(err) => tracing::warn!("Failed to fetch {repo}: {err}"),
+            }
+            match commits {
+                Ok(commits) => {
+                    metadata.last_commit =
+                        commits.into_iter().next().map(|commit| commit.commit.committer.date);
+                }
+                Err(err) => tracing::warn!("Failed to fetch commits of {repo}: {err}"),
+            }
+            match releases {
+                Ok(releases) => {
+                    metadata.releases = releases
+                        .into_iter()
+                        .take(MAX_RELEASES)
+                        .map(|release| release.tag_name)
+                        .collect();
+                }
+                Err(err) => tracing::warn!("Failed to fetch releases of {repo}: {err}"),
+            }
+
+            Ok(metadata)
+        }
+
+        async fn get<T: DeserializeOwned>(
+            &self,
+            path: &str,
+            query: &[(&str, &str)],
+        ) -> Result<T> {
+            let url = format!("{}/{path}", self.config.url.trim_end_matches('/'));
+            let mut request =
+                self.client.get(url).query(query).header(ACCEPT, "application/json");
+            if let Some(token) = &self.config.token {
+                // Understood by GitHub as well as Gitea and Forgejo
+                request = request.header(AUTHORIZATION, format!("token {token}"));
+            }
+
+            let body = request.send().await?.error_for_status()?.bytes().await?;
+            Ok(serde_json::from_slice(&body)?)
+        }
+
+        fn save(&self, cache: &HashMap<String, CachedMetadata>) -> Result<()> {
+            if let Some(parent) = self.cache_path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+
+            std::fs::write(&self.cache_path, serde_json::to_vec(cache)?)?;
+            Ok(())
+        }
+    }
+
+    fn now() -> u64 {
+        SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs()
+    }
+
+    /// Fetches details about the projects ahead of the first session and keeps them fresh,
+    /// as sessions only ever show what is cached rather than waiting on the forge.
+    #[instrument(level = "debug")]
+    pub async fn warm_cache() {
+        if let Some(forge) = &*FORGE {
+            let cards = crate::components::Content::projects_content();
+            let repos = cards.iter().map(|card| card.repository).collect::<Vec<_>>();
+            loop {
+                forge.refresh(&repos).await;
+                tokio::time::sleep(FAILURE_BACKOFF).await;
+            }
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::sync::Arc;
+
+        use axum::http::{StatusCode, Uri};
+        use axum::{Json, Router};
+        use pretty_assertions::assert_eq;
+        use serde_json::{json, Value};
+
+        use super::*;
+
+        /// Serves a Gitea-style repository at `erica/gitea`, an empty one at `erica/empty`,
+        /// and a GitHub-style one at `erica/github`, counting the requests made.
+        async fn mock_forge(requests: Arc<AtomicUsize>) -> String {
+            let router = Router::new().fallback(move |uri: Uri| {
+                requests.fetch_add(1, Ordering::SeqCst);
+                async move { respond(uri.path()) }
+            });
+            let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let addr = listener.local_addr().unwrap();
+            tokio::spawn(async move { axum::serve(listener, router).await });
+
+            format!("http://{addr}")
+        }
+
+        fn respond(path: &str) -> Result<Json<Value>, StatusCode> {
+            match path {
+                "/repos/erica/gitea" => Ok(Json(json!({
+                    "description": "Fresh from the forge",
+                    "stars_count": 12,
+                    "language": "",
+                }))),
+                "/repos/erica/empty" => Ok(Json(json!({
+                    "description": "Nothing here yet",
+                    "stars_count": 0,
+                    "language": null,
+                }))),
+                // What Gitea responds with for the commits of an empty repository
+                "/repos/erica/empty/commits" => Err(StatusCode::CONFLICT),
+                "/repos/erica/github" => Ok(Json(json!({
+                    "description": null,
+                    "stargazers_count": 3,
+                    "language": "Rust",
+                }))),
+                path if path.ends_with("/commits") => Ok(Json(json!([
+                    { "commit": { "committer": { "date": "2025-03-14T12:00:00Z" } } }
+                ]))),
+                path if path.ends_with("/releases") => Ok(Json(Value::Array(
+                    (0..8).rev().map(|i| json!({ "tag_name": format!("v0.{i}.0") })).collect(),
+                ))),
+                _ => Err(StatusCode::NOT_FOUND),
+            }
+        }
+
+        #[tokio::test]
+        async fn test_forge_metadata() {
+            let requests = Arc::new(AtomicUsize::new(0));
+            let config = ForgeConfig { url: mock_forge(requests.clone()).await, token: None };
+            let cache_path = std::env::temp_dir()
+                .join(format!("ssh-portfolio-forge-{}", std::process::id()))
+                .join("forge-cache.json");
+
+            let repos = ["erica/gitea", "erica/github", "erica/empty", "erica/missing"];
+            let forge = Forge::new(config.clone(), cache_path.clone());
+            forge.refresh(&repos).await;
+            let metadata = forge.cached(&repos);
+            assert_eq!(
+                metadata["erica/gitea"],
+                RepoMetadata {
+                    description: Some(String::from("Fresh from the forge")),
+                    stars: Some(12),
+                    language: None,
+                    last_commit: Some(String::from("2025-03-14T12:00:00Z")),
+                    releases: vec!["v0.7.0", "v0.6.0", "v0.5.0", "v0.4.0", "v0.3.0"]
+                        .into_iter()
+                        .map(String::from)
+                        .collect(),
+                }
+            );
+            assert_eq!(metadata["erica/github"].description, None);
+            assert_eq!(metadata["erica/github"].stars, Some(3));
+            assert_eq!(metadata["erica/github"].language.as_deref(), Some("Rust"));
+            assert_eq!(metadata["erica/github"].last_commit_date(), Some("2025-03-14"));
+            // A failing endpoint only leaves out what it would've returned
+            assert_eq!(metadata["erica/empty"].stars, Some(0));
+            assert_eq!(metadata["erica/empty"].last_commit, None);
+            assert_eq!(metadata["erica/empty"].releases.len(), 5);
+            assert!(!metadata.contains_key("erica/missing"));
+
+            // Cached repositories are not fetched again, even after a restart, and neither are
+            // those which just failed to be
+            let fetched = requests.load(Ordering::SeqCst);
+            let forge = Forge::new(config, cache_path.clone());
+            assert_eq!(forge.cached(&repos), metadata);
+            forge.refresh(&repos).await;
+            assert_eq!(requests.load(Ordering::SeqCst), fetched);
+            assert_eq!(forge.cached(&repos), metadata);
+
+            // Failing again backs off for longer
+            let mut stale = forge.cache.lock().unwrap().clone();
+            let missing = stale.get_mut("erica/missing").unwrap();
+            assert_eq!(missing.failures, 1);
+            missing.fetched_at -= FAILURE_BACKOFF.as_secs() + 1;
+            assert!(missing.is_stale());
+            *forge.cache.lock().unwrap() = stale;
+            forge.refresh(&repos).await;
+            assert_eq!(requests.load(Ordering::SeqCst), fetched + 3);
+            let missing = forge.cache.lock().unwrap()["erica/missing"].clone();
+            assert_eq!(missing.failures, 2);
+            assert!(!missing.is_stale());
+
+            let _ = std::fs::remove_dir_all(cache_path.parent().unwrap());
+        }
+    }
+}
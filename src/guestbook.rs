@@ -0,0 +1,494 @@
+//! A guestbook visitors can sign with the SSH key they connected with, stored in an SQLite
+//! database within the data directory.
+
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use color_eyre::eyre::eyre;
+use color_eyre::Result;
+use lazy_static::lazy_static;
+use rusqlite::{params, Connection, OptionalExtension, Row};
+
+use crate::cli::GuestbookCommand;
+use crate::config::get_data_dir;
+
+/// The most characters a message may have.
+pub const MAX_MESSAGE_LENGTH: usize = 280;
+/// The most characters a display name may have.
+pub const MAX_NAME_LENGTH: usize = 32;
+/// How many entries a key may write within each period.
+const RATE_LIMITS: [(Duration, usize); 2] = [
+    (Duration::from_secs(10 * 60), 1),      // 10 minutes
+    (Duration::from_secs(24 * 60 * 60), 3), // 1 day
+];
+/// Words which may not appear in names or messages. Matched against whole words, after
+/// undoing common letter substitutions.
+const BLOCKED_WORDS: &[&str] = &[
+    "asshole",
+    "bastard",
+    "bitch",
+    "cunt",
+    "dick",
+    "fag",
+    "faggot",
+    "fuck",
+    "fucker",
+    "fucking",
+    "motherfucker",
+    "nigga",
+    "nigger",
+    "retard",
+    "shit",
+    "slut",
+    "whore",
+];
+
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS entries (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        fingerprint TEXT NOT NULL,
+        name TEXT NOT NULL,
+        message TEXT NOT NULL,
+        created_at INTEGER NOT NULL,
+        hidden INTEGER NOT NULL DEFAULT 0
+    );
+    CREATE INDEX IF NOT EXISTS entries_by_key ON entries (fingerprint, created_at);
+    CREATE TABLE IF NOT EXISTS bans (
+        fingerprint TEXT PRIMARY KEY,
+        banned_at INTEGER NOT NULL
+    );
+";
+
+/// Entries along with their date, and whether they're hidden by moderation in any way.
+const ENTRIES: &str = "
+    SELECT id, name, message, fingerprint, created_at,
+        strftime('%Y-%m-%d', created_at, 'unixepoch') AS date,
+        hidden OR fingerprint IN (SELECT fingerprint FROM bans) AS hidden
+    FROM entries
+";
+
+lazy_static! {
+    pub(crate) static ref GUESTBOOK: Option<Arc<Guestbook>> =
+        Guestbook::open(&get_data_dir().join("guestbook.db"))
+            .inspect_err(|err| tracing::error!("Failed to open guestbook: {err}"))
+            .ok()
+            .map(Arc::new);
+}
+
+/// A message left in the guestbook.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Entry {
+    pub id: i64,
+    pub name: String,
+    pub message: String,
+    /// The SHA-256 fingerprint of the key the entry was signed with.
+    pub fingerprint: String,
+    /// When the entry was written, as seconds since the unix epoch.
+    pub created_at: i64,
+    /// When the entry was written, formatted as `YYYY-MM-DD`.
+    pub date: String,
+    /// Whether a moderator hid the entry, or banned the key it was signed with.
+    pub hidden: bool,
+}
+
+impl Entry {
+    fn from_row(row: &Row) -> rusqlite::Result<Self> {
+        Ok(Self {
+            id: row.get("id")?,
+            name: row.get("name")?,
+            message: row.get("message")?,
+            fingerprint: row.get("fingerprint")?,
+            created_at: row.get("created_at")?,
+            date: row.get("date")?,
+            hidden: row.get("hidden")?,
+        })
+    }
+}
+
+/// Why an entry was not accepted, shown to the visitor who tried to write it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Rejection {
+    /// The visitor didn't authenticate with a key, so there is nothing to sign with.
+    Anonymous,
+    Banned,
+    EmptyName,
+    NameTooLong,
+    EmptyMessage,
+    MessageTooLong,
+    Profanity,
+    /// The key was used to write too many entries recently.
+    RateLimited {
+        retry_after: Duration,
+    },
+}
+
+impl std::fmt::Display for Rejection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Anonymous => write!(f, "connect with an ssh key to sign the guestbook"),
+            Self::Banned => write!(f, "this key may no longer sign the guestbook"),
+            Self::EmptyName => write!(f, "a name is needed"),
+            Self::NameTooLong => write!(f, "names are at most {MAX_NAME_LENGTH} characters"),
+            Self::EmptyMessage => write!(f, "a message is needed"),
+            Self::MessageTooLong => {
+                write!(f, "messages are at most {MAX_MESSAGE_LENGTH} characters")
+            }
+            Self::Profanity => write!(f, "let's keep it friendly"),
+            Self::RateLimited { retry_after } => {
+                let minutes = retry_after.as_secs().div_ceil(60);
+                write!(f, "slow down! try again in {minutes} minute(s)")
+            }
+        }
+    }
+}
+
+impl std::error::Error for Rejection {}
+
+#[derive(Debug)]
+pub struct Guestbook {
+    conn: Mutex<Connection>,
+}
+
+impl Guestbook {
+    /// Opens the database at the given path, creating it if it doesn't exist yet.
+    pub fn open(path: &Path) -> Result<Self> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        let conn = Connection::open(path)?;
+        // Lets moderation commands run while the server has the database open
+        conn.busy_timeout(Duration::from_secs(5))?;
+        conn.pragma_update_and_check(None, "journal_mode", "WAL", |_| Ok(()))?;
+        Self::with_connection(conn)
+    }
+
+    /// Opens a database which only lives as long as the guestbook, for tests.
+    pub fn open_in_memory() -> Result<Self> {
+        Self::with_connection(Connection::open_in_memory()?)
+    }
+
+    fn with_connection(conn: Connection) -> Result<Self> {
+        conn.execute_batch(SCHEMA)?;
+        Ok(Self { conn: Mutex::new(conn) })
+    }
+
+    fn conn(&self) -> Result<std::sync::MutexGuard<'_, Connection>> {
+        self.conn.lock().map_err(|_| eyre!("Guestbook database lock poisoned"))
+    }
+
+    /// The entries in the guestbook, newest first. Hidden ones are only included if asked for.
+    pub fn entries(&self, include_hidden: bool) -> Result<Vec<Entry>> {
+        let conn = self.conn()?;
+        let mut statement = conn.prepare(&format!(
+            "SELECT * FROM ({ENTRIES}) WHERE ?1 OR NOT hidden ORDER BY created_at DESC, id \
+             DESC"
+        ))?;
+
+        let entries = statement
+            .query_map([include_hidden], Entry::from_row)?
+            .collect::<rusqlite::Result<_>>()?;
+        Ok(entries)
+    }
+
+    /// Writes an entry signed with the key with the given fingerprint, if it passes the
+    /// limits. Rejected entries fail with a [`Rejection`].
+    pub fn sign(&self, fingerprint: Option<&str>, name: &str, message: &str) -> Result<Entry> {
+        self.sign_at(fingerprint, name, message, now())
+    }
+
+    fn sign_at(
+        &self,
+        fingerprint: Option<&str>,
+        name: &str,
+        message: &str,
+        now: i64,
+    ) -> Result<Entry> {
+        let fingerprint = fingerprint.ok_or(Rejection::Anonymous)?;
+        let (name, message) = validate(name, message)?;
+
+        let conn = self.conn()?;
+        let banned = conn
+            .query_row("SELECT 1 FROM bans WHERE fingerprint = ?1", [fingerprint], |_| Ok(()))
+            .optional()?
+            .is_some();
+        if banned {
+            return Err(Rejection::Banned.into());
+        }
+
+        for (period, limit) in RATE_LIMITS {
+            let since = now - period.as_secs() as i64;
+            let recent = conn
+                .prepare(
+                    "SELECT created_at FROM entries
+                    WHERE fingerprint = ?1 AND created_at > ?2
+                    ORDER BY created_at DESC",
+                )?
+                .query_map(params![fingerprint, since], |row| row.get::<_, i64>(0))?
+                .collect::<rusqlite::Result<Vec<_>>>()?;
+
+            if let Some(oldest) = recent.get(limit - 1) {
+                // Another entry may be written once the oldest one counted leaves the period
+                let retry_after = oldest + period.as_secs() as i64 - now;
+                let retry_after = Duration::from_secs(retry_after.max(1) as u64);
+                return Err(Rejection::RateLimited { retry_after }.into());
+            }
+        }
+
+        conn.execute(
+            "INSERT INTO entries (fingerprint, name, message, created_at) VALUES (?1, ?2, \
+             ?3, ?4)",
+            params![fingerprint, name, message, now],
+        )?;
+        let id = conn.last_insert_rowid();
+        drop(conn);
+
+        tracing::info!(%fingerprint, "Guestbook signed by {name}");
+        self.entry(id)?.ok_or_else(|| eyre!("Guestbook entry {id} vanished after writing it"))
+    }
+
+    fn entry(&self, id: i64) -> Result<Option<Entry>> {
+        let conn = self.conn()?;
+        let entry = conn
+            .query_row(&format!("{ENTRIES} WHERE id = ?1"), [id], Entry::from_row)
+            .optional()?;
+        Ok(entry)
+    }
+
+    /// Hides or unhides an entry, returning whether it exists.
+    pub fn set_hidden(&self, id: i64, hidden: bool) -> Result<bool> {
+        let updated = self
+            .conn()?
+            .execute("UPDATE entries SET hidden = ?2 WHERE id = ?1", params![id, hidden])?;
+        Ok(updated > 0)
+    }
+
+    /// Deletes an entry for good, returning whether it existed.
+    pub fn delete(&self, id: i64) -> Result<bool> {
+        Ok(self.conn()?.execute("DELETE FROM entries WHERE id = ?1", [id])? > 0)
+    }
+
+    /// Stops a key from signing the guestbook, and hides the entries it signed.
+    pub fn ban(&self, fingerprint: &str) -> Result<()> {
+        self.conn()?.execute(
+            "INSERT OR IGNORE INTO bans (fingerprint, banned_at) VALUES (?1, ?2)",
+            params![fingerprint, now()],
+        )?;
+        Ok(())
+    }
+
+    /// Lets a banned key sign the guestbook again, returning whether it was banned.
+    pub fn unban(&self, fingerprint: &str) -> Result<bool> {
+        Ok(self.conn()?.execute("DELETE FROM bans WHERE fingerprint = ?1", [fingerprint])? > 0)
+    }
+}
+
+/// Checks a name and message against the limits, returning them trimmed and without any
+/// control or format characters, which could mess with the terminals of other visitors or
+/// reorder what they see.
+fn validate(name: &str, message: &str) -> Result<(String, String), Rejection> {
+    let clean = |text: &str| {
+        text.chars().filter(|&c| !c.is_control() && !is_format(c)).collect::<String>()
+    };
+    let (name, message) = (clean(name), clean(message));
+    let (name, message) = (name.trim(), message.trim());
+
+    if name.is_empty() {
+        return Err(Rejection::EmptyName);
+    } else if name.chars().count() > MAX_NAME_LENGTH {
+        return Err(Rejection::NameTooLong);
+    } else if message.is_empty() {
+        return Err(Rejection::EmptyMessage);
+    } else if message.chars().count() > MAX_MESSAGE_LENGTH {
+        return Err(Rejection::MessageTooLong);
+    } else if is_profane(name) || is_profane(message) {
+        return Err(Rejection::Profanity);
+    }
+
+    Ok((name.to_string(), message.to_string()))
+}
+
+/// Whether the character is in the Unicode format (`Cf`) category, such as the bidirectional
+/// overrides and zero width characters.
+fn is_format(c: char) -> bool {
+    matches!(
+        c,
+        '\u{ad}'
+            | '\u{600}'..='\u{605}'
+            | '\u{61c}'
+            | '\u{6dd}'
+            | '\u{70f}'
+            | '\u{890}'..='\u{891}'
+            | '\u{8e2}'
+            | '\u{180e}'
+            | '\u{200b}'..='\u{200f}'
+            | '\u{202a}'..='\u{202e}'
+            | '\u{2060}'..='\u{2064}'
+            | '\u{2066}'..='\u{206f}'
+            | '\u{feff}'
+            | '\u{fff9}'..='\u{fffb}'
+            | '\u{110bd}'
+            | '\u{110cd}'
+            | '\u{13430}'..='\u{1343f}'
+            | '\u{1bca0}'..='\u{1bca3}'
+            | '\u{1d173}'..='\u{1d17a}'
+            | '\u{e0001}'
+            | '\u{e0020}'..='\u{e007f}'
+    )
+}
+
+fn is_profane(text: &str) -> bool {
+    let text = text
+        .to_lowercase()
+        .chars()
+        .map(|c| match c {
+            '0' => 'o',
+            '1' | '!' => 'i',
+            '3' => 'e',
+            '4' | '@' => 'a',
+            '5' | '$' => 's',
+            '7' => 't',
+            c => c,
+        })
+        .collect::<String>();
+
+    text.split(|c: char| !c.is_alphabetic()).any(|word| {
+        BLOCKED_WORDS.contains(&word) || BLOCKED_WORDS.contains(&word.trim_end_matches('s'))
+    })
+}
+
+fn now() -> i64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs() as i64
+}
+
+/// Runs a moderation command against the guestbook in the data directory, printing what it
+/// did.
+pub fn moderate(command: &GuestbookCommand) -> Result<()> {
+    let guestbook = Guestbook::open(&get_data_dir().join("guestbook.db"))?;
+    let found = |found: bool, what: String| {
+        if found {
+            println!("{what}");
+            Ok(())
+        } else {
+            Err(eyre!("No such entry or key"))
+        }
+    };
+
+    match command {
+        GuestbookCommand::List { all } => {
+            for entry in guestbook.entries(*all)? {
+                let hidden = if entry.hidden { " (hidden)" } else { "" };
+                println!(
+                    "#{} {} {} [{}]{hidden}\n    {}",
+                    entry.id, entry.date, entry.name, entry.fingerprint, entry.message
+                );
+            }
+            Ok(())
+        }
+        GuestbookCommand::Hide { id } => {
+            found(guestbook.set_hidden(*id, true)?, format!("Hid entry #{id}"))
+        }
+        GuestbookCommand::Unhide { id } => {
+            found(guestbook.set_hidden(*id, false)?, format!("Unhid entry #{id}"))
+        }
+        GuestbookCommand::Delete { id } => {
+            found(guestbook.delete(*id)?, format!("Deleted entry #{id}"))
+        }
+        GuestbookCommand::Ban { fingerprint } => {
+            guestbook.ban(fingerprint)?;
+            found(true, format!("Banned {fingerprint}"))
+        }
+        GuestbookCommand::Unban { fingerprint } => {
+            found(guestbook.unban(fingerprint)?, format!("Unbanned {fingerprint}"))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    const KEY: Option<&str> = Some("SHA256:visitor");
+    const NOW: i64 = 1_741_977_000; // 2025-03-14
+
+    fn rejection(result: Result<Entry>) -> Rejection {
+        result.unwrap_err().downcast::<Rejection>().unwrap()
+    }
+
+    #[test]
+    fn test_validate() {
+        assert_eq!(
+            validate("  erica ", "hi\x1b[2J there"),
+            Ok((String::from("erica"), String::from("hi[2J there")))
+        );
+        assert_eq!(
+            validate("\u{202e}erica", "hi\u{200b} there\u{2066}"),
+            Ok((String::from("erica"), String::from("hi there")))
+        );
+        assert_eq!(validate("\u{202e}", "hi"), Err(Rejection::EmptyName));
+        assert_eq!(validate(" ", "hi"), Err(Rejection::EmptyName));
+        assert_eq!(validate("erica", "\n"), Err(Rejection::EmptyMessage));
+        assert_eq!(validate(&"a".repeat(33), "hi"), Err(Rejection::NameTooLong));
+        assert_eq!(validate("erica", &"é".repeat(281)), Err(Rejection::MessageTooLong));
+        assert_eq!(validate("erica", "what the FUCK"), Err(Rejection::Profanity));
+        assert_eq!(validate("sh1t", "hi"), Err(Rejection::Profanity));
+        assert!(validate("erica", "scunthorpe is a dickens of a town").is_ok());
+    }
+
+    #[test]
+    fn test_sign() -> Result<()> {
+        let guestbook = Guestbook::open_in_memory()?;
+        assert_eq!(
+            rejection(guestbook.sign_at(None, "erica", "hi", NOW)),
+            Rejection::Anonymous
+        );
+
+        let entry = guestbook.sign_at(KEY, "erica", "hello!", NOW)?;
+        assert_eq!(entry.date, "2025-03-14");
+        assert_eq!(entry.fingerprint, "SHA256:visitor");
+        assert_eq!(guestbook.entries(false)?, vec![entry.clone()]);
+
+        // Rate limited per key, both in the short and long run
+        assert_eq!(
+            rejection(guestbook.sign_at(KEY, "erica", "again", NOW + 60)),
+            Rejection::RateLimited { retry_after: Duration::from_secs(9 * 60) }
+        );
+        guestbook.sign_at(Some("SHA256:other"), "someone", "hey", NOW + 60)?;
+        guestbook.sign_at(KEY, "erica", "again", NOW + 11 * 60)?;
+        guestbook.sign_at(KEY, "erica", "and again", NOW + 22 * 60)?;
+        assert!(matches!(
+            rejection(guestbook.sign_at(KEY, "erica", "one more", NOW + 33 * 60)),
+            Rejection::RateLimited { .. }
+        ));
+
+        let messages = |include_hidden| -> Result<Vec<String>> {
+            Ok(guestbook
+                .entries(include_hidden)?
+                .into_iter()
+                .map(|entry| entry.message)
+                .collect())
+        };
+        assert_eq!(messages(false)?, vec!["and again", "again", "hey", "hello!"]);
+
+        // Moderation
+        assert!(guestbook.set_hidden(entry.id, true)?);
+        assert!(guestbook.delete(entry.id + 1)?);
+        assert!(!guestbook.delete(entry.id + 1)?);
+        assert_eq!(messages(false)?, vec!["and again", "again"]);
+        assert_eq!(messages(true)?, vec!["and again", "again", "hello!"]);
+
+        guestbook.ban("SHA256:visitor")?;
+        assert_eq!(messages(false)?, Vec::<String>::new());
+        assert_eq!(
+            rejection(guestbook.sign_at(KEY, "erica", "hi", NOW * 2)),
+            Rejection::Banned
+        );
+        assert!(guestbook.unban("SHA256:visitor")?);
+        assert_eq!(messages(false)?, vec!["and again", "again"]);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,514 @@
+//! Runs the whole app headlessly for snapshot tests, feeding it input through the same channels
+//! an [`SshSession`](crate::ssh::SshSession) would. Components can also be driven and drawn on
+//! their own, to test them without going through the rest of the app.
+
+use std::collections::VecDeque;
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use color_eyre::eyre::{eyre, Result};
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::backend::{Backend, TestBackend, WindowSize};
+use ratatui::buffer::{Buffer, Cell};
+use ratatui::layout::{Position, Size};
+use tokio::sync::{mpsc, Mutex, RwLock};
+use tokio::task::JoinHandle;
+use tokio::time::{sleep, timeout, Instant};
+
+use crate::action::Action;
+use crate::app::App;
+use crate::components::Component;
+#[cfg(feature = "contact")]
+use crate::config::ContactConfig;
+#[cfg(feature = "contact")]
+use crate::contact::Contact;
+#[cfg(feature = "guestbook")]
+use crate::guestbook::Guestbook;
+#[cfg(any(feature = "guestbook", feature = "contact"))]
+use crate::ssh::Visitor;
+use crate::tui::backend::SessionBackend;
+use crate::tui::pacing::FramePacer;
+use crate::tui::palette::ColorDepth;
+use crate::tui::terminal::TerminalInfo;
+use crate::tui::terminfo::Glyphs;
+use crate::tui::{Event, Terminal};
+
+/// How long the app has to go without rendering for it to be considered settled.
+const SETTLE_PERIOD: Duration = Duration::from_millis(50);
+/// How long the app has to render a frame in response to input.
+const FRAME_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Environment variable which, when set, makes snapshot assertions write snapshots instead.
+const UPDATE_SNAPSHOTS_ENV: &str = "UPDATE_SNAPSHOTS";
+
+/// A backend which draws into an in-memory buffer, counting the frames drawn.
+#[derive(Debug)]
+pub struct HeadlessBackend {
+    inner: TestBackend,
+    frames: Arc<AtomicUsize>,
+    pacer: Arc<FramePacer>,
+}
+
+impl HeadlessBackend {
+    pub fn new(width: u16, height: u16) -> Self {
+        Self {
+            inner: TestBackend::new(width, height),
+            frames: Arc::default(),
+            pacer: Arc::default(),
+        }
+    }
+}
+
+impl Backend for HeadlessBackend {
+    fn size(&self) -> io::Result<Size> {
+        self.inner.size()
+    }
+
+    fn draw<'a, I>(&mut self, content: I) -> io::Result<()>
+    where
+        I: Iterator<Item = (u16, u16, &'a Cell)>, {
+        self.inner.draw(content)
+    }
+
+    fn hide_cursor(&mut self) -> io::Result<()> {
+        self.inner.hide_cursor()
+    }
+
+    fn show_cursor(&mut self) -> io::Result<()> {
+        self.inner.show_cursor()
+    }
+
+    fn get_cursor_position(&mut self) -> io::Result<Position> {
+        self.inner.get_cursor_position()
+    }
+
+    fn set_cursor_position<P: Into<Position>>(&mut self, position: P) -> io::Result<()> {
+        self.inner.set_cursor_position(position)
+    }
+
+    fn clear(&mut self) -> io::Result<()> {
+        self.inner.clear()
+    }
+
+    fn window_size(&mut self) -> io::Result<WindowSize> {
+        self.inner.window_size()
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.frames.fetch_add(1, Ordering::Relaxed);
+        Backend::flush(&mut self.inner)
+    }
+}
+
+impl Write for HeadlessBackend {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        // Escape sequences written outside of frames, such as entering the alternate screen
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl SessionBackend for HeadlessBackend {
+    fn set_dims(&mut self, (width, height): (u16, u16)) {
+        self.inner.resize(width, height);
+    }
+
+    fn set_color_depth(&mut self, _color_depth: ColorDepth) {}
+
+    fn set_glyphs(&mut self, _glyphs: Glyphs) {}
+
+    fn set_raw_mode(&mut self, _raw: bool) {}
+
+    fn pacer(&self) -> Arc<FramePacer> {
+        Arc::clone(&self.pacer)
+    }
+}
+
+/// Drives a running [`App`], rendering to a [`HeadlessBackend`].
+pub struct Harness {
+    terminal: Arc<Mutex<Terminal<HeadlessBackend>>>,
+    frames: Arc<AtomicUsize>,
+    keystroke_tx: mpsc::UnboundedSender<Vec<u8>>,
+    resize_tx: mpsc::UnboundedSender<(u16, u16)>,
+    task: JoinHandle<Result<()>>,
+}
+
+impl Harness {
+    /// Starts the app in a terminal of the given size, returning once it has rendered.
+    pub async fn new(width: u16, height: u16) -> Result<Self> {
+        let mut terminal_info = TerminalInfo::default();
+        terminal_info.set_term("xterm-256color");
+        terminal_info.finish_probe();
+
+        let (keystroke_tx, keystroke_rx) = mpsc::unbounded_channel();
+        let (resize_tx, resize_rx) = mpsc::unbounded_channel();
+        let app = App::with_posts(
+            Arc::new(RwLock::new(terminal_info)),
+            4.0,
+            60.0,
+            keystroke_rx,
+            resize_rx,
+            #[cfg(feature = "blog")]
+            fixtures::posts(),
+        )?;
+
+        // Each harness gets a guestbook and mailbox of its own, written to with a key of its
+        // own
+        #[cfg(feature = "guestbook")]
+        let app = app.guestbook(Some(Arc::new(Guestbook::open_in_memory()?)));
+        #[cfg(feature = "contact")]
+        let app = app.contact(Arc::new(Contact::new(
+            ContactConfig::default(),
+            &std::env::temp_dir()
+                .join(format!("ssh-portfolio-harness-{}", std::process::id())),
+        )));
+        #[cfg(any(feature = "guestbook", feature = "contact"))]
+        let app = app.visitor(Arc::new(RwLock::new(Visitor {
+            user: String::from("visitor"),
+            fingerprint: Some(String::from("SHA256:harnesskey")),
+            address: Some([127, 0, 0, 1].into()),
+        })));
+
+        let backend = HeadlessBackend::new(width, height);
+        let frames = Arc::clone(&backend.frames);
+        let terminal = Arc::new(Mutex::new(Terminal::new(backend)?));
+
+        let app_terminal = Arc::clone(&terminal);
+        let task = tokio::task::spawn(async move {
+            let mut app = app;
+            app.run(app_terminal, Arc::new(RwLock::new(None))).await
+        });
+
+        let harness = Self { terminal, frames, keystroke_tx, resize_tx, task };
+        harness.settle(0).await?;
+        Ok(harness)
+    }
+
+    /// Sends keystrokes one by one, each as the bytes a terminal would send for it, then waits
+    /// for the app to render the result.
+    pub async fn press(&self, keys: &[&[u8]]) -> Result<()> {
+        let frames = self.frames.load(Ordering::Relaxed);
+        for key in keys {
+            self.keystroke_tx.send(key.to_vec())?;
+        }
+
+        self.settle(frames).await
+    }
+
+    /// Resizes the terminal as a client would, then waits for the app to render the result.
+    pub async fn resize(&self, width: u16, height: u16) -> Result<()> {
+        let frames = self.frames.load(Ordering::Relaxed);
+        self.resize_tx.send((width, height))?;
+        self.settle(frames).await
+    }
+
+    /// Waits for the app to render something of its own accord, such as once a timeout is up.
+    pub async fn wait(&self) -> Result<()> {
+        self.settle(self.frames.load(Ordering::Relaxed)).await
+    }
+
+    /// Waits until a frame after the given one has been rendered, and no more have been for
+    /// a while.
+    async fn settle(&self, after: usize) -> Result<()> {
+        let deadline = Instant::now() + FRAME_TIMEOUT;
+        let mut last = after;
+        loop {
+            if Instant::now() >= deadline {
+                return Err(eyre!("App did not settle in {FRAME_TIMEOUT:?}"));
+            }
+
+            if self.task.is_finished() {
+                return Err(eyre!("App exited while waiting for it to render"));
+            }
+
+            sleep(SETTLE_PERIOD).await;
+            let frames = self.frames.load(Ordering::Relaxed);
+            if frames > after && frames == last {
+                return Ok(());
+            }
+
+            last = frames;
+        }
+    }
+
+    /// The text currently on screen, with trailing whitespace trimmed from each line. The
+    /// version and branch are masked, so that snapshots don't change with every commit.
+    pub async fn screen(&self) -> String {
+        let terminal = self.terminal.lock().await;
+        text(terminal.backend().inner.buffer())
+            .replace(env!("PKG_FULL_VERSION"), "<version>")
+            .replace(env!("VERGEN_GIT_BRANCH"), "<branch>")
+    }
+
+    /// Compares the screen to the snapshot with the given name, under `src/snapshots`. Missing
+    /// or changed snapshots fail the test, unless `UPDATE_SNAPSHOTS` is set to write them.
+    pub async fn assert_snapshot(&self, name: &str) {
+        let screen = self.screen().await;
+        let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("src")
+            .join("snapshots")
+            .join(format!("{name}.txt"));
+
+        if std::env::var_os(UPDATE_SNAPSHOTS_ENV).is_some() {
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(&path, screen).unwrap();
+            return;
+        }
+
+        let snapshot = std::fs::read_to_string(&path).unwrap_or_else(|err| {
+            panic!(
+                "snapshot `{name}` could not be read ({err}), run with \
+                 {UPDATE_SNAPSHOTS_ENV}=1 to write it"
+            )
+        });
+        pretty_assertions::assert_eq!(
+            snapshot,
+            screen,
+            "snapshot `{name}` changed, rerun with {UPDATE_SNAPSHOTS_ENV}=1 to accept"
+        );
+    }
+
+    /// Quits the app, waiting for it to exit.
+    pub async fn quit(self) -> Result<()> {
+        // Ctrl-C
+        self.keystroke_tx.send(vec![0x03])?;
+        timeout(FRAME_TIMEOUT, self.task).await??
+    }
+}
+
+/// The text in a buffer, with trailing whitespace trimmed from each line.
+fn text(buffer: &Buffer) -> String {
+    let mut text = String::new();
+    for y in 0..buffer.area.height {
+        let mut line = String::new();
+        let mut skip = 0;
+        for x in 0..buffer.area.width {
+            let symbol = buffer[(x, y)].symbol();
+            if skip > 0 {
+                // Cells hidden behind the previous wide character
+                skip -= 1;
+                continue;
+            }
+
+            skip = ratatui::text::Span::raw(symbol).width().saturating_sub(1);
+            line.push_str(symbol);
+        }
+
+        text.push_str(line.trim_end());
+        text.push('\n');
+    }
+
+    text
+}
+
+/// Draws a component on its own in a terminal of the given size, returning the text on screen
+/// like [`Harness::screen`].
+pub fn render(component: &mut dyn Component, width: u16, height: u16) -> Result<String> {
+    let mut terminal = ratatui::Terminal::new(TestBackend::new(width, height))?;
+    let mut result = Ok(());
+    terminal.draw(|frame| result = component.draw(frame, frame.area()))?;
+    result?;
+
+    Ok(text(terminal.backend().buffer()))
+}
+
+/// Sends actions to a component on its own, followed by any it sends back in turn, the way
+/// [`App`] would.
+pub fn dispatch(
+    component: &mut dyn Component,
+    actions: impl IntoIterator<Item = Action>,
+) -> Result<()> {
+    let (action_tx, mut action_rx) = mpsc::unbounded_channel();
+    component.register_action_handler(action_tx)?;
+
+    let mut queue = actions.into_iter().collect::<VecDeque<_>>();
+    while let Some(action) = queue.pop_front() {
+        queue.extend(component.update(action)?);
+        while let Ok(action) = action_rx.try_recv() {
+            queue.push_back(action);
+        }
+    }
+
+    Ok(())
+}
+
+/// Presses keys within a component which has focus, as if typed into it.
+// Only the components behind features take text
+#[allow(dead_code)]
+pub fn press(component: &mut dyn Component, keys: &[KeyCode]) -> Result<()> {
+    for key in keys {
+        component.handle_focused_event(&Event::Key(KeyEvent::from(*key)))?;
+    }
+
+    Ok(())
+}
+
+/// Types text into a component which has focus, a key at a time.
+#[allow(dead_code)]
+pub fn type_text(component: &mut dyn Component, text: &str) -> Result<()> {
+    press(component, &text.chars().map(KeyCode::Char).collect::<Vec<_>>())
+}
+
+#[cfg(feature = "blog")]
+pub mod fixtures {
+    use std::str::FromStr;
+    use std::sync::Arc;
+
+    use atrium_api::types::string::Datetime;
+
+    use crate::com::whtwnd::blog::entry;
+    use crate::components::Post;
+
+    fn post(title: &str, subtitle: Option<&str>, created_at: &str, content: &str) -> Post {
+        Arc::new(
+            entry::RecordData {
+                blobs: None,
+                content: content.to_string(),
+                created_at: Some(Datetime::from_str(created_at).unwrap()),
+                is_draft: None,
+                ogp: None,
+                subtitle: subtitle.map(str::to_string),
+                theme: None,
+                title: Some(title.to_string()),
+                visibility: None,
+            }
+            .into(),
+        )
+    }
+
+    /// Posts standing in for the ones fetched from ATProto.
+    pub fn posts() -> Vec<Post> {
+        vec![
+            post(
+                "Hello, world",
+                Some("The obligatory first post"),
+                "2024-11-02T10:00:00.000Z",
+                "This blog is served over #SSH.\n\n## Why\n\nBecause it's fun.",
+            ),
+            post(
+                "Writing a TUI",
+                None,
+                "2025-03-14T18:30:00.000Z",
+                "Some notes on building terminal interfaces with `ratatui`.",
+            ),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RIGHT: &[u8] = b"\x1b[C";
+    const ENTER: &[u8] = b"\r";
+    const ESC: &[u8] = b"\x1b";
+    const CTRL_Z: &[u8] = b"\x1a";
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_home() -> Result<()> {
+        for (width, height) in [(105, 25), (160, 40)] {
+            let harness = Harness::new(width, height).await?;
+            let screen = harness.screen().await;
+            assert!(screen.contains("devcomp.xyz"));
+            assert!(screen.contains("about") && screen.contains("projects"));
+
+            harness.assert_snapshot(&format!("home_{width}x{height}")).await;
+            harness.quit().await?;
+        }
+
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_too_small() -> Result<()> {
+        let harness = Harness::new(80, 24).await?;
+        assert!(harness.screen().await.contains("window size must be at least 105x25"));
+
+        // Growing the window brings the app back
+        harness.resize(120, 30).await?;
+        assert!(harness.screen().await.contains("devcomp.xyz"));
+        harness.assert_snapshot("resized_120x30").await;
+
+        harness.quit().await
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_suspend() -> Result<()> {
+        let harness = Harness::new(120, 30).await?;
+        harness.press(&[CTRL_Z]).await?;
+        let screen = harness.screen().await;
+        assert!(screen.contains("paused") && screen.contains("disconnecting in"));
+
+        // The countdown keeps being drawn while suspended
+        harness.wait().await?;
+        assert!(harness.screen().await.contains("paused"));
+
+        // Any key resumes the app
+        harness.press(&[b"x"]).await?;
+        let screen = harness.screen().await;
+        assert!(screen.contains("devcomp.xyz") && !screen.contains("paused"));
+
+        harness.quit().await
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_palette() -> Result<()> {
+        let harness = Harness::new(120, 30).await?;
+        harness.press(&[b":"]).await?;
+        let screen = harness.screen().await;
+        assert!(screen.contains("jump to") && screen.contains("▶ tab      about"));
+
+        // Running a project switches to its tab and opens it
+        harness.press(&[b"e", b"f", b"i"]).await?;
+        assert!(harness.screen().await.contains("▶ project  CompeyDev/bad-apple-efi"));
+        harness.assert_snapshot("palette_120x30").await;
+        harness.press(&[ENTER]).await?;
+        let screen = harness.screen().await;
+        assert!(screen.contains("built with UEFI") && !screen.contains("jump to"));
+
+        // What was run last is listed first the next time around
+        harness.press(&[b"q", b":"]).await?;
+        assert!(harness.screen().await.contains("▶ project  CompeyDev/bad-apple-efi"));
+        harness.press(&[ESC]).await?;
+        assert!(!harness.screen().await.contains("jump to"));
+
+        harness.quit().await
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_key_sequences() -> Result<()> {
+        let harness = Harness::new(120, 30).await?;
+
+        // The start of a sequence lists what can follow it
+        harness.press(&[b"g"]).await?;
+        let screen = harness.screen().await;
+        assert!(screen.contains("t        next tab") && screen.contains("shift-t  prev tab"));
+        harness.assert_snapshot("which_key_120x30").await;
+        harness.press(&[b"t"]).await?;
+        let screen = harness.screen().await;
+        assert!(screen.contains("0x5eal/luau-unzip") && !screen.contains("next tab"));
+        harness.press(&[b"g", b"T"]).await?;
+        assert!(!harness.screen().await.contains("0x5eal/luau-unzip"));
+
+        // Keys which don't follow on from the sequence start over, and escape gets out of it
+        harness.press(&[b"g", RIGHT]).await?;
+        assert!(harness.screen().await.contains("0x5eal/luau-unzip"));
+        harness.press(&[b"g", ESC]).await?;
+        let screen = harness.screen().await;
+        assert!(screen.contains("devcomp.xyz") && !screen.contains("next tab"));
+
+        // Sequences which aren't finished in time are given up on
+        harness.press(&[b"g"]).await?;
+        assert!(harness.screen().await.contains("next tab"));
+        harness.wait().await?;
+        assert!(!harness.screen().await.contains("next tab"));
+
+        harness.quit().await
+    }
+}
@@ -0,0 +1,125 @@
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::Instant;
+
+use axum::http::StatusCode;
+use axum::response::IntoResponse;
+use axum::Json;
+use lazy_static::lazy_static;
+use serde::Serialize;
+
+use crate::CONFIG;
+
+lazy_static! {
+    pub(crate) static ref HEALTH: Health = Health::default();
+}
+
+/// State the readiness checks are evaluated against, updated as the servers start up.
+#[derive(Debug, Default)]
+pub struct Health {
+    ssh_listening: AtomicBool,
+    blog_fetched_at: Mutex<Option<Instant>>,
+}
+
+/// The result of a single readiness check.
+#[derive(Debug, Serialize, PartialEq, Eq)]
+pub struct Check {
+    ok: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    detail: Option<String>,
+}
+
+impl Check {
+    fn new(ok: bool, detail: impl Into<String>) -> Self {
+        Self { ok, detail: Some(detail.into()) }
+    }
+}
+
+/// The body of a `/readyz` response.
+#[derive(Debug, Serialize)]
+pub struct Readiness {
+    ready: bool,
+    checks: BTreeMap<&'static str, Check>,
+}
+
+impl Health {
+    /// Marks the SSH listener as bound and accepting connections.
+    pub fn set_ssh_listening(&self, listening: bool) {
+        self.ssh_listening.store(listening, Ordering::Relaxed);
+    }
+
+    /// Records a successful fetch of blog posts into the cache.
+    pub fn blog_fetched(&self) {
+        if let Ok(mut fetched_at) = self.blog_fetched_at.lock() {
+            *fetched_at = Some(Instant::now());
+        }
+    }
+
+    /// Evaluates every readiness check, given the number of host keys loaded.
+    pub fn readiness(&self, host_keys: usize) -> Readiness {
+        let mut checks = BTreeMap::new();
+        checks.insert(
+            "ssh_listener",
+            if self.ssh_listening.load(Ordering::Relaxed) {
+                Check::new(true, "bound")
+            } else {
+                Check::new(false, "not bound yet")
+            },
+        );
+
+        checks.insert(
+            "host_keys",
+            Check::new(host_keys > 0, format!("{host_keys} host key(s) loaded")),
+        );
+
+        #[cfg(feature = "blog")]
+        checks.insert(
+            "blog",
+            match self.blog_fetched_at.lock().ok().and_then(|fetched_at| *fetched_at) {
+                Some(fetched_at) => Check::new(
+                    true,
+                    format!("posts fetched {}s ago", fetched_at.elapsed().as_secs()),
+                ),
+                None => Check::new(false, "posts have never been fetched"),
+            },
+        );
+
+        Readiness { ready: checks.values().all(|check| check.ok), checks }
+    }
+}
+
+/// Handler for the `/healthz` route, which only tells whether the process is alive.
+pub async fn handle_healthz() -> impl IntoResponse {
+    "ok"
+}
+
+/// Handler for the `/readyz` route, responding with a 503 until every check passes.
+pub async fn handle_readyz() -> impl IntoResponse {
+    let readiness = HEALTH.readiness(CONFIG.private_keys.len());
+    let status =
+        if readiness.ready { StatusCode::OK } else { StatusCode::SERVICE_UNAVAILABLE };
+
+    (status, Json(readiness))
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_readiness() {
+        let health = Health::default();
+        let readiness = health.readiness(1);
+        assert!(!readiness.ready);
+        assert_eq!(readiness.checks["ssh_listener"], Check::new(false, "not bound yet"));
+        assert_eq!(readiness.checks["host_keys"], Check::new(true, "1 host key(s) loaded"));
+
+        health.set_ssh_listening(true);
+        health.blog_fetched();
+        assert!(health.readiness(1).ready);
+        assert!(!health.readiness(0).ready);
+    }
+}
@@ -1,4 +1,6 @@
-use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use crossterm::event::{
+    KeyCode, KeyEvent, KeyModifiers, MouseButton, MouseEvent, MouseEventKind,
+};
 
 pub const CTRL_C: char = 3 as char;
 pub const CTRL_D: char = 4 as char;
@@ -27,23 +29,6 @@ impl KeyCodeExt for KeyCode {
             13 => KeyCode::Enter,
             27 => KeyCode::Esc,
 
-            // Arrow keys
-            37 => KeyCode::Left,
-            38 => KeyCode::Up,
-            39 => KeyCode::Right,
-            40 => KeyCode::Down,
-
-            // Navigation keys
-            33 => KeyCode::PageUp,
-            34 => KeyCode::PageDown,
-            36 => KeyCode::Home,
-            35 => KeyCode::End,
-            45 => KeyCode::Insert,
-            46 => KeyCode::Delete,
-
-            // Menu key
-            93 => KeyCode::Menu,
-
             // Printable ASCII characters
             b' '..=b'~' => KeyCode::Char(code as char),
 
@@ -58,6 +43,9 @@ impl KeyCodeExt for KeyCode {
             // Pause/Break
             19 => KeyCode::Pause,
 
+            // Other control characters, which are sent for Ctrl and a letter
+            1..=26 => KeyCode::Char(code as char),
+
             // Anything else
             _ => KeyCode::Null,
         }
@@ -65,6 +53,16 @@ impl KeyCodeExt for KeyCode {
 
     #[rustfmt::skip]
     fn from_xterm_seq(seq: &[u8]) -> Self {
+        // Characters outside of ASCII take up multiple bytes
+        if let Ok(text) = std::str::from_utf8(seq) {
+            let mut chars = text.chars();
+            if let (Some(c), None) = (chars.next(), chars.next()) {
+                if !c.is_ascii() {
+                    return Self::Char(c);
+                }
+            }
+        }
+
         let codes = seq
             .iter()
             .map(|&b| <Self as KeyCodeExt>::from(b))
@@ -75,8 +73,14 @@ impl KeyCodeExt for KeyCode {
             [Self::Esc, Self::Char('['), Self::Char('B')] => Self::Down,
             [Self::Esc, Self::Char('['), Self::Char('C')] => Self::Right,
             [Self::Esc, Self::Char('['), Self::Char('D')] => Self::Left,
+            [Self::Esc, Self::Char('['), Self::Char('H')] => Self::Home,
+            [Self::Esc, Self::Char('['), Self::Char('F')] => Self::End,
+            [Self::Esc, Self::Char('O'), Self::Char('H')] => Self::Home,
+            [Self::Esc, Self::Char('O'), Self::Char('F')] => Self::End,
+            [Self::Esc, Self::Char('['), Self::Char('Z')] => Self::BackTab,
             [Self::Esc, Self::Char('['), Self::Char('1'), Self::Char('~')] => Self::Home,
             [Self::Esc, Self::Char('['), Self::Char('4'), Self::Char('~')] => Self::End,
+            [Self::Esc, Self::Char('['), Self::Char('2'), Self::Char('~')] => Self::Insert,
             [Self::Esc, Self::Char('['), Self::Char('3'), Self::Char('~')] => Self::Delete,
             [Self::Esc, Self::Char('['), Self::Char('5'), Self::Char('~')] => Self::PageUp,
             [Self::Esc, Self::Char('['), Self::Char('6'), Self::Char('~')] => Self::PageDown,
@@ -99,14 +103,79 @@ impl KeyCodeExt for KeyCode {
 
     fn into_key_event(self) -> KeyEvent {
         match self {
-            Self::Char(CTRL_C) => KeyEvent::new(KeyCode::Char('c'), KeyModifiers::CONTROL),
-            Self::Char(CTRL_D) => KeyEvent::new(KeyCode::Char('d'), KeyModifiers::CONTROL),
-            Self::Char(CTRL_Z) => KeyEvent::new(KeyCode::Char('z'), KeyModifiers::CONTROL),
+            // Ctrl and a letter is sent as its position in the alphabet
+            Self::Char(c @ '\x01'..=CTRL_Z) => KeyEvent::new(
+                KeyCode::Char((b'a' + c as u8 - 1) as char),
+                KeyModifiers::CONTROL,
+            ),
             other => KeyEvent::new(other, KeyModifiers::empty()),
         }
     }
 }
 
+/// Parses text pasted into the terminal. Clients with bracketed paste enabled wrap it in
+/// `ESC [ 200 ~` and `ESC [ 201 ~`, which may arrive in separate chunks for long pastes, while
+/// others send it as if it was typed, only all at once.
+pub fn parse_paste(seq: &[u8]) -> Option<String> {
+    let unwrapped = seq.strip_prefix(b"\x1b[200~").unwrap_or(seq);
+    let unwrapped = unwrapped.strip_suffix(b"\x1b[201~").unwrap_or(unwrapped);
+    if unwrapped.len() != seq.len() {
+        return Some(String::from_utf8_lossy(unwrapped).into_owned());
+    }
+
+    // Single characters are keys, and anything else with control characters is a sequence
+    let text = std::str::from_utf8(seq).ok()?;
+    let is_key = text.chars().nth(1).is_none()
+        || text.contains(|c: char| c.is_control() && !matches!(c, '\r' | '\n' | '\t'));
+    (!is_key).then(|| text.to_string())
+}
+
+/// Parses an SGR mouse report (`ESC [ < button ; column ; row M`), which clients send once
+/// mouse capture is enabled. Releases end with `m` rather than `M`.
+pub fn parse_mouse_event(seq: &[u8]) -> Option<MouseEvent> {
+    let report = std::str::from_utf8(seq.strip_prefix(b"\x1b[<")?).ok()?;
+    let (params, pressed) = match report.strip_suffix('M') {
+        Some(params) => (params, true),
+        None => (report.strip_suffix('m')?, false),
+    };
+
+    let mut params = params.split(';').map(str::parse::<u16>);
+    let (code, column, row) =
+        (params.next()?.ok()?, params.next()?.ok()?, params.next()?.ok()?);
+
+    let button = match code & 0b11 {
+        0 => MouseButton::Left,
+        1 => MouseButton::Middle,
+        _ => MouseButton::Right,
+    };
+
+    let kind = match (code & !0b11100, pressed) {
+        (64, _) => MouseEventKind::ScrollUp,
+        (65, _) => MouseEventKind::ScrollDown,
+        (66, _) => MouseEventKind::ScrollLeft,
+        (67, _) => MouseEventKind::ScrollRight,
+        // Motion without any button held down
+        (35, _) => MouseEventKind::Moved,
+        (32..=34, _) => MouseEventKind::Drag(button),
+        (0..=2, true) => MouseEventKind::Down(button),
+        (0..=3, false) => MouseEventKind::Up(button),
+        _ => return None,
+    };
+
+    let mut modifiers = KeyModifiers::empty();
+    modifiers.set(KeyModifiers::SHIFT, code & 4 != 0);
+    modifiers.set(KeyModifiers::ALT, code & 8 != 0);
+    modifiers.set(KeyModifiers::CONTROL, code & 16 != 0);
+
+    // Reports count from 1, rather than 0
+    Some(MouseEvent {
+        kind,
+        column: column.saturating_sub(1),
+        row: row.saturating_sub(1),
+        modifiers,
+    })
+}
+
 #[rustfmt::skip]
 #[cfg(test)]
 mod tests {
@@ -138,6 +207,16 @@ mod tests {
         assert_eq!(<KeyCode as KeyCodeExt>::from(0), KeyCode::Null);
     }
 
+    #[test]
+    fn test_keycode_from_punctuation() {
+        assert_eq!(<KeyCode as KeyCodeExt>::from(b'!'), KeyCode::Char('!'));
+        assert_eq!(<KeyCode as KeyCodeExt>::from(b'&'), KeyCode::Char('&'));
+        assert_eq!(<KeyCode as KeyCodeExt>::from(b'('), KeyCode::Char('('));
+        assert_eq!(<KeyCode as KeyCodeExt>::from(b'-'), KeyCode::Char('-'));
+        assert_eq!(<KeyCode as KeyCodeExt>::from(b'.'), KeyCode::Char('.'));
+        assert_eq!(<KeyCode as KeyCodeExt>::from(b']'), KeyCode::Char(']'));
+    }
+
     #[test]
     fn test_keycode_from_invalid() {
         assert_eq!(<KeyCode as KeyCodeExt>::from(255), KeyCode::Null);
@@ -162,6 +241,12 @@ mod tests {
 
         assert_eq!(<KeyCode as KeyCodeExt>::from_xterm_seq(&[27, 91, 49, 56, 126]), KeyCode::F(7));
         assert_eq!(<KeyCode as KeyCodeExt>::from_xterm_seq(&[27, 91, 49, 57, 126]), KeyCode::F(8));
+
+        assert_eq!(<KeyCode as KeyCodeExt>::from_xterm_seq(b"\x1b[H"), KeyCode::Home);
+        assert_eq!(<KeyCode as KeyCodeExt>::from_xterm_seq(b"\x1bOF"), KeyCode::End);
+        assert_eq!(<KeyCode as KeyCodeExt>::from_xterm_seq(b"\x1b[Z"), KeyCode::BackTab);
+        assert_eq!(<KeyCode as KeyCodeExt>::from_xterm_seq("é".as_bytes()), KeyCode::Char('é'));
+        assert_eq!(<KeyCode as KeyCodeExt>::from_xterm_seq("日本".as_bytes()), KeyCode::Null);
     }
 
     #[test]
@@ -169,5 +254,31 @@ mod tests {
         let key_code = KeyCode::Char('a');
         let key_event = <KeyCode as KeyCodeExt>::into_key_event(key_code);
         assert_eq!(key_event, KeyEvent::new(KeyCode::Char('a'), KeyModifiers::empty()));
+
+        let key_event = <KeyCode as KeyCodeExt>::into_key_event(<KeyCode as KeyCodeExt>::from(11));
+        assert_eq!(key_event, KeyEvent::new(KeyCode::Char('k'), KeyModifiers::CONTROL));
+        let key_event = <KeyCode as KeyCodeExt>::into_key_event(<KeyCode as KeyCodeExt>::from(3));
+        assert_eq!(key_event, KeyEvent::new(KeyCode::Char('c'), KeyModifiers::CONTROL));
+    }
+
+    #[test]
+    fn test_parse_paste() {
+        assert_eq!(parse_paste(b"\x1b[200~hello\rworld\x1b[201~").as_deref(), Some("hello\rworld"));
+        assert_eq!(parse_paste(b"\x1b[200~the start").as_deref(), Some("the start"));
+        assert_eq!(parse_paste(b"the end\x1b[201~").as_deref(), Some("the end"));
+        assert_eq!(parse_paste("héllo".as_bytes()).as_deref(), Some("héllo"));
+        assert_eq!(parse_paste(b"a"), None);
+        assert_eq!(parse_paste("é".as_bytes()), None);
+        assert_eq!(parse_paste(b"\x1b[A"), None);
+    }
+
+    #[test]
+    fn test_parse_mouse_event() {
+        let event = parse_mouse_event(b"\x1b[<0;12;5M").unwrap();
+        assert_eq!((event.kind, event.column, event.row), (MouseEventKind::Down(MouseButton::Left), 11, 4));
+        assert_eq!(parse_mouse_event(b"\x1b[<0;12;5m").unwrap().kind, MouseEventKind::Up(MouseButton::Left));
+        assert_eq!(parse_mouse_event(b"\x1b[<65;1;1M").unwrap().kind, MouseEventKind::ScrollDown);
+        assert_eq!(parse_mouse_event(b"\x1b[<18;1;1M").unwrap().modifiers, KeyModifiers::CONTROL);
+        assert_eq!(parse_mouse_event(b"\x1b[A"), None);
     }
 }
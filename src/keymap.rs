@@ -0,0 +1,103 @@
+use std::collections::HashMap;
+
+use crossterm::event::KeyEvent;
+
+use crate::action::Action;
+
+/// The key sequences bound to actions in a mode, as a tree of the keys to press one after
+/// another. A sequence may be both bound to an action and the start of longer ones, in which
+/// case the longer ones are waited for before running it.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct KeyTrie {
+    action: Option<Action>,
+    next: HashMap<KeyEvent, KeyTrie>,
+}
+
+impl KeyTrie {
+    pub fn new<'a>(
+        bindings: impl IntoIterator<Item = (&'a Vec<KeyEvent>, &'a Action)>,
+    ) -> Self {
+        let mut trie = Self::default();
+        for (keys, action) in bindings {
+            trie.insert(keys, action.clone());
+        }
+
+        trie
+    }
+
+    pub fn insert(&mut self, keys: &[KeyEvent], action: Action) {
+        let node = keys.iter().fold(self, |node, key| node.next.entry(*key).or_default());
+        node.action = Some(action);
+    }
+
+    /// What pressing the keys leads to, if they are bound to anything or are the start of a
+    /// longer sequence.
+    pub fn get(&self, keys: &[KeyEvent]) -> Option<&KeyTrie> {
+        keys.iter().try_fold(self, |node, key| node.next.get(key))
+    }
+
+    /// The action the keys leading here are bound to.
+    pub fn action(&self) -> Option<&Action> {
+        self.action.as_ref()
+    }
+
+    /// Whether the keys leading here are the start of a longer sequence.
+    pub fn is_prefix(&self) -> bool {
+        !self.next.is_empty()
+    }
+
+    /// The keys which can be pressed next, along with what each of them leads to.
+    pub fn continuations(&self) -> impl Iterator<Item = (&KeyEvent, &KeyTrie)> {
+        self.next.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::config::parse_key_sequence;
+
+    fn trie(bindings: &[(&str, Action)]) -> KeyTrie {
+        let bindings = bindings
+            .iter()
+            .map(|(keys, action)| (parse_key_sequence(keys).unwrap(), action.clone()))
+            .collect::<HashMap<_, _>>();
+        KeyTrie::new(&bindings)
+    }
+
+    #[test]
+    fn test_sequences() {
+        let keys = |raw| parse_key_sequence(raw).unwrap();
+        let trie = trie(&[
+            ("<q>", Action::Quit),
+            ("<g><t>", Action::NextTab),
+            ("<g><Shift-t>", Action::PrevTab),
+        ]);
+
+        let quit = trie.get(&keys("<q>")).unwrap();
+        assert_eq!(quit.action(), Some(&Action::Quit));
+        assert!(!quit.is_prefix());
+
+        // The start of a sequence is bound to nothing, but lists what can follow it
+        let go = trie.get(&keys("<g>")).unwrap();
+        assert_eq!(go.action(), None);
+        assert!(go.is_prefix());
+        assert_eq!(go.continuations().count(), 2);
+        assert_eq!(go.get(&keys("<t>")).unwrap().action(), Some(&Action::NextTab));
+        assert_eq!(trie.get(&keys("<g><Shift-t>")).unwrap().action(), Some(&Action::PrevTab));
+
+        assert_eq!(trie.get(&keys("<x>")), None);
+        assert_eq!(trie.get(&keys("<g><x>")), None);
+        assert_eq!(trie.get(&keys("<q><q>")), None);
+    }
+
+    #[test]
+    fn test_prefix_bound_to_action() {
+        let trie = trie(&[("<g>", Action::Search), ("<g><g>", Action::NextMatch)]);
+        let go = trie.get(&parse_key_sequence("<g>").unwrap()).unwrap();
+        assert_eq!(go.action(), Some(&Action::Search));
+        assert!(go.is_prefix());
+    }
+}
@@ -1,15 +1,47 @@
+use std::collections::HashMap;
 use std::io;
-use std::net::SocketAddr;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::{Arc, PoisonError};
 
-use axum::extract::Path;
+use axum::extract::ws::{Message, WebSocket, WebSocketUpgrade};
+use axum::extract::{ConnectInfo, Path};
 use axum::http::{header, StatusCode};
-use axum::response::IntoResponse;
+use axum::response::{IntoResponse, Response};
 use axum::routing::get;
 use axum::Router;
+use color_eyre::eyre::{self, eyre};
+use futures::{SinkExt, StreamExt};
+use lazy_static::lazy_static;
 use rust_embed::Embed;
+use serde::Deserialize;
 use tokio::net::TcpListener;
+use tokio::sync::{mpsc, Mutex, RwLock};
 use tracing::instrument;
 
+use crate::app::App;
+#[cfg(any(feature = "guestbook", feature = "contact"))]
+use crate::ssh::Visitor;
+use crate::tui::backend::{WebBackend, WebTermWriter};
+use crate::tui::terminal::TerminalInfo;
+use crate::tui::Terminal;
+use crate::{health, metrics, OPTIONS, SESSIONS};
+
+#[cfg(feature = "blog")]
+mod blog;
+
+/// The `$TERM` web terminals are treated as, xterm.js emulates xterm closely enough.
+const WEB_TERM: &str = "xterm-256color";
+
+/// How many web terminals may be open at once, each of which runs a whole session.
+const MAX_WEB_SESSIONS: usize = 64;
+/// How many of those may be opened from the same address.
+const MAX_WEB_SESSIONS_PER_ADDRESS: usize = 3;
+
+lazy_static! {
+    /// How many web terminals are open from each address.
+    static ref WEB_SESSIONS: std::sync::Mutex<HashMap<IpAddr, usize>> = Default::default();
+}
+
 #[derive(Embed)]
 #[folder = "www/build"]
 pub struct WebLandingServer;
@@ -19,37 +51,44 @@ impl WebLandingServer {
     pub async fn start(addr: SocketAddr) -> io::Result<()> {
         let app = Router::new()
             .route("/", get(handle_index))
-            .route("/{*path}", get(handle_static_file))
-            .layer({
-                let layer = tower_http::trace::TraceLayer::new_for_http();
-                #[cfg(not(debug_assertions))]
-                let layer = layer
-                    .make_span_with(move |req: &axum::extract::Request<_>| {
-                        let method = req.method().clone();
-                        let path = req.uri().path().to_owned();
-
-                        tracing::info_span!("web", method = %method, path = %path)
-                    })
-                    .on_request(())
-                    .on_response(
-                        |res: &axum::response::Response<_>,
-                         latency: std::time::Duration,
-                         _span: &tracing::Span| {
-                            let status = res.status();
-                            tracing::info!(
-                                status = %status,
-                                latency = ?latency,
-                            );
-                        },
-                    );
-
-                layer
-            });
+            .route("/term", get(handle_term))
+            .route("/healthz", get(health::handle_healthz))
+            .route("/readyz", get(health::handle_readyz))
+            .route("/{*path}", get(handle_static_file));
+
+        #[cfg(feature = "blog")]
+        let app = app.merge(blog::router());
+
+        let app = app.layer(axum::middleware::from_fn(metrics::track_http)).layer({
+            let layer = tower_http::trace::TraceLayer::new_for_http();
+            #[cfg(not(debug_assertions))]
+            let layer = layer
+                .make_span_with(move |req: &axum::extract::Request<_>| {
+                    let method = req.method().clone();
+                    let path = req.uri().path().to_owned();
+
+                    tracing::info_span!("web", method = %method, path = %path)
+                })
+                .on_request(())
+                .on_response(
+                    |res: &axum::response::Response<_>,
+                     latency: std::time::Duration,
+                     _span: &tracing::Span| {
+                        let status = res.status();
+                        tracing::info!(
+                            status = %status,
+                            latency = ?latency,
+                        );
+                    },
+                );
+
+            layer
+        });
 
         let listener = TcpListener::bind(addr).await?;
         tracing::info!("Web server listening!");
 
-        axum::serve(listener, app).await?;
+        axum::serve(listener, app.into_make_service_with_connect_info::<SocketAddr>()).await?;
         Ok(())
     }
 }
@@ -58,6 +97,59 @@ async fn handle_index() -> impl IntoResponse {
     handle_static_file(Path("index.html".into())).await
 }
 
+async fn handle_term(
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    ws: WebSocketUpgrade,
+) -> Response {
+    let Some(slot) = WebSessionSlot::acquire(addr.ip()) else {
+        tracing::debug!("Turning away web terminal, too many are open");
+        return (StatusCode::TOO_MANY_REQUESTS, "too many terminals open, try again later")
+            .into_response();
+    };
+
+    ws.on_upgrade(move |socket| {
+        SESSIONS.track_future(async move {
+            let _slot = slot;
+            match WebSession::run(socket, addr.ip()).await {
+                Ok(()) => tracing::info!("Web session exited successfully"),
+                Err(err) => tracing::error!("Web session errored: {err}"),
+            }
+        })
+    })
+}
+
+/// A slot taken up by an open web terminal, which is given back once dropped.
+#[derive(Debug)]
+struct WebSessionSlot(IpAddr);
+
+impl WebSessionSlot {
+    /// Takes up a slot for the address, unless too many web terminals are open already, in
+    /// total or from the address.
+    fn acquire(ip: IpAddr) -> Option<Self> {
+        let mut open = WEB_SESSIONS.lock().unwrap_or_else(PoisonError::into_inner);
+        let total = open.values().sum::<usize>();
+        let from_address = open.get(&ip).copied().unwrap_or_default();
+        if total >= MAX_WEB_SESSIONS || from_address >= MAX_WEB_SESSIONS_PER_ADDRESS {
+            return None;
+        }
+
+        open.insert(ip, from_address + 1);
+        Some(Self(ip))
+    }
+}
+
+impl Drop for WebSessionSlot {
+    fn drop(&mut self) {
+        let mut open = WEB_SESSIONS.lock().unwrap_or_else(PoisonError::into_inner);
+        if let Some(count) = open.get_mut(&self.0) {
+            *count -= 1;
+            if *count == 0 {
+                open.remove(&self.0);
+            }
+        }
+    }
+}
+
 async fn handle_static_file(Path(path): Path<String>) -> impl IntoResponse {
     WebLandingServer::get(&path)
         .map(|file| {
@@ -66,3 +158,156 @@ async fn handle_static_file(Path(path): Path<String>) -> impl IntoResponse {
         })
         .ok_or((StatusCode::NOT_FOUND, "404 Not Found"))
 }
+
+/// A control message sent by the web terminal as JSON in a text frame. Binary frames are raw
+/// input, the same as what an SSH client would send.
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+enum ClientMessage {
+    Input { data: String },
+    Resize { cols: u16, rows: u16 },
+}
+
+/// Serves the TUI to an xterm.js terminal over a WebSocket, the web equivalent of
+/// [`SshSession`](crate::ssh::SshSession).
+pub struct WebSession;
+
+impl WebSession {
+    #[instrument(skip(socket), name = "web_session")]
+    async fn run(socket: WebSocket, address: IpAddr) -> eyre::Result<()> {
+        let (mut sink, mut stream) = socket.split();
+
+        // The terminal has to tell us how big it is before we can render anything, much like
+        // the `pty-req` of an SSH session
+        let (cols, rows) = loop {
+            match stream.next().await {
+                Some(Ok(Message::Text(text))) => {
+                    if let Ok(ClientMessage::Resize { cols, rows }) =
+                        serde_json::from_str(&text)
+                    {
+                        break (cols, rows);
+                    }
+                }
+                Some(Ok(Message::Close(_))) | None => return Ok(()),
+                Some(Ok(_)) => {}
+                Some(Err(err)) => return Err(eyre!(err)),
+            }
+        };
+
+        tracing::info!("Serving app to web terminal, dims: {cols} * {rows}");
+
+        // There is no probing to wait on, xterm.js is a known quantity
+        let terminal_info = Arc::new(RwLock::new(TerminalInfo::default()));
+        {
+            let mut terminal_info = terminal_info.write().await;
+            terminal_info.set_term(WEB_TERM);
+            terminal_info.set_colorterm("truecolor");
+            terminal_info.finish_probe();
+        }
+
+        let (keystroke_tx, keystroke_rx) = mpsc::unbounded_channel();
+        let (resize_tx, resize_rx) = mpsc::unbounded_channel();
+        let (output_tx, mut output_rx) = mpsc::unbounded_channel();
+
+        let mut app = tokio::task::block_in_place(|| {
+            App::new(
+                terminal_info,
+                OPTIONS.tick_rate,
+                OPTIONS.frame_rate,
+                keystroke_rx,
+                resize_rx,
+            )
+        })?;
+
+        // There is no username or key to go by, only where the visitor connected from
+        #[cfg(any(feature = "guestbook", feature = "contact"))]
+        {
+            let visitor = Visitor { address: Some(address), ..Visitor::default() };
+            app = app.visitor(Arc::new(RwLock::new(visitor)));
+        }
+
+        let terminal = Arc::new(Mutex::new(Terminal::<WebBackend>::new(WebBackend::new(
+            WebTermWriter::new(output_tx),
+            cols,
+            rows,
+        ))?));
+
+        let tui = Arc::new(RwLock::new(None));
+        let mut app_task = tokio::spawn(async move { app.run(terminal, tui).await });
+
+        loop {
+            tokio::select! {
+                Some(output) = output_rx.recv() => {
+                    sink.send(Message::Binary(output.into())).await?;
+                }
+
+                message = stream.next() => {
+                    let open = Self::handle_message(message, &keystroke_tx, &resize_tx)
+                        .inspect_err(|_| app_task.abort())?;
+
+                    if !open {
+                        // The visitor closed the page, nobody is left to quit the app
+                        app_task.abort();
+                        return Ok(());
+                    }
+                }
+
+                result = &mut app_task => {
+                    result??;
+                    break;
+                }
+            }
+        }
+
+        // Write out whatever was rendered while exiting before hanging up
+        while let Ok(output) = output_rx.try_recv() {
+            sink.send(Message::Binary(output.into())).await?;
+        }
+
+        sink.send(Message::Close(None)).await?;
+        Ok(())
+    }
+
+    /// Forwards a message from the web terminal to the app, returning whether the socket is
+    /// still open.
+    fn handle_message(
+        message: Option<Result<Message, axum::Error>>,
+        keystroke_tx: &mpsc::UnboundedSender<Vec<u8>>,
+        resize_tx: &mpsc::UnboundedSender<(u16, u16)>,
+    ) -> eyre::Result<bool> {
+        match message {
+            Some(Ok(Message::Binary(data))) => keystroke_tx.send(data.to_vec())?,
+            Some(Ok(Message::Text(text))) => match serde_json::from_str(&text) {
+                Ok(ClientMessage::Input { data }) => keystroke_tx.send(data.into_bytes())?,
+                Ok(ClientMessage::Resize { cols, rows }) => resize_tx.send((cols, rows))?,
+                Err(err) => tracing::warn!("Ignoring malformed web terminal message: {err}"),
+            },
+            Some(Ok(Message::Close(_))) | None => return Ok(false),
+            Some(Ok(_)) => {}
+            Some(Err(err)) => return Err(eyre!(err)),
+        }
+
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_web_session_slots() {
+        let ip = IpAddr::from([192, 0, 2, 30]);
+        let slots = (0..MAX_WEB_SESSIONS_PER_ADDRESS)
+            .map(|_| WebSessionSlot::acquire(ip).unwrap())
+            .collect::<Vec<_>>();
+
+        // Other addresses still get a slot while one has used all of its own
+        assert!(WebSessionSlot::acquire(ip).is_none());
+        assert!(WebSessionSlot::acquire(IpAddr::from([192, 0, 2, 31])).is_some());
+
+        drop(slots);
+        assert!(WebSessionSlot::acquire(ip).is_some());
+        assert!(!WEB_SESSIONS.lock().unwrap().contains_key(&ip));
+    }
+}
@@ -0,0 +1,364 @@
+use std::fmt::Write as _;
+
+use axum::extract::Path;
+use axum::http::{header, StatusCode};
+use axum::response::{Html, IntoResponse};
+use axum::routing::get;
+use axum::Router;
+use chrono::{DateTime, FixedOffset};
+use lazy_static::lazy_static;
+use pulldown_cmark::{CowStr, Event, Options, Parser, Tag};
+
+use crate::blog::{BlogPost, SOURCE};
+use crate::{CONFIG, OPTIONS};
+
+/// URL schemes which links and images within posts may use, anything else such as
+/// `javascript:` is replaced.
+const ALLOWED_SCHEMES: [&str; 3] = ["http", "https", "mailto"];
+
+lazy_static! {
+    static ref SITE: Site = match &CONFIG.site_url {
+        Some(url) => Site::from_url(url),
+        None => Site::from_url(&format!("http://{}:{}", OPTIONS.host, OPTIONS.web_port)),
+    };
+}
+
+/// A web mirror of the blog tab, along with feeds for it.
+pub fn router() -> Router {
+    Router::new()
+        .route("/blog", get(handle_index))
+        .route("/blog/{rkey}", get(handle_post))
+        .route("/feed.xml", get(handle_rss))
+        .route("/atom.xml", get(handle_atom))
+}
+
+/// Where the site is publicly reachable from, as configured by `site_url`.
+#[derive(Debug, PartialEq, Eq)]
+struct Site {
+    base_url: String,
+    ssh_host: String,
+}
+
+impl Site {
+    fn from_url(url: &str) -> Self {
+        let base_url = url.trim_end_matches('/');
+        let host = base_url.split_once("://").map_or(base_url, |(_, rest)| rest);
+        let host = host.split('/').next().unwrap_or(host);
+
+        // The SSH server lives on the same host, just on another port
+        let ssh_host = match host.rsplit_once(':') {
+            Some((name, port)) if port.bytes().all(|b| b.is_ascii_digit()) => name,
+            _ => host,
+        };
+
+        Self { base_url: base_url.to_string(), ssh_host: ssh_host.to_string() }
+    }
+
+    fn ssh_command(&self) -> String {
+        match OPTIONS.ssh_port {
+            22 => format!("ssh {}", self.ssh_host),
+            port => format!("ssh -p {port} {}", self.ssh_host),
+        }
+    }
+
+    fn ssh_url(&self) -> String {
+        format!("ssh://{}:{}", self.ssh_host, OPTIONS.ssh_port)
+    }
+}
+
+type Response<T> = Result<T, (StatusCode, &'static str)>;
+
+async fn posts() -> Response<Vec<BlogPost>> {
+    SOURCE.posts().await.map_err(|err| {
+        tracing::error!("Failed to fetch blog posts: {err}");
+        (StatusCode::BAD_GATEWAY, "502 Bad Gateway")
+    })
+}
+
+async fn handle_index() -> Response<Html<String>> {
+    let posts = posts().await?;
+    Ok(Html(render_index(&posts, &SITE)))
+}
+
+async fn handle_post(Path(rkey): Path<String>) -> Response<Html<String>> {
+    let posts = posts().await?;
+    let post = posts
+        .iter()
+        .find(|post| post.rkey == rkey)
+        .ok_or((StatusCode::NOT_FOUND, "404 Not Found"))?;
+
+    Ok(Html(render_post(post, &SITE)))
+}
+
+async fn handle_rss() -> Response<impl IntoResponse> {
+    let posts = posts().await?;
+    Ok((
+        [(header::CONTENT_TYPE, "application/rss+xml; charset=utf-8")],
+        render_rss(&posts, &SITE),
+    ))
+}
+
+async fn handle_atom() -> Response<impl IntoResponse> {
+    let posts = posts().await?;
+    Ok((
+        [(header::CONTENT_TYPE, "application/atom+xml; charset=utf-8")],
+        render_atom(&posts, &SITE),
+    ))
+}
+
+fn render_index(posts: &[BlogPost], site: &Site) -> String {
+    let mut list = String::new();
+    for post in posts {
+        let _ = write!(
+            list,
+            r#"<li><time>{}</time> <a href="/blog/{}">{}</a></li>"#,
+            created_at(post).map_or_else(String::new, |date| date.date_naive().to_string()),
+            escape(&post.rkey),
+            escape(title(post)),
+        );
+    }
+
+    page("blog", &format!("<h1>blog</h1>\n<ul>{list}</ul>"), site)
+}
+
+fn render_post(post: &BlogPost, site: &Site) -> String {
+    let mut body = format!("<h1>{}</h1>", escape(title(post)));
+    if let Some(subtitle) = &post.record.subtitle {
+        let _ = write!(body, "<p><em>{}</em></p>", escape(subtitle));
+    }
+    if let Some(date) = created_at(post) {
+        let _ = write!(body, "<p><time>{}</time></p>", date.date_naive());
+    }
+    if let Some(ogp) = &post.record.ogp {
+        let _ = write!(
+            body,
+            r#"<img src="{}" alt="">"#,
+            escape(&safe_url(ogp.url.as_str().into()))
+        );
+    }
+
+    body.push_str(&markdown_html(&post.record.content));
+    page(title(post), &body, site)
+}
+
+fn page(title: &str, body: &str, site: &Site) -> String {
+    format!(
+        r#"<!doctype html>
+<html lang="en">
+<head>
+<meta charset="utf-8">
+<meta name="viewport" content="width=device-width, initial-scale=1">
+<title>{title}</title>
+<link rel="alternate" type="application/rss+xml" href="/feed.xml">
+<link rel="alternate" type="application/atom+xml" href="/atom.xml">
+<style>body {{ max-width: 72ch; margin: 2rem auto; padding: 0 1rem; font-family: monospace; line-height: 1.5 }} img {{ max-width: 100% }}</style>
+</head>
+<body>
+<nav><a href="/blog">blog</a> · <a href="/feed.xml">rss</a> · <a href="/atom.xml">atom</a></nav>
+<main>
+{body}
+</main>
+<footer><p>read this in your terminal: <a href="{ssh_url}"><code>{ssh_command}</code></a></p></footer>
+</body>
+</html>
+"#,
+        title = escape(title),
+        ssh_url = escape(&site.ssh_url()),
+        ssh_command = escape(&site.ssh_command()),
+    )
+}
+
+fn render_rss(posts: &[BlogPost], site: &Site) -> String {
+    let mut items = String::new();
+    for post in posts {
+        let link = escape(&format!("{}/blog/{}", site.base_url, post.rkey));
+        let pub_date = created_at(post).map_or_else(String::new, |date| {
+            format!("<pubDate>{}</pubDate>", date.to_rfc2822())
+        });
+        let _ = write!(
+            items,
+            r#"<item><title>{}</title><link>{link}</link><guid>{link}</guid>{pub_date}<description>{}</description></item>"#,
+            escape(title(post)),
+            escape(&markdown_html(&post.record.content)),
+        );
+    }
+
+    format!(
+        r#"<?xml version="1.0" encoding="utf-8"?>
+<rss version="2.0"><channel><title>blog</title><link>{link}</link><description>{description}</description>{items}</channel></rss>
+"#,
+        link = escape(&format!("{}/blog", site.base_url)),
+        description = escape(&site.ssh_command()),
+    )
+}
+
+fn render_atom(posts: &[BlogPost], site: &Site) -> String {
+    let mut entries = String::new();
+    for post in posts {
+        let link = escape(&format!("{}/blog/{}", site.base_url, post.rkey));
+        let summary = post.record.subtitle.as_ref().map_or_else(String::new, |subtitle| {
+            format!("<summary>{}</summary>", escape(subtitle))
+        });
+        let _ = write!(
+            entries,
+            r#"<entry><title>{}</title><id>{link}</id><link href="{link}"/><updated>{}</updated>{summary}<content type="html">{}</content></entry>"#,
+            escape(title(post)),
+            updated(post),
+            escape(&markdown_html(&post.record.content)),
+        );
+    }
+
+    // Atom requires the feed to say when it last changed, which is when its newest post was
+    let feed_updated = posts.iter().map(updated).max().unwrap_or_else(|| updated_at(None));
+    format!(
+        r#"<?xml version="1.0" encoding="utf-8"?>
+<feed xmlns="http://www.w3.org/2005/Atom"><title>blog</title><id>{link}</id><link href="{link}"/><link rel="self" href="{base_url}/atom.xml"/><author><name>{author}</name></author><updated>{feed_updated}</updated>{entries}</feed>
+"#,
+        link = escape(&format!("{}/blog", site.base_url)),
+        base_url = escape(&site.base_url),
+        author = escape(&site.ssh_host),
+    )
+}
+
+fn title(post: &BlogPost) -> &str {
+    post.record.title.as_deref().unwrap_or(&post.rkey)
+}
+
+fn created_at(post: &BlogPost) -> Option<DateTime<FixedOffset>> {
+    crate::blog::created_at(&post.record)
+}
+
+fn updated(post: &BlogPost) -> String {
+    updated_at(created_at(post))
+}
+
+fn updated_at(date: Option<DateTime<FixedOffset>>) -> String {
+    date.unwrap_or(DateTime::UNIX_EPOCH.fixed_offset()).to_utc().to_rfc3339()
+}
+
+/// Renders a post's markdown to HTML. Any HTML within it is escaped, and links or images
+/// with a scheme other than those allowed are defused, since posts may come from feeds we
+/// don't control.
+fn markdown_html(markdown: &str) -> String {
+    let parser = Parser::new_ext(markdown, Options::all()).map(|event| match event {
+        Event::Html(html) | Event::InlineHtml(html) => Event::Text(html),
+        Event::Start(Tag::Link { link_type, dest_url, title, id }) => {
+            Event::Start(Tag::Link { link_type, dest_url: safe_url(dest_url), title, id })
+        }
+        Event::Start(Tag::Image { link_type, dest_url, title, id }) => {
+            Event::Start(Tag::Image { link_type, dest_url: safe_url(dest_url), title, id })
+        }
+        event => event,
+    });
+
+    let mut html = String::new();
+    pulldown_cmark::html::push_html(&mut html, parser);
+    html
+}
+
+/// The URL as is if it's relative or has an allowed scheme, otherwise one going nowhere.
+fn safe_url(url: CowStr<'_>) -> CowStr<'_> {
+    // Browsers ignore whitespace and control characters within the scheme
+    let url_chars = url.chars().filter(|c| !c.is_ascii_whitespace() && !c.is_control());
+    let scheme = url_chars
+        .take_while(|c| !matches!(c, '/' | '?' | '#'))
+        .collect::<String>()
+        .split_once(':')
+        .map(|(scheme, _)| scheme.to_ascii_lowercase());
+
+    match scheme {
+        Some(scheme) if !ALLOWED_SCHEMES.contains(&scheme.as_str()) => CowStr::Borrowed("#"),
+        _ => url,
+    }
+}
+
+/// Escapes text for use within HTML or XML, including in attribute values.
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::blog::PostData;
+
+    fn post() -> BlogPost {
+        PostData {
+            content: String::from("Built with **ratatui** <script>alert(1)</script>"),
+            title: Some(String::from("Writing a TUI")),
+            created_at: Some(String::from("2025-03-14T18:30:00.000Z")),
+            ..Default::default()
+        }
+        .into_post(String::from("3lkf2xyz"))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_site_from_url() {
+        assert_eq!(
+            Site::from_url("https://devcomp.xyz:8080/"),
+            Site {
+                base_url: String::from("https://devcomp.xyz:8080"),
+                ssh_host: String::from("devcomp.xyz")
+            }
+        );
+        assert_eq!(
+            Site::from_url("https://example.com/portfolio"),
+            Site {
+                base_url: String::from("https://example.com/portfolio"),
+                ssh_host: String::from("example.com")
+            }
+        );
+    }
+
+    #[test]
+    fn test_markdown_html_links() {
+        let html = markdown_html(
+            "[site](https://example.com) [mail](mailto:me@example.com) [post](/blog/tui) \
+             [xss](javascript:alert(1)) [XSS](JavaScript:alert(1)) ![pixel](data:image/png,x)",
+        );
+
+        assert!(html.contains(r#"<a href="https://example.com">"#));
+        assert!(html.contains(r#"<a href="mailto:me@example.com">"#));
+        assert!(html.contains(r#"<a href="/blog/tui">"#));
+        assert!(html.contains(r##"<a href="#">xss</a>"##));
+        assert!(html.contains(r##"<a href="#">XSS</a>"##));
+        assert!(html.contains(r##"<img src="#" alt="pixel""##));
+        assert!(!html.contains("javascript") && !html.contains("data:"));
+    }
+
+    #[test]
+    fn test_render_post() {
+        let site = Site {
+            base_url: String::from("https://example.com"),
+            ssh_host: String::from("example.com"),
+        };
+        let html = render_post(&post(), &site);
+
+        assert!(html.contains("<h1>Writing a TUI</h1>"));
+        assert!(html.contains("<time>2025-03-14</time>"));
+        assert!(html.contains("Built with <strong>ratatui</strong> &lt;script&gt;"));
+        assert!(html.contains(&escape(&site.ssh_command())));
+
+        let atom = render_atom(&[post()], &site);
+        assert!(atom.contains("<id>https://example.com/blog/3lkf2xyz</id>"));
+        assert!(atom.contains("<updated>2025-03-14T18:30:00+00:00</updated>"));
+
+        let rss = render_rss(&[post()], &site);
+        assert!(rss.contains("<pubDate>Fri, 14 Mar 2025 18:30:00 +0000</pubDate>"));
+    }
+}
@@ -1,6 +1,7 @@
 #![feature(optimize_attribute)]
 
 use std::net::SocketAddr;
+use std::time::Duration;
 
 use clap::Parser as _;
 use cli::Cli;
@@ -8,8 +9,11 @@ use color_eyre::eyre::eyre;
 use color_eyre::Result;
 use lazy_static::lazy_static;
 use russh::server::Config as SshConfig;
-use russh::MethodSet;
+use signal_hook::consts::{SIGINT, SIGTERM};
+use signal_hook::iterator::Signals;
 use ssh::SshServer;
+use tokio_util::sync::CancellationToken;
+use tokio_util::task::TaskTracker;
 
 #[cfg(feature = "blog")]
 pub(crate) use atproto::com;
@@ -24,13 +28,25 @@ mod action;
 mod app;
 #[cfg(feature = "blog")]
 pub(crate) mod atproto;
+#[cfg(feature = "blog")]
+mod blog;
 mod cli;
 mod components;
 mod config;
+#[cfg(feature = "contact")]
+mod contact;
 mod errors;
+mod forge;
+#[cfg(feature = "guestbook")]
+mod guestbook;
+#[cfg(test)]
+mod harness;
+mod health;
 mod keycode;
+mod keymap;
 mod landing;
 mod logging;
+mod metrics;
 mod ssh;
 mod tui;
 
@@ -40,6 +56,8 @@ lazy_static! {
     pub(crate) static ref OPTIONS: Cli = Cli::parse();
     pub(crate) static ref SSH_SOCKET_ADDR: Option<SocketAddr> = Some(SocketAddr::from((host_ip().ok()?, OPTIONS.ssh_port)));
     pub(crate) static ref WEB_SERVER_ADDR: Option<SocketAddr> = Some(SocketAddr::from((host_ip().ok()?, OPTIONS.web_port)));
+    pub(crate) static ref SHUTDOWN: CancellationToken = CancellationToken::new();
+    pub(crate) static ref SESSIONS: TaskTracker = TaskTracker::new();
 }
 
 #[tokio::main]
@@ -49,17 +67,38 @@ async fn main() -> Result<()> {
     crate::logging::init()?;
     let _ = *OPTIONS; // force clap to run by evaluating it
 
+    #[cfg(feature = "guestbook")]
+    if let Some(cli::Command::Guestbook(command)) = &OPTIONS.command {
+        return guestbook::moderate(command);
+    }
+
     eprintln!(" {} v{}\n", env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"));
 
     let ssh_socket_addr = SSH_SOCKET_ADDR.ok_or(eyre!("Invalid host IP provided"))?;
     let web_server_addr = WEB_SERVER_ADDR.ok_or(eyre!("Invalid host IP provided"))?;
 
-    loop {
+    handle_signals()?;
+
+    #[cfg(feature = "blog")]
+    tokio::task::spawn(blog::warm_cache());
+    #[cfg(feature = "forge")]
+    tokio::task::spawn(forge::warm_cache());
+    if let Some(metrics_addr) = OPTIONS.metrics_addr {
+        tokio::task::spawn(async move {
+            if let Err(err) = metrics::serve(metrics_addr).await {
+                tracing::error!("Metrics server errored: {err}");
+            }
+        });
+    }
+
+    let result = loop {
         let task = tokio::task::spawn(async move {
             let ssh_config = ssh_config();
             tokio::select! {
                 ssh_res = SshServer::start(ssh_socket_addr, ssh_config) => ssh_res,
-                web_res = WebLandingServer::start(web_server_addr) => web_res.map_err(|err| eyre!(err))
+                web_res = WebLandingServer::start(web_server_addr) => web_res.map_err(|err| eyre!(err)),
+                // Dropping the servers stops accepting connections, existing sessions live on
+                _ = SHUTDOWN.cancelled() => Ok(()),
             }
         });
 
@@ -72,7 +111,47 @@ async fn main() -> Result<()> {
                 }
             }
         }
+    };
+
+    if SHUTDOWN.is_cancelled() {
+        return drain_sessions().await;
     }
+
+    result
+}
+
+/// Starts shutting down on `SIGTERM` or `SIGINT`, a second signal exits immediately.
+fn handle_signals() -> Result<()> {
+    let mut signals = Signals::new([SIGTERM, SIGINT])?;
+    std::thread::spawn(move || {
+        for signal in signals.forever() {
+            if SHUTDOWN.is_cancelled() {
+                tracing::warn!("Received signal {signal} again, exiting without draining");
+                std::process::exit(128 + signal);
+            }
+
+            tracing::info!("Received signal {signal}, shutting down");
+            SHUTDOWN.cancel();
+        }
+    });
+
+    Ok(())
+}
+
+/// Waits for live sessions to end after they were notified of the shutdown, giving up after
+/// the drain timeout.
+async fn drain_sessions() -> Result<()> {
+    SESSIONS.close();
+
+    let drain_timeout = Duration::from_secs_f64(OPTIONS.drain_timeout);
+    tracing::info!("Draining {} session(s) for up to {drain_timeout:?}", SESSIONS.len());
+
+    tokio::time::timeout(drain_timeout, SESSIONS.wait())
+        .await
+        .map(|_| tracing::info!("All sessions drained, exiting"))
+        .map_err(|_| {
+            eyre!("{} session(s) did not end before the drain timeout", SESSIONS.len())
+        })
 }
 
 /// Converts the supplied hostname IP via CLI to a fixed size array of `[u8; 4]`, defaults to `127.0.0.1`
@@ -94,7 +173,7 @@ pub fn host_ip() -> Result<[u8; 4]> {
 #[instrument(name = "config")]
 fn ssh_config() -> SshConfig {
     let conf = SshConfig {
-        methods: MethodSet::empty(),
+        methods: ssh::auth_methods(),
         keys: CONFIG.private_keys.clone(),
         ..Default::default()
     };
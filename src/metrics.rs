@@ -0,0 +1,309 @@
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::future::Future;
+use std::io;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicI64, AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use axum::extract::{MatchedPath, Request};
+use axum::http::header;
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use axum::routing::get;
+use axum::Router;
+use lazy_static::lazy_static;
+
+lazy_static! {
+    pub(crate) static ref METRICS: Metrics = Metrics::default();
+}
+
+/// Upper bounds of the latency histogram buckets, in seconds.
+const LATENCY_BUCKETS: [f64; 10] = [0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0];
+
+/// A value which only ever goes up.
+#[derive(Debug, Default)]
+pub struct Counter(AtomicU64);
+
+impl Counter {
+    pub fn inc(&self) {
+        self.inc_by(1);
+    }
+
+    pub fn inc_by(&self, n: u64) {
+        self.0.fetch_add(n, Ordering::Relaxed);
+    }
+
+    pub fn get(&self) -> u64 {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// A value which can go up and down.
+#[derive(Debug, Default)]
+pub struct Gauge(AtomicI64);
+
+impl Gauge {
+    pub fn inc(&self) {
+        self.0.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn dec(&self) {
+        self.0.fetch_sub(1, Ordering::Relaxed);
+    }
+
+    pub fn get(&self) -> i64 {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// A set of counters, one for each combination of label values.
+#[derive(Debug)]
+pub struct CounterVec {
+    labels: &'static [&'static str],
+    values: Mutex<BTreeMap<Vec<String>, u64>>,
+}
+
+impl CounterVec {
+    pub fn new(labels: &'static [&'static str]) -> Self {
+        Self { labels, values: Mutex::default() }
+    }
+
+    /// Increments the counter for the given label values, which must be in the same order as
+    /// the label names.
+    pub fn inc(&self, values: &[&str]) {
+        debug_assert_eq!(values.len(), self.labels.len());
+        if let Ok(mut counters) = self.values.lock() {
+            *counters
+                .entry(values.iter().map(|value| value.to_string()).collect())
+                .or_default() += 1;
+        }
+    }
+}
+
+/// Observations sorted into cumulative buckets by their duration.
+#[derive(Debug)]
+pub struct Histogram {
+    buckets: &'static [f64],
+    counts: Vec<AtomicU64>,
+    sum_micros: AtomicU64,
+    count: AtomicU64,
+}
+
+impl Histogram {
+    pub fn new(buckets: &'static [f64]) -> Self {
+        Self {
+            buckets,
+            counts: buckets.iter().map(|_| AtomicU64::new(0)).collect(),
+            sum_micros: AtomicU64::new(0),
+            count: AtomicU64::new(0),
+        }
+    }
+
+    pub fn observe(&self, duration: Duration) {
+        let seconds = duration.as_secs_f64();
+        for (bound, count) in self.buckets.iter().zip(&self.counts) {
+            if seconds <= *bound {
+                count.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+
+        self.sum_micros.fetch_add(duration.as_micros() as u64, Ordering::Relaxed);
+        self.count.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Observes how long the given future takes to complete.
+    pub async fn time<F: Future>(&self, future: F) -> F::Output {
+        let start = Instant::now();
+        let output = future.await;
+        self.observe(start.elapsed());
+        output
+    }
+}
+
+/// Everything we keep track of, exported in the Prometheus text format at `/metrics`.
+#[derive(Debug)]
+pub struct Metrics {
+    pub ssh_sessions_active: Gauge,
+    pub ssh_sessions_total: Counter,
+    pub ssh_auth_total: CounterVec,
+    pub ssh_keystrokes_total: Counter,
+    pub ssh_bytes_flushed_total: Counter,
+    pub terminal_kinds_total: CounterVec,
+    pub frames_rendered_total: Counter,
+    pub blog_cache_hits_total: Counter,
+    pub blog_cache_misses_total: Counter,
+    pub atproto_fetch_seconds: Histogram,
+    pub http_requests_total: CounterVec,
+    pub http_request_seconds: Histogram,
+}
+
+impl Default for Metrics {
+    fn default() -> Self {
+        Self {
+            ssh_sessions_active: Gauge::default(),
+            ssh_sessions_total: Counter::default(),
+            ssh_auth_total: CounterVec::new(&["method"]),
+            ssh_keystrokes_total: Counter::default(),
+            ssh_bytes_flushed_total: Counter::default(),
+            terminal_kinds_total: CounterVec::new(&["kind"]),
+            frames_rendered_total: Counter::default(),
+            blog_cache_hits_total: Counter::default(),
+            blog_cache_misses_total: Counter::default(),
+            atproto_fetch_seconds: Histogram::new(&LATENCY_BUCKETS),
+            http_requests_total: CounterVec::new(&["method", "path", "status"]),
+            http_request_seconds: Histogram::new(&LATENCY_BUCKETS),
+        }
+    }
+}
+
+impl Metrics {
+    /// Renders all metrics in the Prometheus text exposition format.
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+
+        #[rustfmt::skip]
+        let scalars = [
+            ("ssh_sessions_active", "gauge", "Currently connected SSH sessions.", self.ssh_sessions_active.get() as f64),
+            ("ssh_sessions_total", "counter", "SSH sessions opened.", self.ssh_sessions_total.get() as f64),
+            ("ssh_keystrokes_total", "counter", "Chunks of keystroke data received over SSH.", self.ssh_keystrokes_total.get() as f64),
+            ("ssh_bytes_flushed_total", "counter", "Bytes flushed to SSH channels.", self.ssh_bytes_flushed_total.get() as f64),
+            ("frames_rendered_total", "counter", "Frames rendered across all sessions.", self.frames_rendered_total.get() as f64),
+            ("blog_cache_hits_total", "counter", "Blog post lookups served from the cache.", self.blog_cache_hits_total.get() as f64),
+            ("blog_cache_misses_total", "counter", "Blog post lookups which had to fetch from the blog source.", self.blog_cache_misses_total.get() as f64),
+        ];
+
+        for (name, kind, help, value) in scalars {
+            write_header(&mut out, name, kind, help);
+            let _ = writeln!(out, "{name} {value}");
+        }
+
+        for (name, help, counters) in [
+            ("ssh_auth_total", "SSH authentication attempts by method.", &self.ssh_auth_total),
+            (
+                "terminal_kinds_total",
+                "Sessions by detected terminal kind.",
+                &self.terminal_kinds_total,
+            ),
+            ("http_requests_total", "HTTP requests handled.", &self.http_requests_total),
+        ] {
+            write_header(&mut out, name, "counter", help);
+            let Ok(values) = counters.values.lock() else { continue };
+            for (label_values, value) in values.iter() {
+                let labels = counters
+                    .labels
+                    .iter()
+                    .zip(label_values)
+                    .map(|(label, value)| format!("{label}=\"{}\"", escape(value)))
+                    .collect::<Vec<_>>()
+                    .join(",");
+
+                let _ = writeln!(out, "{name}{{{labels}}} {value}");
+            }
+        }
+
+        for (name, help, histogram) in [
+            (
+                "atproto_fetch_seconds",
+                "Latency of fetching blog posts from ATProto.",
+                &self.atproto_fetch_seconds,
+            ),
+            (
+                "http_request_seconds",
+                "Latency of handling HTTP requests.",
+                &self.http_request_seconds,
+            ),
+        ] {
+            write_header(&mut out, name, "histogram", help);
+            for (bound, count) in histogram.buckets.iter().zip(&histogram.counts) {
+                let _ = writeln!(
+                    out,
+                    "{name}_bucket{{le=\"{bound}\"}} {}",
+                    count.load(Ordering::Relaxed)
+                );
+            }
+
+            let count = histogram.count.load(Ordering::Relaxed);
+            let sum = histogram.sum_micros.load(Ordering::Relaxed) as f64 / 1_000_000.0;
+            let _ = writeln!(out, "{name}_bucket{{le=\"+Inf\"}} {count}");
+            let _ = writeln!(out, "{name}_sum {sum}");
+            let _ = writeln!(out, "{name}_count {count}");
+        }
+
+        out
+    }
+}
+
+fn write_header(out: &mut String, name: &str, kind: &str, help: &str) {
+    let _ = writeln!(out, "# HELP {name} {help}");
+    let _ = writeln!(out, "# TYPE {name} {kind}");
+}
+
+/// Escapes a label value as required by the text format.
+fn escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+/// Serves the metrics at `/metrics`, on an address of their own so that they can be kept
+/// away from the public web server.
+pub async fn serve(addr: SocketAddr) -> io::Result<()> {
+    let app = Router::new().route("/metrics", get(handle_metrics));
+    let listener = tokio::net::TcpListener::bind(addr).await?;
+    tracing::info!("Serving metrics on {addr}");
+
+    axum::serve(listener, app).await
+}
+
+/// Handler for the `/metrics` route.
+pub async fn handle_metrics() -> impl IntoResponse {
+    ([(header::CONTENT_TYPE, "text/plain; version=0.0.4")], METRICS.render())
+}
+
+/// Middleware recording the count and latency of HTTP requests.
+pub async fn track_http(request: Request, next: Next) -> Response {
+    let method = request.method().to_string();
+    let path = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map_or("unmatched", |path| path.as_str())
+        .to_string();
+
+    let start = Instant::now();
+    let response = next.run(request).await;
+    METRICS.http_request_seconds.observe(start.elapsed());
+    METRICS.http_requests_total.inc(&[&method, &path, response.status().as_str()]);
+
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_render_metrics() {
+        let metrics = Metrics::default();
+        metrics.ssh_sessions_active.inc();
+        metrics.ssh_auth_total.inc(&["none"]);
+        metrics.ssh_auth_total.inc(&["none"]);
+        metrics.atproto_fetch_seconds.observe(Duration::from_millis(30));
+
+        let rendered = metrics.render();
+        let lines = rendered.lines().filter(|line| !line.starts_with('#')).collect::<Vec<_>>();
+
+        assert!(lines.contains(&"ssh_sessions_active 1"));
+        assert!(lines.contains(&"ssh_auth_total{method=\"none\"} 2"));
+        assert!(lines.contains(&"atproto_fetch_seconds_bucket{le=\"0.025\"} 0"));
+        assert!(lines.contains(&"atproto_fetch_seconds_bucket{le=\"0.05\"} 1"));
+        assert!(lines.contains(&"atproto_fetch_seconds_bucket{le=\"+Inf\"} 1"));
+        assert!(lines.contains(&"atproto_fetch_seconds_sum 0.03"));
+    }
+
+    #[test]
+    fn test_escape_label_value() {
+        assert_eq!(escape("a\"b\\c\nd"), "a\\\"b\\\\c\\nd");
+    }
+}
@@ -1,102 +1,285 @@
-use std::io::Write;
-use std::net::SocketAddr;
+use std::future::Future;
+use std::io::{self, Write};
+use std::mem;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use std::sync::Arc;
+use std::time::Instant;
 
 use color_eyre::eyre::{self, eyre};
-use russh::server::{Auth, Config, Handle, Handler, Msg, Server, Session};
-use russh::{Channel, ChannelId, CryptoVec, Pty};
+use russh::keys::{HashAlg, PublicKey};
+use russh::server::{Auth, Config, Handle, Handler, Msg, Response, Server, Session};
+use russh::{Channel, ChannelId, CryptoVec, MethodKind, MethodSet, Pty};
 use tokio::net::TcpListener;
-use tokio::runtime::Handle as TokioHandle;
-use tokio::sync::{mpsc, oneshot, Mutex, RwLock};
+use tokio::sync::{mpsc, oneshot, Mutex, Notify, RwLock};
+use tokio::task::JoinHandle;
 use tracing::instrument;
 
 use crate::app::App;
+use crate::health::HEALTH;
+use crate::metrics::METRICS;
 use crate::tui::backend::SshBackend;
+use crate::tui::pacing::FramePacer;
+use crate::tui::palette::ColorDepth;
+use crate::tui::probe::{TerminalProbe, PROBE_QUERY};
+use crate::tui::pty::LineDiscipline;
 use crate::tui::terminal::{TerminalInfo, TerminalKind};
+use crate::tui::terminfo::Multiplexer;
 use crate::tui::{Terminal, Tui};
-use crate::OPTIONS;
+use crate::{OPTIONS, SESSIONS};
+
+/// Environment variable which visitors can forward to force a color depth, e.g.
+/// `SSH_PORTFOLIO_COLORS=256 ssh -o SendEnv=SSH_PORTFOLIO_COLORS ...`.
+const COLOR_DEPTH_ENV: &str = "SSH_PORTFOLIO_COLORS";
+
+/// The authentication methods offered to clients. With the guestbook, keys tell visitors
+/// apart, and those without one get in through keyboard-interactive authentication without
+/// being asked anything. Otherwise there is no need for either, so none are offered.
+pub fn auth_methods() -> MethodSet {
+    if cfg!(feature = "guestbook") {
+        MethodSet::from(&[MethodKind::PublicKey, MethodKind::KeyboardInteractive][..])
+    } else {
+        MethodSet::empty()
+    }
+}
 
-#[derive(Debug)]
-pub struct TermWriter {
-    inner: CryptoVec,
+/// Who is on the other end of a session, as far as authentication tells.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(not(any(feature = "guestbook", feature = "contact")), allow(dead_code))]
+pub struct Visitor {
+    /// The username the visitor connected with.
+    pub user: String,
+    /// The SHA-256 fingerprint of the key the visitor authenticated with, if they did.
+    pub fingerprint: Option<String>,
+    /// The address the visitor connected from.
+    pub address: Option<IpAddr>,
+}
+
+/// Somewhere the output of a session can be sent to, implemented for SSH channels and mocked
+/// in tests.
+pub trait OutputSink: Send + 'static {
+    fn send(&mut self, data: CryptoVec) -> impl Future<Output = eyre::Result<()>> + Send;
+}
 
+/// Sends output to the client through a channel of its SSH session.
+#[derive(Debug)]
+pub struct ChannelSink {
     session: Handle,
     channel: Channel<Msg>,
 }
 
+impl OutputSink for ChannelSink {
+    async fn send(&mut self, data: CryptoVec) -> eyre::Result<()> {
+        self.session
+            .data(self.channel.id(), data)
+            .await
+            .map_err(|_| eyre!("Failed to send data to channel {}", self.channel.id()))
+    }
+}
+
+/// Output flushed by a [`TermWriter`], waiting to be sent by [`Output::drain`].
+///
+/// Anything flushed while a send is in progress is batched into the next one, and the
+/// session's [`FramePacer`] holds back frames until the backlog has been sent.
+#[derive(Debug)]
+pub struct Output {
+    pending: std::sync::Mutex<CryptoVec>,
+    /// The size of the batch being sent, which still counts towards the backlog until the
+    /// send completes.
+    in_flight: AtomicUsize,
+    notify: Notify,
+    closed: AtomicBool,
+    pacer: Arc<FramePacer>,
+}
+
+impl Output {
+    pub fn new(pacer: Arc<FramePacer>) -> Arc<Self> {
+        Arc::new(Self {
+            pending: std::sync::Mutex::new(CryptoVec::new()),
+            in_flight: AtomicUsize::new(0),
+            notify: Notify::new(),
+            closed: AtomicBool::new(false),
+            pacer,
+        })
+    }
+
+    /// Queues bytes to be sent, leaving the given buffer empty. Anything queued after the
+    /// output was closed is discarded.
+    fn push(&self, bytes: &mut Vec<u8>) -> io::Result<()> {
+        // Checked with the queue locked, so that nothing is left behind once it's been cleared
+        let mut pending =
+            self.pending.lock().map_err(|_| io::Error::other("output queue poisoned"))?;
+        if self.closed.load(Ordering::Relaxed) {
+            bytes.clear();
+            return Ok(());
+        }
+
+        pending.extend(bytes);
+        bytes.clear();
+
+        self.pacer.set_backlog(pending.len() + self.in_flight.load(Ordering::Relaxed));
+        self.notify.notify_one();
+        Ok(())
+    }
+
+    /// Stops accepting output, [`Output::drain`] returns once everything queued so far is
+    /// sent.
+    pub fn close(&self) {
+        self.closed.store(true, Ordering::Relaxed);
+        self.notify.notify_one();
+    }
+
+    /// Sends queued output to the sink until the output is closed, or the sink fails.
+    pub async fn drain(self: Arc<Self>, mut sink: impl OutputSink) {
+        loop {
+            let batch = match self.pending.lock() {
+                Ok(mut pending) => {
+                    self.in_flight.store(pending.len(), Ordering::Relaxed);
+                    mem::replace(&mut *pending, CryptoVec::new())
+                }
+                Err(_) => break,
+            };
+
+            if batch.is_empty() {
+                if self.closed.load(Ordering::Relaxed) {
+                    break;
+                }
+
+                self.notify.notified().await;
+                continue;
+            }
+
+            let (start, sent) = (Instant::now(), batch.len());
+            if let Err(err) = sink.send(batch).await {
+                tracing::error!("Stopped sending output: {err}");
+
+                // Nothing will be sent anymore, so none of it should hold back rendering
+                self.close();
+                if let Ok(mut pending) = self.pending.lock() {
+                    *pending = CryptoVec::new();
+                }
+                self.in_flight.store(0, Ordering::Relaxed);
+                self.pacer.set_backlog(0);
+                break;
+            }
+
+            // Only what was flushed during the send is left behind once it's done
+            match self.pending.lock() {
+                Ok(pending) => {
+                    self.in_flight.store(0, Ordering::Relaxed);
+                    self.pacer.set_backlog(pending.len());
+                }
+                Err(_) => break,
+            }
+
+            METRICS.ssh_bytes_flushed_total.inc_by(sent as u64);
+            self.pacer.record_flush(sent, start.elapsed());
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct TermWriter {
+    inner: Vec<u8>,
+    output: Arc<Output>,
+}
+
 impl TermWriter {
-    #[instrument(skip(session, channel), level = "trace", fields(channel_id = %channel.id()))]
-    fn new(session: Handle, channel: Channel<Msg>) -> Self {
+    #[instrument(skip_all, level = "trace")]
+    fn new(output: Arc<Output>) -> Self {
         tracing::trace!("Acquiring new SSH writer");
-        Self { session, channel, inner: CryptoVec::new() }
+        Self { inner: Vec::new(), output }
     }
 
-    #[optimize(speed)]
-    fn flush_inner(&mut self) -> std::io::Result<()> {
-        let handle = TokioHandle::current();
-        handle.block_on(async move {
-            self.session
-                .data(self.channel.id(), self.inner.clone())
-                .await
-                .map_err(|err| {
-                    std::io::Error::other(String::from_iter(
-                        err.iter().map(|item| *item as char),
-                    ))
-                })
-                .map(|_| self.inner.clear())
-        })
+    pub fn pacer(&self) -> Arc<FramePacer> {
+        Arc::clone(&self.output.pacer)
+    }
+}
+
+impl Drop for TermWriter {
+    fn drop(&mut self) {
+        // Lets the drain task end if the session went away without closing the output
+        self.output.close();
     }
 }
 
 impl Write for TermWriter {
     #[instrument(skip(self, buf), level = "debug")]
     #[optimize(speed)]
-    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
         tracing::trace!("Writing {} bytes into SSH terminal writer buffer", buf.len());
-        self.inner.extend(buf);
+        self.inner.extend_from_slice(buf);
         Ok(buf.len())
     }
 
     #[instrument(skip(self), level = "trace")]
     #[optimize(speed)]
-    fn flush(&mut self) -> std::io::Result<()> {
+    fn flush(&mut self) -> io::Result<()> {
+        // Several flushes can happen without anything being written in between, such as when
+        // a frame had no changes, so there's no need to send empty packets for those
+        if self.inner.is_empty() {
+            return Ok(());
+        }
+
         tracing::trace!("Flushing SSH terminal writer buffer");
-        tokio::task::block_in_place(|| self.flush_inner())
+        self.output.push(&mut self.inner)
     }
 }
 
 #[allow(clippy::type_complexity)]
 pub struct SshSession {
     terminal_info: Arc<RwLock<TerminalInfo>>,
+    terminal_probe: Option<TerminalProbe>,
+    probe_sent_at: Option<Instant>,
+    pacer: Arc<FramePacer>,
+    line_discipline: Arc<LineDiscipline>,
+    term_dims: (u16, u16),
     app: Option<Arc<Mutex<App>>>,
     keystroke_tx: mpsc::UnboundedSender<Vec<u8>>,
     resize_tx: mpsc::UnboundedSender<(u16, u16)>,
     init_dims_tx: Option<oneshot::Sender<((u16, u16), (u16, u16))>>,
     init_dims_rx: Option<oneshot::Receiver<((u16, u16), (u16, u16))>>,
     tui: Arc<RwLock<Option<Tui>>>,
+    visitor: Arc<RwLock<Visitor>>,
 }
 
 impl SshSession {
-    pub fn new() -> Self {
+    pub fn new(peer_addr: Option<SocketAddr>) -> Self {
         let (keystroke_tx, keystroke_rx) = mpsc::unbounded_channel();
         let (resize_tx, resize_rx) = mpsc::unbounded_channel();
         let (init_dims_tx, init_dims_rx) = oneshot::channel();
 
         let term_info = Arc::new(RwLock::new(TerminalInfo::default()));
+        let visitor = Arc::new(RwLock::new(Visitor {
+            address: peer_addr.map(|addr| addr.ip()),
+            ..Visitor::default()
+        }));
+        METRICS.ssh_sessions_total.inc();
+        METRICS.ssh_sessions_active.inc();
+
+        let app = App::new(
+            term_info,
+            OPTIONS.tick_rate,
+            OPTIONS.frame_rate,
+            keystroke_rx,
+            resize_rx,
+        );
+        #[cfg(any(feature = "guestbook", feature = "contact"))]
+        let app = app.map(|app| app.visitor(Arc::clone(&visitor)));
 
         Self {
             terminal_info: Arc::clone(&term_info),
-            app: App::new(
-                term_info,
-                OPTIONS.tick_rate,
-                OPTIONS.frame_rate,
-                keystroke_rx,
-                resize_rx,
-            )
-            .inspect_err(|err| tracing::error!("Failed to create app: {err}"))
-            .ok()
-            .map(|app| Arc::new(Mutex::new(app))),
+            terminal_probe: None,
+            probe_sent_at: None,
+            pacer: Arc::default(),
+            line_discipline: Arc::new(LineDiscipline::default()),
+            term_dims: (0, 0),
+            app: app
+                .inspect_err(|err| tracing::error!("Failed to create app: {err}"))
+                .ok()
+                .map(|app| Arc::new(Mutex::new(app))),
             tui: Arc::new(RwLock::new(None)),
+            visitor,
             keystroke_tx,
             resize_tx,
             init_dims_tx: Some(init_dims_tx),
@@ -108,10 +291,17 @@ impl SshSession {
         app: Arc<Mutex<App>>,
         term: Arc<Mutex<Terminal>>,
         tui: Arc<RwLock<Option<Tui>>>,
+        output: (Arc<Output>, JoinHandle<()>),
         session: &Handle,
         channel_id: ChannelId,
     ) -> eyre::Result<()> {
         app.lock_owned().await.run(term, tui).await?;
+
+        // Everything the app wrote has to reach the client before the channel is closed
+        let (output, drain) = output;
+        output.close();
+        drain.await?;
+
         session.close(channel_id).await.map_err(|_| eyre!("failed to close session"))?;
         session
             .exit_status_request(channel_id, 0)
@@ -120,11 +310,63 @@ impl SshSession {
     }
 }
 
+impl Drop for SshSession {
+    fn drop(&mut self) {
+        METRICS.ssh_sessions_active.dec();
+    }
+}
+
 impl Handler for SshSession {
     type Error = eyre::Error;
 
     #[instrument(skip(self), name = "user_login", fields(method = "none"))]
     async fn auth_none(&mut self, user: &str) -> Result<Auth, Self::Error> {
+        METRICS.ssh_auth_total.inc(&["none"]);
+
+        // Clients try this first, if it were accepted they'd never offer the keys the
+        // guestbook tells visitors apart by
+        if cfg!(feature = "guestbook") {
+            return Ok(Auth::Reject {
+                proceed_with_methods: Some(auth_methods()),
+                partial_success: false,
+            });
+        }
+
+        Ok(Auth::Accept)
+    }
+
+    #[instrument(skip(self, public_key), name = "user_login", fields(method = "publickey"))]
+    async fn auth_publickey(
+        &mut self,
+        user: &str,
+        public_key: &PublicKey,
+    ) -> Result<Auth, Self::Error> {
+        METRICS.ssh_auth_total.inc(&["publickey"]);
+
+        // Any key is accepted, they're only used to tell visitors apart
+        let fingerprint = public_key.fingerprint(HashAlg::Sha256).to_string();
+        tracing::debug!(%fingerprint, "Visitor authenticated with a key");
+        let mut visitor = self.visitor.write().await;
+        visitor.user = user.to_string();
+        visitor.fingerprint = Some(fingerprint);
+
+        Ok(Auth::Accept)
+    }
+
+    #[instrument(
+        skip(self, _response),
+        name = "user_login",
+        fields(method = "keyboard-interactive")
+    )]
+    async fn auth_keyboard_interactive<'a>(
+        &'a mut self,
+        user: &str,
+        _submethods: &str,
+        _response: Option<Response<'a>>,
+    ) -> Result<Auth, Self::Error> {
+        METRICS.ssh_auth_total.inc(&["keyboard-interactive"]);
+        self.visitor.write().await.user = user.to_string();
+
         Ok(Auth::Accept)
     }
 
@@ -140,24 +382,46 @@ impl Handler for SshSession {
 
             let inner_app = Arc::clone(app);
             let tui = Arc::clone(&self.tui);
+            let line_discipline = Arc::clone(&self.line_discipline);
+            let pacer = Arc::clone(&self.pacer);
             let rx = self.init_dims_rx.take().unwrap();
 
             tracing::info!("Serving app to open session");
-            tokio::task::spawn(async move {
+            SESSIONS.spawn(async move {
                 let result =
                     async || -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
                         let ((term_width, term_height), (pixel_width, pixel_height)) =
                             rx.await?;
+
+                        // Ask the terminal what it is capable of, the responses are picked out
+                        // of the input stream in `Handler::data`
+                        session_handle
+                            .data(channel_id, CryptoVec::from_slice(PROBE_QUERY.as_bytes()))
+                            .await
+                            .map_err(|_| "Failed to send terminal probe")?;
+
+                        let output = Output::new(pacer);
+                        let sink = ChannelSink { session: session_handle.clone(), channel };
+                        let drain = tokio::task::spawn(Arc::clone(&output).drain(sink));
+
                         let writer = Arc::new(Mutex::new(Terminal::new(SshBackend::new(
-                            TermWriter::new(session_handle.clone(), channel),
+                            TermWriter::new(Arc::clone(&output)),
+                            line_discipline,
                             term_width,
                             term_height,
                             pixel_width,
                             pixel_height,
                         ))?));
 
-                        Self::run_app(inner_app, writer, tui, &session_handle, channel_id)
-                            .await?;
+                        Self::run_app(
+                            inner_app,
+                            writer,
+                            tui,
+                            (output, drain),
+                            &session_handle,
+                            channel_id,
+                        )
+                        .await?;
                         Ok(())
                     };
 
@@ -184,18 +448,27 @@ impl Handler for SshSession {
         variable_value: &str,
         _session: &mut Session,
     ) -> Result<(), Self::Error> {
-        // FIXME: currently, terminals which don't set `$TERM_PROGRAM` just get stuck in the
-        // polling loop forever where we wait for the type to be probed, a workaround is to force
-        // set the variable to an empty string or something invalid:
-        //
-        // `TERM_PROGRAM="" ssh -o SendEnv=TERM_PROGRAM devcomp.xyz`
-        if variable_name == "TERM_PROGRAM" {
-            self.terminal_info
-                .write()
-                .await
-                .set_kind(TerminalKind::from_term_program(variable_value));
-
-            tracing::info!("Terminal program found: {:?}", self.terminal_info);
+        // `$TERM_PROGRAM` is only forwarded if the client is configured to do so, but it takes
+        // precedence over the terminal kind reported by the capability probe if it is
+        let mut terminal_info = self.terminal_info.write().await;
+        match variable_name {
+            "TERM_PROGRAM" if variable_value == "tmux" => {
+                // tmux overwrites `$TERM_PROGRAM`, so the terminal is left for the probe to detect
+                terminal_info.set_multiplexer(Multiplexer::Tmux);
+            }
+            "TERM_PROGRAM" => {
+                terminal_info.set_kind(TerminalKind::from_term_program(variable_value));
+                tracing::info!("Terminal program found: {:?}", terminal_info.kind());
+            }
+            "TMUX" => terminal_info.set_multiplexer(Multiplexer::Tmux),
+            "STY" => terminal_info.set_multiplexer(Multiplexer::Screen),
+            "COLORTERM" => terminal_info.set_colorterm(variable_value),
+            "NO_COLOR" => terminal_info.set_no_color(!variable_value.is_empty()),
+            COLOR_DEPTH_ENV => match variable_value.parse::<ColorDepth>() {
+                Ok(depth) => terminal_info.force_color_depth(depth),
+                Err(err) => tracing::warn!("Ignoring {COLOR_DEPTH_ENV}: {err}"),
+            },
+            _ => {}
         }
 
         Ok(())
@@ -210,11 +483,15 @@ impl Handler for SshSession {
         row_height: u32,
         pix_width: u32,
         pix_height: u32,
-        _modes: &[(Pty, u32)],
+        modes: &[(Pty, u32)],
         session: &mut Session,
     ) -> Result<(), Self::Error> {
         tracing::info!("PTY requested by terminal: {term}");
         tracing::debug!("dims: {col_width} * {row_height}, pixel: {pix_width} * {pix_height}");
+        if !self.terminal_info.write().await.set_term(term) {
+            session.channel_failure(channel_id)?;
+            return Err(eyre!("Unsupported terminal type: {term}"));
+        }
 
         #[cfg(feature = "blog")]
         if pix_width != 0 && pix_height != 0 {
@@ -228,10 +505,10 @@ impl Handler for SshSession {
             ));
         }
 
-        if !term.contains("xterm") {
-            session.channel_failure(channel_id)?;
-            return Err(eyre!("Unsupported terminal type: {term}"));
-        }
+        self.line_discipline.configure(modes);
+        self.term_dims = (col_width as u16, row_height as u16);
+        self.terminal_probe = Some(TerminalProbe::new());
+        self.probe_sent_at = Some(Instant::now());
 
         let tx = self.init_dims_tx.take().unwrap();
         if !tx.is_closed() {
@@ -255,10 +532,55 @@ impl Handler for SshSession {
         data: &[u8],
         _session: &mut Session,
     ) -> Result<(), Self::Error> {
+        let mut data = data.to_vec();
+        if let Some(probe) = self.terminal_probe.as_mut() {
+            data = probe.feed(&data);
+
+            // How long the probe took to be answered is our best guess of the round trip time
+            if probe.is_done() {
+                if let Some(sent_at) = self.probe_sent_at.take() {
+                    self.pacer.record_rtt(sent_at.elapsed());
+                }
+            }
+
+            let mut terminal_info = self.terminal_info.write().await;
+            if probe.is_done() || terminal_info.is_probed() {
+                let (result, mut pending) = self.terminal_probe.take().unwrap().finish();
+                data.append(&mut pending);
+
+                // The app may have already given up on waiting for a response
+                if !terminal_info.is_probed() {
+                    tracing::debug!("Terminal probe finished: {result:?}");
+                    terminal_info.apply_probe(result, self.term_dims);
+                }
+            }
+
+            if data.is_empty() {
+                return Ok(());
+            }
+        }
+
+        // Probe responses are never translated, they don't come from the keyboard
+        let data = self.line_discipline.translate(&data);
+        if data.is_empty() {
+            return Ok(());
+        }
+
         tracing::debug!("Received keystroke data from SSH: {:?}, sending", data);
-        self.keystroke_tx
-            .send(data.to_vec())
-            .map_err(|_| eyre!("Failed to send event keystroke data"))
+        METRICS.ssh_keystrokes_total.inc();
+        self.keystroke_tx.send(data).map_err(|_| eyre!("Failed to send event keystroke data"))
+    }
+
+    #[instrument(skip_all, fields(channel_id = %_channel_id), level = "trace")]
+    async fn window_adjusted(
+        &mut self,
+        _channel_id: ChannelId,
+        new_size: u32,
+        _: &mut Session,
+    ) -> Result<(), Self::Error> {
+        tracing::trace!("Channel window adjusted by client to {new_size} bytes");
+        self.pacer.set_window(new_size);
+        Ok(())
     }
 
     #[instrument(skip_all, fields(channel_id = %_channel_id))]
@@ -273,6 +595,7 @@ impl Handler for SshSession {
     ) -> Result<(), Self::Error> {
         tracing::info!("Terminal window resized by client, notifying components");
         tracing::debug!("dims: {col_width} * {row_height}, pixel: {pix_width} * {pix_height}");
+        self.term_dims = (col_width as u16, row_height as u16);
 
         #[cfg(feature = "blog")]
         self.terminal_info.write().await.set_font_size((
@@ -296,6 +619,7 @@ impl SshServer {
     pub async fn start(addr: SocketAddr, config: Config) -> eyre::Result<()> {
         let listener = TcpListener::bind(addr).await?;
         tracing::info!("SSH server listening!");
+        HEALTH.set_ssh_listening(true);
 
         Self.run_on_socket(Arc::new(config), &listener).await.map_err(|err| eyre!(err))
     }
@@ -306,6 +630,104 @@ impl Server for SshServer {
 
     #[instrument(skip(self))]
     fn new_client(&mut self, peer_addr: Option<SocketAddr>) -> Self::Handler {
-        tokio::task::block_in_place(SshSession::new)
+        tokio::task::block_in_place(|| SshSession::new(peer_addr))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[derive(Debug, Default)]
+    struct MockSink {
+        sent: Arc<std::sync::Mutex<Vec<Vec<u8>>>>,
+        delay: Duration,
+        fail: bool,
+    }
+
+    impl OutputSink for MockSink {
+        async fn send(&mut self, data: CryptoVec) -> eyre::Result<()> {
+            tokio::time::sleep(self.delay).await;
+            if self.fail {
+                return Err(eyre!("channel closed"));
+            }
+
+            self.sent.lock().unwrap().push(data.to_vec());
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_output_batches_flushes() {
+        let sink = MockSink { delay: Duration::from_millis(10), ..Default::default() };
+        let sent = Arc::clone(&sink.sent);
+        let output = Output::new(Arc::default());
+        let drain = tokio::task::spawn(Arc::clone(&output).drain(sink));
+
+        let mut writer = TermWriter::new(Arc::clone(&output));
+        writer.write_all(b"frame 1;").unwrap();
+        writer.flush().unwrap();
+        writer.flush().unwrap();
+
+        // Wait for the first frame to be picked up, the next two are flushed while it's sent
+        tokio::time::sleep(Duration::from_millis(1)).await;
+        // The first frame is still counted as backlog until it has been sent
+        assert!(output.pacer.is_backed_up());
+        for frame in [b"frame 2;", b"frame 3;"] {
+            writer.write_all(frame).unwrap();
+            writer.flush().unwrap();
+        }
+
+        assert!(output.pacer.is_backed_up());
+        drop(writer);
+        drain.await.unwrap();
+
+        assert_eq!(
+            *sent.lock().unwrap(),
+            [b"frame 1;".to_vec(), b"frame 2;frame 3;".to_vec()]
+        );
+        assert!(!output.pacer.is_backed_up());
+    }
+
+    #[tokio::test]
+    async fn test_output_discarded_once_closed() {
+        let sink = MockSink::default();
+        let sent = Arc::clone(&sink.sent);
+        let output = Output::new(Arc::default());
+        let drain = tokio::task::spawn(Arc::clone(&output).drain(sink));
+
+        let mut writer = TermWriter::new(Arc::clone(&output));
+        writer.write_all(b"bye").unwrap();
+        writer.flush().unwrap();
+        output.close();
+        writer.write_all(b"too late").unwrap();
+        writer.flush().unwrap();
+
+        drain.await.unwrap();
+        assert_eq!(*sent.lock().unwrap(), [b"bye".to_vec()]);
+    }
+
+    #[tokio::test]
+    async fn test_output_closed_on_failed_send() {
+        let sink = MockSink { fail: true, ..Default::default() };
+        let output = Output::new(Arc::default());
+        let drain = tokio::task::spawn(Arc::clone(&output).drain(sink));
+
+        let mut writer = TermWriter::new(Arc::clone(&output));
+        writer.write_all(b"lost").unwrap();
+        writer.flush().unwrap();
+        assert!(output.pacer.is_backed_up());
+        drain.await.unwrap();
+
+        // Output that can't be sent anymore is dropped, rather than backing up for good
+        assert!(!output.pacer.is_backed_up());
+        writer.write_all(b"also lost").unwrap();
+        writer.flush().unwrap();
+        assert!(!output.pacer.is_backed_up());
+        assert!(output.pending.lock().unwrap().is_empty());
     }
 }
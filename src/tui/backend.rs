@@ -1,21 +1,74 @@
-use std::io;
+use std::io::{self, Write};
 use std::ops::{Deref, DerefMut};
+use std::sync::Arc;
 
 use ratatui::backend::{Backend, CrosstermBackend, WindowSize};
+use ratatui::buffer::Cell;
 use ratatui::layout::Size;
+use tokio::sync::mpsc;
 
 use crate::ssh::TermWriter;
+use crate::tui::pacing::FramePacer;
+use crate::tui::palette::ColorDepth;
+use crate::tui::pty::LineDiscipline;
+use crate::tui::terminfo::Glyphs;
+
+/// A backend which renders for a remote client, over SSH or a WebSocket.
+pub trait SessionBackend: Backend + Write + Send + 'static {
+    /// Sets the dimensions of the client's terminal, as reported by the client.
+    fn set_dims(&mut self, dims: (u16, u16));
+
+    /// Sets the color depth to downsample to before writing cells.
+    fn set_color_depth(&mut self, color_depth: ColorDepth);
+
+    /// Sets the glyphs to substitute symbols with before writing cells.
+    fn set_glyphs(&mut self, glyphs: Glyphs);
+
+    /// Puts the client's terminal into raw mode while the app is displayed, and back into
+    /// cooked mode once it isn't.
+    fn set_raw_mode(&mut self, raw: bool);
+
+    /// The pacer deciding how often frames are rendered for the client.
+    fn pacer(&self) -> Arc<FramePacer>;
+}
+
+/// Draws cells using the given crossterm backend, mapping their colors and symbols to what the
+/// client can actually display.
+#[optimize(speed)]
+fn draw_mapped<'a, W, I>(
+    inner: &mut CrosstermBackend<W>,
+    content: I,
+    color_depth: ColorDepth,
+    glyphs: Glyphs,
+) -> io::Result<()>
+where
+    W: Write,
+    I: Iterator<Item = (u16, u16, &'a Cell)>, {
+    if color_depth == ColorDepth::TrueColor && glyphs == Glyphs::Full {
+        return inner.draw(content);
+    }
+
+    let cells = content
+        .map(|(x, y, cell)| (x, y, glyphs.map_cell(color_depth.map_cell(cell))))
+        .collect::<Vec<_>>();
+
+    inner.draw(cells.iter().map(|(x, y, cell)| (*x, *y, cell.as_ref())))
+}
 
 #[derive(Debug)]
 pub struct SshBackend {
     inner: CrosstermBackend<TermWriter>,
     pub dims: (u16, u16),
     pub pixel: (u16, u16),
+    pub color_depth: ColorDepth,
+    pub glyphs: Glyphs,
+    pub line_discipline: Arc<LineDiscipline>,
 }
 
 impl SshBackend {
     pub fn new(
         writer: TermWriter,
+        line_discipline: Arc<LineDiscipline>,
         init_width: u16,
         init_height: u16,
         init_pixel_width: u16,
@@ -26,6 +79,9 @@ impl SshBackend {
             inner,
             dims: (init_width, init_height),
             pixel: (init_pixel_width, init_pixel_height),
+            color_depth: ColorDepth::default(),
+            glyphs: Glyphs::default(),
+            line_discipline,
         }
     }
 }
@@ -39,7 +95,7 @@ impl Backend for SshBackend {
     fn draw<'a, I>(&mut self, content: I) -> io::Result<()>
     where
         I: Iterator<Item = (u16, u16, &'a ratatui::buffer::Cell)>, {
-        self.inner.draw(content)
+        draw_mapped(&mut self.inner, content, self.color_depth, self.glyphs)
     }
 
     fn hide_cursor(&mut self) -> io::Result<()> {
@@ -91,3 +147,168 @@ impl DerefMut for SshBackend {
         &mut self.inner
     }
 }
+
+impl Write for SshBackend {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.inner.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Write::flush(&mut self.inner)
+    }
+}
+
+impl SessionBackend for SshBackend {
+    fn set_dims(&mut self, dims: (u16, u16)) {
+        self.dims = dims;
+    }
+
+    fn set_color_depth(&mut self, color_depth: ColorDepth) {
+        self.color_depth = color_depth;
+    }
+
+    fn set_glyphs(&mut self, glyphs: Glyphs) {
+        self.glyphs = glyphs;
+    }
+
+    fn set_raw_mode(&mut self, raw: bool) {
+        self.line_discipline.set_raw(raw);
+    }
+
+    fn pacer(&self) -> Arc<FramePacer> {
+        self.inner.writer().pacer()
+    }
+}
+
+/// Buffers frames rendered for a web terminal until flushed, after which they are sent off
+/// to be written to the WebSocket.
+#[derive(Debug)]
+pub struct WebTermWriter {
+    inner: Vec<u8>,
+    output_tx: mpsc::UnboundedSender<Vec<u8>>,
+}
+
+impl WebTermWriter {
+    pub fn new(output_tx: mpsc::UnboundedSender<Vec<u8>>) -> Self {
+        Self { inner: Vec::new(), output_tx }
+    }
+}
+
+impl Write for WebTermWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.inner.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        if self.inner.is_empty() {
+            return Ok(());
+        }
+
+        self.output_tx
+            .send(std::mem::take(&mut self.inner))
+            .map_err(|_| io::Error::new(io::ErrorKind::BrokenPipe, "WebSocket closed"))
+    }
+}
+
+/// The [`SshBackend`] equivalent for browsers, writing frames to a WebSocket connected to an
+/// xterm.js terminal.
+#[derive(Debug)]
+pub struct WebBackend {
+    inner: CrosstermBackend<WebTermWriter>,
+    pub dims: (u16, u16),
+    pub color_depth: ColorDepth,
+    pub glyphs: Glyphs,
+    /// Never fed any measurements, the WebSocket takes frames as fast as we can render them.
+    pub pacer: Arc<FramePacer>,
+}
+
+impl WebBackend {
+    pub fn new(writer: WebTermWriter, init_width: u16, init_height: u16) -> Self {
+        WebBackend {
+            inner: CrosstermBackend::new(writer),
+            dims: (init_width, init_height),
+            color_depth: ColorDepth::default(),
+            glyphs: Glyphs::default(),
+            pacer: Arc::default(),
+        }
+    }
+}
+
+impl Backend for WebBackend {
+    fn size(&self) -> io::Result<Size> {
+        Ok(Size { width: self.dims.0, height: self.dims.1 })
+    }
+
+    #[optimize(speed)]
+    fn draw<'a, I>(&mut self, content: I) -> io::Result<()>
+    where
+        I: Iterator<Item = (u16, u16, &'a ratatui::buffer::Cell)>, {
+        draw_mapped(&mut self.inner, content, self.color_depth, self.glyphs)
+    }
+
+    fn hide_cursor(&mut self) -> io::Result<()> {
+        self.inner.hide_cursor()
+    }
+
+    fn show_cursor(&mut self) -> io::Result<()> {
+        self.inner.show_cursor()
+    }
+
+    fn get_cursor_position(&mut self) -> io::Result<ratatui::prelude::Position> {
+        self.inner.get_cursor_position()
+    }
+
+    fn set_cursor_position<P: Into<ratatui::prelude::Position>>(
+        &mut self,
+        position: P,
+    ) -> io::Result<()> {
+        self.inner.set_cursor_position(position)
+    }
+
+    fn clear(&mut self) -> io::Result<()> {
+        self.inner.clear()
+    }
+
+    fn window_size(&mut self) -> io::Result<ratatui::backend::WindowSize> {
+        // Browsers don't tell us the pixel size of the terminal
+        Ok(WindowSize { columns_rows: self.size()?, pixels: Size::default() })
+    }
+
+    #[optimize(speed)]
+    fn flush(&mut self) -> io::Result<()> {
+        Backend::flush(&mut self.inner)
+    }
+}
+
+impl Write for WebBackend {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.inner.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Write::flush(&mut self.inner)
+    }
+}
+
+impl SessionBackend for WebBackend {
+    fn set_dims(&mut self, dims: (u16, u16)) {
+        self.dims = dims;
+    }
+
+    fn set_color_depth(&mut self, color_depth: ColorDepth) {
+        self.color_depth = color_depth;
+    }
+
+    fn set_glyphs(&mut self, glyphs: Glyphs) {
+        self.glyphs = glyphs;
+    }
+
+    fn set_raw_mode(&mut self, _raw: bool) {
+        // xterm.js hands us every keystroke regardless, there is no line discipline to change
+    }
+
+    fn pacer(&self) -> Arc<FramePacer> {
+        Arc::clone(&self.pacer)
+    }
+}
@@ -3,7 +3,7 @@
 use std::sync::Arc;
 use std::time::Duration;
 
-use backend::SshBackend;
+use backend::{SessionBackend, SshBackend};
 use color_eyre::Result;
 use crossterm::cursor;
 use crossterm::event::{
@@ -11,19 +11,26 @@ use crossterm::event::{
     KeyEvent, MouseEvent,
 };
 use crossterm::terminal::{EnterAlternateScreen, LeaveAlternateScreen};
+use pacing::FramePacer;
 use serde::{Deserialize, Serialize};
 use status::TuiStatus;
 use tokio::runtime::Handle;
 use tokio::sync::mpsc::{self, UnboundedReceiver, UnboundedSender};
-use tokio::sync::{Mutex, RwLock};
+use tokio::sync::{watch, Mutex};
 use tokio::task::{block_in_place, JoinHandle};
-use tokio::time::{interval, sleep, timeout};
+use tokio::time::{interval, sleep, sleep_until, timeout, Instant};
 use tokio_util::sync::CancellationToken;
 use tracing::error;
 
 pub(crate) mod backend;
+pub(crate) mod pacing;
+pub(crate) mod palette;
+pub(crate) mod probe;
+pub(crate) mod pty;
 pub(crate) mod status;
 pub(crate) mod terminal;
+pub(crate) mod terminfo;
+pub(crate) mod text_sizing;
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub enum Event {
@@ -41,34 +48,35 @@ pub enum Event {
     Resize(u16, u16),
 }
 
-pub type Terminal = ratatui::Terminal<SshBackend>;
+pub type Terminal<B = SshBackend> = ratatui::Terminal<B>;
 
 #[derive(Debug)]
-pub struct Tui {
-    pub terminal: Arc<Mutex<Terminal>>,
+pub struct Tui<B: SessionBackend = SshBackend> {
+    pub terminal: Arc<Mutex<Terminal<B>>>,
     pub task: JoinHandle<()>,
     pub cancellation_token: CancellationToken,
     pub event_rx: UnboundedReceiver<Event>,
     pub event_tx: UnboundedSender<Event>,
-    pub resume_tx: Option<UnboundedSender<()>>,
-    pub status: Arc<RwLock<TuiStatus>>,
+    pub status: watch::Sender<TuiStatus>,
+    pub pacer: Arc<FramePacer>,
     pub frame_rate: f64,
     pub tick_rate: f64,
     pub mouse: bool,
     pub paste: bool,
 }
 
-impl Tui {
-    pub fn new(terminal: Arc<Mutex<Terminal>>) -> Result<Self> {
+impl<B: SessionBackend> Tui<B> {
+    pub fn new(terminal: Arc<Mutex<Terminal<B>>>) -> Result<Self> {
         let (event_tx, event_rx) = mpsc::unbounded_channel();
+        let pacer = terminal.try_lock()?.backend().pacer();
         Ok(Self {
             terminal,
             task: tokio::spawn(async {}),
             cancellation_token: CancellationToken::new(),
             event_rx,
             event_tx,
-            resume_tx: Option::None,
-            status: Arc::new(RwLock::new(TuiStatus::Active)),
+            status: watch::Sender::new(TuiStatus::Active),
+            pacer,
             frame_rate: 60.0,
             tick_rate: 4.0,
             mouse: false,
@@ -100,7 +108,8 @@ impl Tui {
         self.cancel(); // Cancel any existing task
         self.cancellation_token = CancellationToken::new();
         let event_loop = Self::event_loop(
-            self.status.clone(),
+            self.status.subscribe(),
+            Arc::clone(&self.pacer),
             self.event_tx.clone(),
             self.cancellation_token.clone(),
             self.tick_rate,
@@ -113,27 +122,45 @@ impl Tui {
 
     #[optimize(speed)]
     async fn event_loop(
-        status: Arc<RwLock<TuiStatus>>,
+        status: watch::Receiver<TuiStatus>,
+        pacer: Arc<FramePacer>,
         event_tx: UnboundedSender<Event>,
         cancellation_token: CancellationToken,
         tick_rate: f64,
         frame_rate: f64,
     ) {
-        let mut tick_interval = interval(Duration::from_secs_f64(1.0 / tick_rate));
-        let mut render_interval = interval(Duration::from_secs_f64(1.0 / frame_rate));
+        let tick_period = Duration::from_secs_f64(1.0 / tick_rate);
+        let mut tick_interval = interval(tick_period);
+        let mut next_render = Instant::now();
 
         // if this fails, then it's likely a bug in the calling code
         event_tx.send(Event::Init).expect("failed to send init event");
 
-        let suspension_status = Arc::clone(&status);
         loop {
-            let _guard = suspension_status.read().await;
             let event = tokio::select! {
                 _ = cancellation_token.cancelled() => {
                     break;
                 }
                 _ = tick_interval.tick() => Event::Tick,
-                _ = render_interval.tick() => Event::Render,
+                _ = sleep_until(next_render) => {
+                    // The frame rate adapts to how quickly the client takes in our output, the
+                    // configured one being the fastest we go. While suspended, there's no more
+                    // than the idle countdown to keep up to date, so once a tick is enough
+                    let frame_interval = if *status.borrow() == TuiStatus::Suspended {
+                        tick_period
+                    } else {
+                        pacer.frame_interval(frame_rate)
+                    };
+                    next_render = Instant::now() + frame_interval;
+
+                    // Frames are held back while the last one is still waiting to be sent, and
+                    // the app skips them itself when nothing has changed
+                    if pacer.is_backed_up() {
+                        continue;
+                    }
+
+                    Event::Render
+                }
             };
 
             if event_tx.send(event).is_err() {
@@ -171,7 +198,7 @@ impl Tui {
 
     pub fn enter(&mut self) -> Result<()> {
         let mut term = self.terminal.try_lock()?;
-        // crossterm::terminal::enable_raw_mode()?; // TODO: Enable raw mode for pty
+        term.backend_mut().set_raw_mode(true);
         crossterm::execute!(term.backend_mut(), EnterAlternateScreen, cursor::Hide)?;
 
         if self.mouse {
@@ -188,11 +215,25 @@ impl Tui {
         Ok(())
     }
 
+    /// Starts or stops capturing the mouse. While it's captured, the client can't select text
+    /// with it.
+    pub fn set_mouse(&mut self, mouse: bool) -> Result<()> {
+        let mut term = self.terminal.try_lock()?;
+        if mouse {
+            crossterm::execute!(term.backend_mut(), EnableMouseCapture)?;
+        } else {
+            crossterm::execute!(term.backend_mut(), DisableMouseCapture)?;
+        }
+
+        self.mouse = mouse;
+        Ok(())
+    }
+
     pub async fn exit(&mut self) -> Result<()> {
         self.stop().await?;
-        // TODO: enable raw mode for pty
         let mut term = self.terminal.try_lock()?;
         term.flush()?;
+        term.backend_mut().set_raw_mode(false);
 
         if self.paste {
             crossterm::execute!(term.backend_mut(), DisableBracketedPaste)?;
@@ -210,32 +251,20 @@ impl Tui {
         self.cancellation_token.cancel();
     }
 
-    pub async fn suspend(&mut self) -> Result<Arc<CancellationToken>> {
-        // Exit the current Tui
-        self.exit().await?;
-
-        // Update the status and initialize a cancellation token
-        let token = Arc::new(CancellationToken::new());
-        let suspension = Arc::new(Mutex::new(()));
-        *self.status.write().await = TuiStatus::Suspended(Arc::clone(&suspension));
+    /// Pauses rendering at the frame rate. Unlike a local terminal, there is no shell for an
+    /// SSH visitor to return to, so the alternate screen is kept and the app stays in charge
+    /// of what is displayed.
+    pub fn suspend(&self) {
+        self.status.send_replace(TuiStatus::Suspended);
+    }
 
-        // Spawn a task holding on the lock until a notification interrupts it
-        let status = Arc::clone(&self.status);
-        let lock_release = Arc::clone(&token);
-        tokio::task::spawn(async move {
-            tokio::select! {
-                _ = lock_release.cancelled() => {
-                    // Lock was released, update the status
-                    *status.write().await = TuiStatus::Active;
-                }
-                _ = tokio::time::sleep(tokio::time::Duration::from_secs(u64::MAX)) => {
-                    // Hold on to the lock until notified
-                    let _ = suspension.lock().await;
-                }
-            }
-        });
+    /// Resumes rendering at the frame rate.
+    pub fn resume(&self) {
+        self.status.send_replace(TuiStatus::Active);
+    }
 
-        Ok(token)
+    pub fn is_suspended(&self) -> bool {
+        *self.status.borrow() == TuiStatus::Suspended
     }
 
     pub async fn next_event(&mut self) -> Option<Event> {
@@ -243,7 +272,7 @@ impl Tui {
     }
 }
 
-impl Drop for Tui {
+impl<B: SessionBackend> Drop for Tui<B> {
     fn drop(&mut self) {
         block_in_place(|| {
             let handle = Handle::current();
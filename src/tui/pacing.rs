@@ -0,0 +1,123 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+/// The longest we wait between frames, no matter how slow the link is.
+const MAX_FRAME_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Decides how often a session renders, based on how quickly its client takes in what we send.
+///
+/// Frames are only worth rendering as fast as they can be delivered, anything faster queues up
+/// behind the SSH channel and makes the session lag further and further behind its input.
+#[derive(Debug)]
+pub struct FramePacer {
+    rtt_micros: AtomicU64,
+    flush_micros: AtomicU64,
+    frame_bytes: AtomicU64,
+    window: AtomicU64,
+    backlog: AtomicU64,
+}
+
+impl Default for FramePacer {
+    fn default() -> Self {
+        Self {
+            rtt_micros: AtomicU64::new(0),
+            flush_micros: AtomicU64::new(0),
+            frame_bytes: AtomicU64::new(0),
+            window: AtomicU64::new(u64::MAX),
+            backlog: AtomicU64::new(0),
+        }
+    }
+}
+
+impl FramePacer {
+    /// Records a round trip to the client, such as the capability probe being answered.
+    pub fn record_rtt(&self, rtt: Duration) {
+        average(&self.rtt_micros, rtt.as_micros() as u64);
+    }
+
+    /// Records a flush of a frame, and how long it took for the channel to accept it.
+    pub fn record_flush(&self, bytes: usize, duration: Duration) {
+        average(&self.flush_micros, duration.as_micros() as u64);
+        average(&self.frame_bytes, bytes as u64);
+
+        let _ = self.window.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |window| {
+            Some(window.saturating_sub(bytes as u64))
+        });
+    }
+
+    /// Sets the remaining size of the channel window, whenever the client adjusts it.
+    pub fn set_window(&self, window: u32) {
+        self.window.store(window.into(), Ordering::Relaxed);
+    }
+
+    /// Sets the number of bytes flushed but not yet picked up to be sent.
+    pub fn set_backlog(&self, bytes: usize) {
+        self.backlog.store(bytes as u64, Ordering::Relaxed);
+    }
+
+    /// Whether output of an earlier frame is still waiting to be sent, in which case there is
+    /// no point in rendering another one yet.
+    pub fn is_backed_up(&self) -> bool {
+        self.backlog.load(Ordering::Relaxed) > 0
+    }
+
+    /// How long to wait before rendering the next frame, never rendering faster than the given
+    /// frame rate.
+    pub fn frame_interval(&self, max_frame_rate: f64) -> Duration {
+        let rtt = Duration::from_micros(self.rtt_micros.load(Ordering::Relaxed));
+        let flush = Duration::from_micros(self.flush_micros.load(Ordering::Relaxed));
+        let frame_bytes = self.frame_bytes.load(Ordering::Relaxed);
+
+        // A frame shouldn't be started before the last one has been handed off
+        let mut interval = Duration::from_secs_f64(1.0 / max_frame_rate).max(flush);
+
+        // Once a frame no longer fits in the window, anything we send waits for the client to
+        // adjust it, which takes at least a round trip
+        if self.window.load(Ordering::Relaxed) < frame_bytes {
+            interval = interval.max(rtt);
+        }
+
+        interval.min(MAX_FRAME_INTERVAL)
+    }
+}
+
+/// Folds a sample into an exponentially weighted moving average.
+fn average(value: &AtomicU64, sample: u64) {
+    let _ = value.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |avg| match avg {
+        0 => Some(sample),
+        avg => Some((avg * 7 + sample) / 8),
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_frame_interval() {
+        let pacer = FramePacer::default();
+        assert_eq!(pacer.frame_interval(50.0), Duration::from_millis(20));
+
+        // Flushes slower than the frame rate slow it down
+        pacer.record_flush(4096, Duration::from_millis(40));
+        assert_eq!(pacer.frame_interval(50.0), Duration::from_millis(40));
+
+        // So does a window too small to fit a frame, until the client adjusts it
+        pacer.record_rtt(Duration::from_millis(200));
+        pacer.set_window(1024);
+        assert_eq!(pacer.frame_interval(50.0), Duration::from_millis(200));
+        pacer.set_window(1 << 20);
+        assert_eq!(pacer.frame_interval(50.0), Duration::from_millis(40));
+
+        pacer.set_backlog(512);
+        assert!(pacer.is_backed_up());
+        pacer.set_backlog(0);
+        assert!(!pacer.is_backed_up());
+
+        pacer.record_rtt(Duration::from_secs(10));
+        pacer.set_window(0);
+        assert_eq!(pacer.frame_interval(50.0), MAX_FRAME_INTERVAL);
+    }
+}
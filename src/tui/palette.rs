@@ -0,0 +1,261 @@
+use std::borrow::Cow;
+use std::str::FromStr;
+
+use ratatui::buffer::Cell;
+use ratatui::style::Color;
+use serde::{Deserialize, Serialize};
+use strum::Display;
+
+use crate::tui::terminfo::TermProfile;
+
+/// The colours of the 16 color ANSI palette, using the xterm defaults.
+const ANSI_COLORS: [(Color, (u8, u8, u8)); 16] = [
+    (Color::Black, (0, 0, 0)),
+    (Color::Red, (205, 0, 0)),
+    (Color::Green, (0, 205, 0)),
+    (Color::Yellow, (205, 205, 0)),
+    (Color::Blue, (0, 0, 238)),
+    (Color::Magenta, (205, 0, 205)),
+    (Color::Cyan, (0, 205, 205)),
+    (Color::Gray, (229, 229, 229)),
+    (Color::DarkGray, (127, 127, 127)),
+    (Color::LightRed, (255, 0, 0)),
+    (Color::LightGreen, (0, 255, 0)),
+    (Color::LightYellow, (255, 255, 0)),
+    (Color::LightBlue, (92, 92, 255)),
+    (Color::LightMagenta, (255, 0, 255)),
+    (Color::LightCyan, (0, 255, 255)),
+    (Color::White, (255, 255, 255)),
+];
+
+/// The intensity of each step along an axis of the 6x6x6 color cube of the 256 color palette.
+const CUBE_STEPS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+/// The number of colors a terminal can display, ordered from least to most capable.
+#[derive(
+    Debug,
+    Default,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    Display,
+    Serialize,
+    Deserialize,
+)]
+#[strum(serialize_all = "lowercase")]
+pub enum ColorDepth {
+    Monochrome,
+    Ansi16,
+    Indexed256,
+    #[default]
+    TrueColor,
+}
+
+impl ColorDepth {
+    /// Detects the color depth from the `$TERM` and `$COLORTERM` of the client.
+    pub fn detect(term: Option<&str>, colorterm: Option<&str>) -> Self {
+        if colorterm.is_some_and(|colorterm| matches!(colorterm, "truecolor" | "24bit")) {
+            return Self::TrueColor;
+        }
+
+        match term {
+            Some(term) => TermProfile::from_term(term).map_or(Self::Ansi16, |p| p.color_depth),
+            None => Self::default(),
+        }
+    }
+
+    /// The next lower color depth, wrapping around to true color after monochrome.
+    pub fn next(self) -> Self {
+        match self {
+            Self::TrueColor => Self::Indexed256,
+            Self::Indexed256 => Self::Ansi16,
+            Self::Ansi16 => Self::Monochrome,
+            Self::Monochrome => Self::TrueColor,
+        }
+    }
+
+    /// Maps a color to the closest color which can be displayed at this color depth.
+    pub fn map_color(self, color: Color) -> Color {
+        match (self, color) {
+            (_, Color::Reset) | (Self::TrueColor, _) => color,
+            (Self::Monochrome, _) => Color::Reset,
+            (Self::Indexed256, Color::Rgb(r, g, b)) => Color::Indexed(rgb_to_indexed(r, g, b)),
+            (Self::Indexed256, _) => color,
+            (Self::Ansi16, Color::Rgb(r, g, b)) => rgb_to_ansi((r, g, b)),
+            (Self::Ansi16, Color::Indexed(index)) => rgb_to_ansi(indexed_to_rgb(index)),
+            (Self::Ansi16, _) => color,
+        }
+    }
+
+    /// Maps the colors of a cell to this color depth, only cloning it if anything changed.
+    pub fn map_cell(self, cell: &Cell) -> Cow<'_, Cell> {
+        if self == Self::TrueColor {
+            return Cow::Borrowed(cell);
+        }
+
+        let (fg, bg, underline) = (
+            self.map_color(cell.fg),
+            self.map_color(cell.bg),
+            self.map_color(cell.underline_color),
+        );
+
+        if (fg, bg, underline) == (cell.fg, cell.bg, cell.underline_color) {
+            return Cow::Borrowed(cell);
+        }
+
+        let mut cell = cell.clone();
+        cell.fg = fg;
+        cell.bg = bg;
+        cell.underline_color = underline;
+        Cow::Owned(cell)
+    }
+}
+
+impl FromStr for ColorDepth {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "truecolor" | "24bit" | "16m" => Ok(Self::TrueColor),
+            "256" | "indexed" | "indexed256" => Ok(Self::Indexed256),
+            "16" | "ansi" | "ansi16" => Ok(Self::Ansi16),
+            "mono" | "monochrome" | "none" | "0" => Ok(Self::Monochrome),
+            other => Err(format!("Unknown color depth: {other}")),
+        }
+    }
+}
+
+/// Converts an index of the 256 color palette into an RGB triplet.
+fn indexed_to_rgb(index: u8) -> (u8, u8, u8) {
+    match index {
+        0..16 => ANSI_COLORS[index as usize].1,
+        16..232 => {
+            let index = index - 16;
+            (
+                CUBE_STEPS[(index / 36) as usize],
+                CUBE_STEPS[(index / 6 % 6) as usize],
+                CUBE_STEPS[(index % 6) as usize],
+            )
+        }
+        232.. => {
+            let level = 8 + (index - 232) * 10;
+            (level, level, level)
+        }
+    }
+}
+
+/// Finds the closest color in the 256 color palette, either in the color cube or the
+/// grayscale ramp.
+fn rgb_to_indexed(r: u8, g: u8, b: u8) -> u8 {
+    let closest_step = |value: u8| -> u8 {
+        CUBE_STEPS
+            .iter()
+            .enumerate()
+            .min_by_key(|&(_, &step)| step.abs_diff(value))
+            .map(|(i, _)| i as u8)
+            .unwrap_or_default()
+    };
+
+    let (ri, gi, bi) = (closest_step(r), closest_step(g), closest_step(b));
+    let cube_index = 16 + 36 * ri + 6 * gi + bi;
+
+    let average = ((r as u16 + g as u16 + b as u16) / 3) as u8;
+    let gray_index = if average < 8 { 232 } else { 232 + ((average - 8) / 10).min(23) };
+
+    if distance((r, g, b), indexed_to_rgb(gray_index))
+        < distance((r, g, b), indexed_to_rgb(cube_index))
+    {
+        gray_index
+    } else {
+        cube_index
+    }
+}
+
+/// Finds the closest color in the 16 color ANSI palette.
+fn rgb_to_ansi(rgb: (u8, u8, u8)) -> Color {
+    ANSI_COLORS
+        .iter()
+        .min_by_key(|(_, ansi)| distance(rgb, *ansi))
+        .map(|(color, _)| *color)
+        .unwrap_or(Color::Reset)
+}
+
+/// The squared euclidean distance between two colors, weighted to roughly match how sensitive
+/// the human eye is to each channel.
+fn distance(a: (u8, u8, u8), b: (u8, u8, u8)) -> u32 {
+    let dr = a.0.abs_diff(b.0) as u32;
+    let dg = a.1.abs_diff(b.1) as u32;
+    let db = a.2.abs_diff(b.2) as u32;
+    2 * dr * dr + 4 * dg * dg + 3 * db * db
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_detect_color_depth() {
+        assert_eq!(ColorDepth::detect(Some("xterm-256color"), None), ColorDepth::Indexed256);
+        assert_eq!(
+            ColorDepth::detect(Some("xterm-256color"), Some("truecolor")),
+            ColorDepth::TrueColor
+        );
+        assert_eq!(ColorDepth::detect(Some("xterm-direct"), None), ColorDepth::TrueColor);
+        assert_eq!(ColorDepth::detect(Some("linux"), None), ColorDepth::Ansi16);
+        assert_eq!(ColorDepth::detect(Some("vt100"), None), ColorDepth::Monochrome);
+        assert_eq!(ColorDepth::detect(None, None), ColorDepth::TrueColor);
+    }
+
+    #[test]
+    fn test_indexed_to_rgb() {
+        assert_eq!(indexed_to_rgb(1), (205, 0, 0));
+        assert_eq!(indexed_to_rgb(16), (0, 0, 0));
+        assert_eq!(indexed_to_rgb(183), (215, 175, 255));
+        assert_eq!(indexed_to_rgb(231), (255, 255, 255));
+        assert_eq!(indexed_to_rgb(232), (8, 8, 8));
+        assert_eq!(indexed_to_rgb(255), (238, 238, 238));
+    }
+
+    #[test]
+    fn test_rgb_to_indexed() {
+        assert_eq!(rgb_to_indexed(215, 175, 255), 183);
+        assert_eq!(rgb_to_indexed(255, 0, 0), 196);
+        assert_eq!(rgb_to_indexed(128, 128, 128), 244);
+    }
+
+    #[test]
+    fn test_map_color() {
+        assert_eq!(
+            ColorDepth::Indexed256.map_color(Color::Rgb(255, 0, 0)),
+            Color::Indexed(196)
+        );
+        assert_eq!(ColorDepth::Ansi16.map_color(Color::Rgb(250, 10, 10)), Color::LightRed);
+        assert_eq!(ColorDepth::Ansi16.map_color(Color::Indexed(183)), Color::Gray);
+        assert_eq!(ColorDepth::Ansi16.map_color(Color::Magenta), Color::Magenta);
+        assert_eq!(ColorDepth::Monochrome.map_color(Color::Magenta), Color::Reset);
+        assert_eq!(ColorDepth::TrueColor.map_color(Color::Rgb(1, 2, 3)), Color::Rgb(1, 2, 3));
+    }
+
+    #[test]
+    fn test_map_cell() {
+        let mut cell = Cell::new("a");
+        cell.fg = Color::Rgb(255, 0, 0);
+
+        assert!(matches!(ColorDepth::TrueColor.map_cell(&cell), Cow::Borrowed(_)));
+        assert_eq!(ColorDepth::Indexed256.map_cell(&cell).fg, Color::Indexed(196));
+        assert_eq!(ColorDepth::Monochrome.map_cell(&cell).fg, Color::Reset);
+    }
+
+    #[test]
+    fn test_parse_color_depth() {
+        assert_eq!("256".parse(), Ok(ColorDepth::Indexed256));
+        assert_eq!("Mono".parse(), Ok(ColorDepth::Monochrome));
+        assert!("lots".parse::<ColorDepth>().is_err());
+    }
+}
@@ -0,0 +1,313 @@
+use std::time::Duration;
+
+const ESC: u8 = 0x1b;
+const BEL: u8 = 0x07;
+
+/// How long to wait for the client to answer the probe query before falling back.
+pub const PROBE_TIMEOUT: Duration = Duration::from_millis(750);
+
+/// The image ID used by the kitty graphics protocol query.
+const KITTY_QUERY_ID: &str = "31";
+
+/// Queries sent to the client to find out what its terminal is capable of. The primary device
+/// attributes (DA1) query goes last, since every terminal answers it and terminals respond to
+/// queries in order, its response marks the end of probing.
+pub const PROBE_QUERY: &str = concat!(
+    // Text sizing protocol: draw a blank two cells wide and check where the cursor ended up
+    "\r\x1b]66;w=2; \x07\x1b[6n\r\x1b[K",
+    // XTVERSION, terminal name and version
+    "\x1b[>0q",
+    // Kitty graphics protocol, a query which is answered but not displayed
+    "\x1b_Gi=31,s=1,v=1,a=q,t=d,f=24;AAAA\x1b\\",
+    // Text area size in pixels, then cell size in pixels
+    "\x1b[14t",
+    "\x1b[16t",
+    // Secondary device attributes (DA2)
+    "\x1b[>c",
+    // Primary device attributes (DA1)
+    "\x1b[c",
+);
+
+/// Everything learned about the client's terminal from its responses to [`PROBE_QUERY`].
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ProbeResult {
+    /// The attributes reported in response to DA1.
+    pub device_attributes: Vec<u16>,
+    /// The parameters reported in response to DA2, usually the terminal type and version.
+    pub secondary_attributes: Vec<u16>,
+    /// The name and version reported in response to XTVERSION.
+    pub version: Option<String>,
+    /// Whether the kitty graphics protocol query was acknowledged.
+    pub kitty_graphics: bool,
+    /// Whether the text sizing protocol moved the cursor as expected.
+    pub text_sizing: bool,
+    /// The size of the text area in pixels, as `(width, height)`.
+    pub window_pixels: Option<(u16, u16)>,
+    /// The size of a single cell in pixels, as `(width, height)`.
+    pub cell_pixels: Option<(u16, u16)>,
+}
+
+impl ProbeResult {
+    /// Whether the terminal supports sixel graphics.
+    pub fn sixel(&self) -> bool {
+        self.device_attributes.contains(&4)
+    }
+
+    /// Whether the terminal supports rectangular area operations (DECCRA, DECERA, ...).
+    pub fn rectangular_ops(&self) -> bool {
+        self.device_attributes.contains(&28)
+    }
+
+    /// The size of a cell in pixels, derived from the window size if the terminal did not
+    /// report it directly.
+    pub fn cell_size(&self, dims: (u16, u16)) -> Option<(u16, u16)> {
+        self.cell_pixels.filter(|&(w, h)| w != 0 && h != 0).or_else(|| {
+            let (width, height) = self.window_pixels?;
+            let (cols, rows) = dims;
+            (width != 0 && height != 0 && cols != 0 && rows != 0)
+                .then(|| (width / cols, height / rows))
+        })
+    }
+}
+
+/// Incrementally parses probe responses out of the client's input stream. Bytes which are not
+/// part of a response, such as keys pressed while probing, are handed back to be processed as
+/// regular input.
+#[derive(Debug, Default)]
+pub struct TerminalProbe {
+    pending: Vec<u8>,
+    result: ProbeResult,
+    done: bool,
+    /// Whether the cursor position query has yet to be answered. Its response looks just like
+    /// a key with modifiers, such as `CSI 1 ; 2 R` for Shift+F3, so only one is taken as such.
+    awaiting_cursor_position: bool,
+}
+
+enum Parsed {
+    /// A complete escape sequence of the given length which is a probe response.
+    Response(usize),
+    /// A complete escape sequence of the given length which is regular input.
+    Input(usize),
+    /// The sequence is not complete yet.
+    Incomplete,
+}
+
+impl TerminalProbe {
+    pub fn new() -> Self {
+        Self { awaiting_cursor_position: true, ..Self::default() }
+    }
+
+    /// Whether the final response has been received.
+    pub fn is_done(&self) -> bool {
+        self.done
+    }
+
+    /// Consume the probe, returning the results and any input which was held back as a
+    /// possible incomplete response.
+    pub fn finish(self) -> (ProbeResult, Vec<u8>) {
+        (self.result, self.pending)
+    }
+
+    /// Feed input from the client into the probe, returning any bytes which were not part of a
+    /// probe response.
+    pub fn feed(&mut self, data: &[u8]) -> Vec<u8> {
+        self.pending.extend_from_slice(data);
+
+        let mut passthrough = Vec::new();
+        let mut pos = 0;
+        while pos < self.pending.len() && !self.done {
+            if self.pending[pos] != ESC {
+                passthrough.push(self.pending[pos]);
+                pos += 1;
+                continue;
+            }
+
+            match self.parse_sequence(pos) {
+                Parsed::Response(len) => pos += len,
+                Parsed::Input(len) => {
+                    passthrough.extend_from_slice(&self.pending[pos..pos + len]);
+                    pos += len;
+                }
+                Parsed::Incomplete => break,
+            }
+        }
+
+        self.pending.drain(..pos);
+        if self.done {
+            passthrough.append(&mut self.pending);
+        }
+
+        passthrough
+    }
+
+    fn parse_sequence(&mut self, start: usize) -> Parsed {
+        let seq = &self.pending[start..];
+        match seq.get(1) {
+            None => Parsed::Incomplete,
+            Some(b'[') => {
+                // CSI: parameter bytes, intermediate bytes, then a single final byte
+                let Some(end) = seq[2..].iter().position(|b| (0x40..=0x7e).contains(b)) else {
+                    return Parsed::Incomplete;
+                };
+
+                let len = end + 3;
+                let (params, terminator) = (&seq[2..len - 1], seq[len - 1]);
+                let params = String::from_utf8_lossy(params).into_owned();
+                if self.handle_csi(&params, terminator) {
+                    Parsed::Response(len)
+                } else {
+                    Parsed::Input(len)
+                }
+            }
+            Some(&kind @ (b'P' | b'_' | b']')) => {
+                // DCS, APC or OSC: a payload terminated by ST (or BEL, for OSC)
+                let payload = &seq[2..];
+                let Some((end, terminator_len)) =
+                    payload.iter().enumerate().find_map(|(i, &b)| match b {
+                        ESC if payload.get(i + 1) == Some(&b'\\') => Some((i, 2)),
+                        BEL if kind == b']' => Some((i, 1)),
+                        _ => None,
+                    })
+                else {
+                    return Parsed::Incomplete;
+                };
+
+                let payload = String::from_utf8_lossy(&payload[..end]).into_owned();
+                match kind {
+                    b'P' => {
+                        if let Some(version) = payload.strip_prefix(">|") {
+                            self.result.version = Some(version.to_string());
+                        }
+                    }
+                    b'_' => {
+                        if let Some(reply) = payload.strip_prefix('G') {
+                            let (keys, message) = reply.split_once(';').unwrap_or((reply, ""));
+                            if keys.split(',').any(|key| key == format!("i={KITTY_QUERY_ID}"))
+                            {
+                                self.result.kitty_graphics = message == "OK";
+                            }
+                        }
+                    }
+                    _ => {}
+                }
+
+                Parsed::Response(end + 2 + terminator_len)
+            }
+            Some(_) => Parsed::Input(1),
+        }
+    }
+
+    /// Records the response to a CSI query, returning whether it was one.
+    fn handle_csi(&mut self, params: &str, terminator: u8) -> bool {
+        let numbers = |params: &str| -> Vec<u16> {
+            params.split(';').filter_map(|param| param.parse().ok()).collect()
+        };
+
+        match (terminator, params.as_bytes().first()) {
+            (b'c', Some(b'?')) => {
+                self.result.device_attributes = numbers(&params[1..]);
+                self.done = true;
+            }
+            (b'c', Some(b'>')) => self.result.secondary_attributes = numbers(&params[1..]),
+            (b't', _) => match numbers(params)[..] {
+                [4, height, width] => self.result.window_pixels = Some((width, height)),
+                [6, height, width] => self.result.cell_pixels = Some((width, height)),
+                _ => return false,
+            },
+            (b'R', _) if self.awaiting_cursor_position => match numbers(params)[..] {
+                // The cursor started in the first column, so it should be in the third one
+                // if the blank was drawn two cells wide
+                [_, col] => {
+                    self.result.text_sizing = col == 3;
+                    self.awaiting_cursor_position = false;
+                }
+                _ => return false,
+            },
+            _ => return false,
+        }
+
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_probe_kitty() {
+        let mut probe = TerminalProbe::new();
+        let passthrough = probe.feed(
+            b"\x1b[5;3R\x1bP>|kitty(0.39.1)\x1b\\\x1b_Gi=31;OK\x1b\\\x1b[4;1000;1600t\x1b[6;20;10t",
+        );
+        assert!(passthrough.is_empty());
+        assert!(!probe.is_done());
+
+        let passthrough = probe.feed(b"\x1b[>1;4000;39c\x1b[?62;c");
+        assert!(passthrough.is_empty());
+        assert!(probe.is_done());
+
+        let (result, pending) = probe.finish();
+        assert!(pending.is_empty());
+        assert_eq!(
+            result,
+            ProbeResult {
+                device_attributes: vec![62],
+                secondary_attributes: vec![1, 4000, 39],
+                version: Some("kitty(0.39.1)".to_string()),
+                kitty_graphics: true,
+                text_sizing: true,
+                window_pixels: Some((1600, 1000)),
+                cell_pixels: Some((10, 20)),
+            }
+        );
+    }
+
+    #[test]
+    fn test_probe_split_responses() {
+        let mut probe = TerminalProbe::new();
+        assert!(probe.feed(b"\x1b[5;1").is_empty());
+        assert!(probe.feed(b"R\x1bP>|XTerm(").is_empty());
+        assert!(probe.feed(b"390)\x1b").is_empty());
+        assert!(probe.feed(b"\\\x1b[?64;1;4;28c").is_empty());
+        assert!(probe.is_done());
+
+        let (result, _) = probe.finish();
+        assert_eq!(result.version.as_deref(), Some("XTerm(390)"));
+        assert!(!result.text_sizing);
+        assert!(!result.kitty_graphics);
+        assert!(result.sixel());
+        assert!(result.rectangular_ops());
+    }
+
+    #[test]
+    fn test_probe_passthrough() {
+        let mut probe = TerminalProbe::new();
+        assert_eq!(probe.feed(b"q"), b"q".to_vec());
+        assert_eq!(probe.feed(b"\x1b[A"), b"\x1b[A".to_vec());
+        assert_eq!(probe.feed(b"\x1b[?1;2cj"), b"j".to_vec());
+        assert!(probe.is_done());
+    }
+
+    #[test]
+    fn test_probe_cursor_position_once() {
+        // Only the one cursor position report asked for is a response, later ones are keys
+        let mut probe = TerminalProbe::new();
+        assert!(probe.feed(b"\x1b[5;3R").is_empty());
+        assert_eq!(probe.feed(b"\x1b[1;2R"), b"\x1b[1;2R".to_vec());
+        assert!(probe.feed(b"\x1b[?62;c").is_empty());
+
+        let (result, _) = probe.finish();
+        assert!(result.text_sizing);
+    }
+
+    #[test]
+    fn test_probe_cell_size() {
+        let result =
+            ProbeResult { window_pixels: Some((1600, 1000)), ..ProbeResult::default() };
+        assert_eq!(result.cell_size((160, 50)), Some((10, 20)));
+        assert_eq!(ProbeResult::default().cell_size((160, 50)), None);
+    }
+}
@@ -0,0 +1,128 @@
+use std::sync::atomic::{AtomicBool, AtomicU8, Ordering};
+
+use russh::Pty;
+
+/// The character the interrupt key (Ctrl-C) sends in raw mode.
+const INTR: u8 = 0x03;
+/// The character the end of file key (Ctrl-D) sends in raw mode.
+const EOF: u8 = 0x04;
+/// The character the suspend key (Ctrl-Z) sends in raw mode.
+const SUSP: u8 = 0x1a;
+
+/// Mode value used by clients to turn off a control character, `_POSIX_VDISABLE`.
+const DISABLED: u32 = 255;
+
+/// Stands in for the line discipline of the pseudo terminal a client asks for.
+///
+/// There is no real PTY on our end, the client puts its own terminal into raw mode and sends
+/// us every keystroke as is. The terminal modes it requests still tell us how it expects its
+/// keys to be interpreted though, which is used to translate input into what the app expects
+/// from a terminal in raw mode, regardless of the client's own settings.
+#[derive(Debug)]
+pub struct LineDiscipline {
+    raw: AtomicBool,
+    intr: AtomicU8,
+    eof: AtomicU8,
+    susp: AtomicU8,
+}
+
+impl Default for LineDiscipline {
+    fn default() -> Self {
+        Self {
+            raw: AtomicBool::new(false),
+            intr: AtomicU8::new(INTR),
+            eof: AtomicU8::new(EOF),
+            susp: AtomicU8::new(SUSP),
+        }
+    }
+}
+
+impl LineDiscipline {
+    /// Applies the terminal modes from a client's PTY request, ignoring any we don't need.
+    pub fn configure(&self, modes: &[(Pty, u32)]) {
+        for &(mode, value) in modes {
+            let (slot, raw) = match mode {
+                Pty::VINTR => (&self.intr, INTR),
+                Pty::VEOF => (&self.eof, EOF),
+                Pty::VSUSP => (&self.susp, SUSP),
+                _ => continue,
+            };
+
+            // Disabled control characters fall back to the raw mode ones
+            let ch = match value {
+                DISABLED => raw,
+                _ => u8::try_from(value).unwrap_or(raw),
+            };
+
+            slot.store(ch, Ordering::Relaxed);
+        }
+    }
+
+    /// Switches between raw mode, where the app sees every keystroke, and cooked mode, used
+    /// while the app isn't displayed, where only job control keys get through.
+    pub fn set_raw(&self, raw: bool) {
+        self.raw.store(raw, Ordering::Relaxed);
+    }
+
+    pub fn is_raw(&self) -> bool {
+        self.raw.load(Ordering::Relaxed)
+    }
+
+    /// Translates input from the client into the bytes a terminal in raw mode would send.
+    pub fn translate(&self, data: &[u8]) -> Vec<u8> {
+        let control = [
+            (self.intr.load(Ordering::Relaxed), INTR),
+            (self.eof.load(Ordering::Relaxed), EOF),
+            (self.susp.load(Ordering::Relaxed), SUSP),
+        ];
+
+        // A line feed typed on its own is Ctrl-J, only ones in pasted text end lines
+        let keystroke = data.len() == 1;
+
+        let mut translated = Vec::with_capacity(data.len());
+        let mut bytes = data.iter().copied().peekable();
+        while let Some(byte) = bytes.next() {
+            let byte = match byte {
+                // Whichever way the client ends lines, the app expects a carriage return
+                b'\r' if bytes.peek() == Some(&b'\n') => {
+                    bytes.next();
+                    b'\r'
+                }
+                b'\n' if !keystroke => b'\r',
+                _ => control.iter().find(|(ch, _)| *ch == byte).map_or(byte, |(_, raw)| *raw),
+            };
+
+            if self.is_raw() || [INTR, EOF, SUSP].contains(&byte) {
+                translated.push(byte);
+            }
+        }
+
+        translated
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_translate_input() {
+        let discipline = LineDiscipline::default();
+        discipline.configure(&[(Pty::VINTR, 0x07), (Pty::VEOF, DISABLED), (Pty::ECHO, 1)]);
+        discipline.set_raw(true);
+
+        assert_eq!(discipline.translate(b"q"), b"q");
+        assert_eq!(discipline.translate(b"\r\n"), b"\r");
+        assert_eq!(discipline.translate(b"\n"), b"\n");
+        assert_eq!(discipline.translate(b"a\nb"), b"a\rb");
+        assert_eq!(discipline.translate(&[0x07]), [INTR]);
+        assert_eq!(discipline.translate(&[EOF]), [EOF]);
+        assert_eq!(discipline.translate(&[0x1b, b'[', b'A']), [0x1b, b'[', b'A']);
+
+        discipline.set_raw(false);
+        assert_eq!(discipline.translate(b"q\r"), b"");
+        assert_eq!(discipline.translate(&[SUSP]), [SUSP]);
+    }
+}
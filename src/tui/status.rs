@@ -1,27 +1,8 @@
-use std::future::{Future, IntoFuture};
-use std::pin::Pin;
-use std::sync::Arc;
-
-use futures::future;
-use tokio::sync::Mutex;
-
-#[derive(Debug, Clone)]
+/// Whether the app is being displayed, or has been paused by the visitor.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
 pub enum TuiStatus {
+    #[default]
     Active,
-    Suspended(Arc<Mutex<()>>),
-}
-
-impl IntoFuture for TuiStatus {
-    type Output = ();
-    type IntoFuture = Pin<Box<dyn Future<Output = Self::Output>>>;
-
-    fn into_future(self) -> Self::IntoFuture {
-        if let Self::Suspended(lock) = self {
-            return Box::pin(async move {
-                let _guard = lock.lock().await;
-            });
-        }
-
-        Box::pin(future::ready(()))
-    }
+    /// Frames are no longer rendered at the frame rate, only ticks keep coming in.
+    Suspended,
 }
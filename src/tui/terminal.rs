@@ -4,6 +4,10 @@ use default_variant::default;
 use serde::{Deserialize, Serialize};
 use strum::Display;
 
+use crate::tui::palette::ColorDepth;
+use crate::tui::probe::ProbeResult;
+use crate::tui::terminfo::{Glyphs, Multiplexer, TermProfile};
+
 #[cfg(feature = "blog")]
 use ratatui_image::{
     picker::{Capability, ProtocolType},
@@ -18,6 +22,14 @@ pub struct TerminalInfo {
     kind: TerminalKind,
     #[cfg(feature = "blog")]
     font_size: Option<FontSize>,
+    probe: Option<ProbeResult>,
+    probed: bool,
+    term: Option<String>,
+    profile: Option<TermProfile>,
+    multiplexer: Option<Multiplexer>,
+    colorterm: Option<String>,
+    no_color: bool,
+    forced_color_depth: Option<ColorDepth>,
 }
 
 impl TerminalInfo {
@@ -26,6 +38,169 @@ impl TerminalInfo {
         &self.kind
     }
 
+    /// Whether probing has finished, either with a response from the terminal or by timing out.
+    pub fn is_probed(&self) -> bool {
+        self.probed
+    }
+
+    /// Records the responses to the capability probe. The terminal kind reported by the probe
+    /// is only used if `$TERM_PROGRAM` did not already identify the terminal.
+    pub fn apply_probe(&mut self, result: ProbeResult, dims: (u16, u16)) {
+        if let Some(kind) = result.version.as_deref().and_then(TerminalKind::from_xtversion) {
+            if matches!(self.kind, TerminalKind::Unsupported(_)) {
+                self.kind = kind;
+            }
+        }
+
+        #[cfg(feature = "blog")]
+        if let Some(font_size) = result.cell_size(dims) {
+            self.font_size = Some(font_size);
+            if matches!(self.kind, TerminalKind::Unsupported(UnsupportedReason::Unsized)) {
+                self.kind = TerminalKind::Unsupported(UnsupportedReason::Unknown);
+            }
+        }
+
+        #[cfg(not(feature = "blog"))]
+        let _ = dims;
+
+        self.probe = Some(result);
+        self.finish_probe();
+    }
+
+    /// Marks probing as finished, falling back to an unknown terminal if nothing was detected.
+    pub fn finish_probe(&mut self) {
+        self.set_kind(TerminalKind::Unsupported(UnsupportedReason::Unknown));
+        self.probed = true;
+    }
+
+    /// Sets the value of `$TERM` reported by the client, returning whether it is one we support.
+    pub fn set_term(&mut self, term: &str) -> bool {
+        self.term = Some(term.to_string());
+        self.profile = TermProfile::from_term(term);
+        self.profile.is_some()
+    }
+
+    /// Records that the client is running within a multiplexer, as reported by its environment
+    /// (`$TMUX`, `$STY` or `$TERM_PROGRAM`).
+    pub fn set_multiplexer(&mut self, multiplexer: Multiplexer) {
+        self.multiplexer = Some(multiplexer);
+    }
+
+    /// Get the multiplexer the client is running within, if any. Since tmux commonly uses a
+    /// `screen` `$TERM`, its environment and its response to `XTVERSION` take precedence over
+    /// what `$TERM` suggests.
+    pub fn multiplexer(&self) -> Option<Multiplexer> {
+        let tmux_version = self
+            .probe
+            .as_ref()
+            .and_then(|probe| probe.version.as_deref())
+            .is_some_and(|version| version.to_lowercase().starts_with("tmux"));
+
+        self.multiplexer
+            .or(tmux_version.then_some(Multiplexer::Tmux))
+            .or(self.profile.and_then(|profile| profile.multiplexer))
+    }
+
+    /// Get the glyphs the terminal is able to display.
+    pub fn glyphs(&self) -> Glyphs {
+        self.profile.map(|profile| profile.glyphs).unwrap_or_default()
+    }
+
+    /// Sets the value of `$COLORTERM` forwarded by the client.
+    pub fn set_colorterm(&mut self, colorterm: &str) {
+        self.colorterm = Some(colorterm.to_string());
+    }
+
+    /// Disables colors, as requested by the client forwarding a non-empty `$NO_COLOR`.
+    pub fn set_no_color(&mut self, no_color: bool) {
+        self.no_color = no_color;
+    }
+
+    /// Forces a color depth regardless of what was detected.
+    pub fn force_color_depth(&mut self, depth: ColorDepth) {
+        self.forced_color_depth = Some(depth);
+    }
+
+    /// Get the color depth to render with. A forced color depth takes precedence, followed by
+    /// `$NO_COLOR`, and then whatever could be detected from the environment and probes.
+    pub fn color_depth(&self) -> ColorDepth {
+        if let Some(depth) = self.forced_color_depth {
+            return depth;
+        }
+
+        if self.no_color {
+            return ColorDepth::Monochrome;
+        }
+
+        let detected = ColorDepth::detect(self.term.as_deref(), self.colorterm.as_deref());
+        if !matches!(self.kind, TerminalKind::Unsupported(_))
+            && self.multiplexer() != Some(Multiplexer::Screen)
+        {
+            // Every terminal we know of supports true color, regardless of `$TERM`, although
+            // screen does not pass it through
+            ColorDepth::TrueColor
+        } else if self.probe.as_ref().is_some_and(|probe| probe.version.is_some()) {
+            // Terminals modern enough to answer `XTVERSION` support at least 256 colors
+            detected.max(ColorDepth::Indexed256)
+        } else {
+            detected
+        }
+    }
+
+    /// Whether the terminal implements kitty's text sizing protocol (`OSC 66`). Multiplexers
+    /// keep track of cell contents themselves, so scaled text never survives going through one.
+    pub fn supports_text_sizing(&self) -> bool {
+        self.multiplexer().is_none()
+            && (self.probe.as_ref().is_some_and(|probe| probe.text_sizing)
+                || self.kind.supports_text_sizing())
+    }
+
+    /// Get the capabilities of the terminal, as probed or otherwise known for its kind.
+    #[cfg(feature = "blog")]
+    pub fn capabilities(&self) -> Vec<Capability> {
+        let mut capabilities = self.kind.capabilities();
+        let Some(probe) = &self.probe else {
+            return capabilities;
+        };
+
+        for (supported, capability) in [
+            (probe.kitty_graphics, Capability::Kitty),
+            (probe.sixel(), Capability::Sixel),
+            (probe.rectangular_ops(), Capability::RectangularOps),
+            (probe.text_sizing, Capability::TextSizingProtocol),
+        ] {
+            if supported && !capabilities.contains(&capability) {
+                capabilities.push(capability);
+            }
+        }
+
+        capabilities
+    }
+
+    /// Get the image protocol to use, preferring the known protocol for the terminal kind over
+    /// probed capabilities, and halfblocks if neither are available. Within screen, which has
+    /// no way to pass image sequences through to the terminal, halfblocks are always used.
+    #[cfg(feature = "blog")]
+    pub fn protocol_type(&self) -> ProtocolType {
+        if self.multiplexer() == Some(Multiplexer::Screen) {
+            return ProtocolType::Halfblocks;
+        }
+
+        match self.kind.as_protocol() {
+            ProtocolType::Halfblocks => {
+                let capabilities = self.capabilities();
+                if capabilities.contains(&Capability::Kitty) {
+                    ProtocolType::Kitty
+                } else if capabilities.contains(&Capability::Sixel) {
+                    ProtocolType::Sixel
+                } else {
+                    ProtocolType::Halfblocks
+                }
+            }
+            protocol => protocol,
+        }
+    }
+
     /// Get the font size.
     #[cfg(feature = "blog")]
     pub fn font_size(&self) -> FontSize {
@@ -75,7 +250,8 @@ pub enum UnsupportedReason {
     /// Terminal emulator is not known.
     Unknown,
 
-    /// Terminal emulator has not been detected yet. This is only set during SSH initialization.
+    /// Terminal emulator has not been detected yet. This is only set during SSH initialization,
+    /// until the capability probe is answered or times out.
     #[default]
     Unprobed,
 }
@@ -115,10 +291,33 @@ impl TerminalKind {
         Self::Unsupported(UnsupportedReason::Unknown)
     }
 
+    /// Identifies the terminal from its response to an `XTVERSION` query, such as
+    /// `kitty(0.39.1)` or `WezTerm 20240203-110809-5046fc22`.
+    pub fn from_xtversion(version: &str) -> Option<Self> {
+        let terminals = [
+            ("kitty", Self::Kitty),
+            ("ghostty", Self::Ghostty),
+            ("iterm2", Self::ITerm2),
+            ("wezterm", Self::Wezterm),
+            ("mintty", Self::MinTty),
+            ("rio", Self::Rio),
+        ];
+
+        let version = version.to_lowercase();
+        terminals.into_iter().find(|(term, _)| version.starts_with(term)).map(|(_, kind)| kind)
+    }
+
     pub fn supported() -> String {
         Self::ALL_SUPPORTED.map(|term| term.to_string()).join(", ")
     }
 
+    /// Whether the terminal implements kitty's text sizing protocol (`OSC 66`). This mirrors
+    /// `Capability::TextSizingProtocol` in [`TerminalKind::capabilities`], but is available
+    /// without the `blog` feature.
+    pub fn supports_text_sizing(&self) -> bool {
+        matches!(self, Self::Kitty)
+    }
+
     #[cfg(feature = "blog")]
     pub fn capabilities(&self) -> Vec<Capability> {
         match *self {
@@ -132,7 +331,7 @@ impl TerminalKind {
             Self::Kitty => vec![
                 Capability::Kitty,
                 Capability::RectangularOps,
-                Capability::TextSizingProtocol, // !! TODO: THIS COULD BE SO FUCKING COOL FOR MARKDOWN HEADINGS !!
+                Capability::TextSizingProtocol, // used for markdown headings, see `ScaledText`
             ],
 
             Self::Unsupported(_) => vec![],
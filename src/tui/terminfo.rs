@@ -0,0 +1,196 @@
+use std::borrow::Cow;
+
+use ratatui::buffer::Cell;
+use ratatui::text::Span;
+use serde::{Deserialize, Serialize};
+use strum::Display;
+
+use crate::tui::palette::ColorDepth;
+
+/// A terminal multiplexer sitting between the client's terminal emulator and us.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Display, Serialize, Deserialize)]
+#[strum(serialize_all = "lowercase")]
+pub enum Multiplexer {
+    Tmux,
+    Screen,
+}
+
+/// The set of glyphs a terminal is able to display.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Display, Serialize, Deserialize)]
+#[strum(serialize_all = "lowercase")]
+pub enum Glyphs {
+    /// Anything unicode, including nerd font icons and emoji.
+    #[default]
+    Full,
+    /// Only what a typical console font covers, such as the Linux console: latin characters and
+    /// the basic box drawing characters.
+    Limited,
+}
+
+impl Glyphs {
+    /// Maps the symbol of a cell to one which can be displayed with this set of glyphs, only
+    /// cloning it if anything changed.
+    pub fn map_cell(self, cell: Cow<'_, Cell>) -> Cow<'_, Cell> {
+        if self == Self::Full {
+            return cell;
+        }
+
+        match self.map_symbol(cell.symbol()) {
+            Some(symbol) => {
+                let mut cell = cell.into_owned();
+                cell.set_symbol(&symbol);
+                Cow::Owned(cell)
+            }
+            None => cell,
+        }
+    }
+
+    /// Maps a symbol to one which can be displayed with this set of glyphs, preserving its
+    /// width. Returns `None` if the symbol can be displayed as is.
+    pub fn map_symbol(self, symbol: &str) -> Option<String> {
+        // Escape sequences smuggled into cells, such as images, are left alone
+        if self == Self::Full || symbol.is_ascii() || symbol.starts_with('\x1b') {
+            return None;
+        }
+
+        // Box drawing characters which console fonts are known to have
+        const SUPPORTED: &str = "─│┌┐└┘├┤┬┴┼█";
+
+        let mut chars = symbol.chars();
+        let replacement = match (chars.next()?, chars.next()) {
+            (ch, None) if SUPPORTED.contains(ch) => return None,
+            (ch, None) if ch.is_alphanumeric() && (ch as u32) < 0x2000 => return None,
+            ('╭', None) => "┌",
+            ('╮', None) => "┐",
+            ('╰', None) => "└",
+            ('╯', None) => "┘",
+            ('━', None) => "─",
+            ('┊' | '┆', None) => "│",
+            ('▶', None) => ">",
+            ('•', None) => "*",
+            ('…', None) => ".",
+            // Nerd font icons, emoji and everything else a console font is unlikely to have
+            _ => return Some(" ".repeat(Span::raw(symbol).width())),
+        };
+
+        Some(replacement.to_string())
+    }
+}
+
+/// What we know about a terminal from its `$TERM`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TermProfile {
+    /// The multiplexer the `$TERM` belongs to, if any.
+    pub multiplexer: Option<Multiplexer>,
+    /// The color depth implied by the `$TERM`.
+    pub color_depth: ColorDepth,
+    /// The glyphs the terminal is able to display.
+    pub glyphs: Glyphs,
+}
+
+impl TermProfile {
+    const fn new(color_depth: ColorDepth) -> Self {
+        Self { multiplexer: None, color_depth, glyphs: Glyphs::Full }
+    }
+
+    const fn multiplexer(mut self, multiplexer: Multiplexer) -> Self {
+        self.multiplexer = Some(multiplexer);
+        self
+    }
+
+    const fn glyphs(mut self, glyphs: Glyphs) -> Self {
+        self.glyphs = glyphs;
+        self
+    }
+
+    /// The `$TERM` values we know how to talk to. Entries are matched against the `$TERM` up to
+    /// its first variant suffix (`xterm-256color` matches `xterm`, `screen.linux` matches
+    /// `screen`), so more specific entries must come first.
+    const ALLOWLIST: [(&str, Self); 22] = [
+        ("xterm-kitty", Self::new(ColorDepth::TrueColor)),
+        ("xterm-ghostty", Self::new(ColorDepth::TrueColor)),
+        ("xterm", Self::new(ColorDepth::Ansi16)),
+        ("alacritty", Self::new(ColorDepth::TrueColor)),
+        ("foot", Self::new(ColorDepth::TrueColor)),
+        ("wezterm", Self::new(ColorDepth::TrueColor)),
+        ("kitty", Self::new(ColorDepth::TrueColor)),
+        ("ghostty", Self::new(ColorDepth::TrueColor)),
+        ("rio", Self::new(ColorDepth::TrueColor)),
+        ("contour", Self::new(ColorDepth::TrueColor)),
+        ("ms-terminal", Self::new(ColorDepth::TrueColor)),
+        ("mintty", Self::new(ColorDepth::TrueColor)),
+        ("konsole", Self::new(ColorDepth::Ansi16)),
+        ("gnome", Self::new(ColorDepth::Ansi16)),
+        ("vte", Self::new(ColorDepth::Ansi16)),
+        ("rxvt", Self::new(ColorDepth::Ansi16)),
+        ("st", Self::new(ColorDepth::Ansi16)),
+        ("tmux", Self::new(ColorDepth::Ansi16).multiplexer(Multiplexer::Tmux)),
+        ("screen", Self::new(ColorDepth::Ansi16).multiplexer(Multiplexer::Screen)),
+        ("linux", Self::new(ColorDepth::Ansi16).glyphs(Glyphs::Limited)),
+        ("vt220", Self::new(ColorDepth::Monochrome).glyphs(Glyphs::Limited)),
+        ("vt100", Self::new(ColorDepth::Monochrome).glyphs(Glyphs::Limited)),
+    ];
+
+    /// Looks up the profile for a `$TERM`, returning `None` if it is not one we support.
+    pub fn from_term(term: &str) -> Option<Self> {
+        let term = term.to_lowercase();
+        let (_, mut profile) = Self::ALLOWLIST.into_iter().find(|(name, _)| {
+            term.strip_prefix(name)
+                .is_some_and(|rest| rest.is_empty() || rest.starts_with(['-', '.']))
+        })?;
+
+        // Variants of a terminal description may advertise more (or fewer) colors
+        if term.contains("-direct") || term.contains("-truecolor") || term.contains("-24bit") {
+            profile.color_depth = ColorDepth::TrueColor;
+        } else if term.contains("-256color") {
+            profile.color_depth = profile.color_depth.max(ColorDepth::Indexed256);
+        } else if term.ends_with("-mono") || term.ends_with("-m") {
+            profile.color_depth = ColorDepth::Monochrome;
+        }
+
+        Some(profile)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_term_profile() {
+        let profile = TermProfile::from_term("xterm-256color").unwrap();
+        assert_eq!(profile.color_depth, ColorDepth::Indexed256);
+        assert_eq!(profile.multiplexer, None);
+
+        let profile = TermProfile::from_term("tmux-256color").unwrap();
+        assert_eq!(profile.multiplexer, Some(Multiplexer::Tmux));
+
+        let profile = TermProfile::from_term("screen").unwrap();
+        assert_eq!(profile.multiplexer, Some(Multiplexer::Screen));
+        assert_eq!(profile.color_depth, ColorDepth::Ansi16);
+
+        let profile = TermProfile::from_term("linux").unwrap();
+        assert_eq!(profile.glyphs, Glyphs::Limited);
+
+        assert_eq!(
+            TermProfile::from_term("xterm-kitty").unwrap().color_depth,
+            ColorDepth::TrueColor
+        );
+        assert_eq!(TermProfile::from_term("foot").unwrap().color_depth, ColorDepth::TrueColor);
+        assert_eq!(TermProfile::from_term("stterm"), None);
+        assert_eq!(TermProfile::from_term("dumb"), None);
+    }
+
+    #[test]
+    fn test_limited_glyphs() {
+        assert_eq!(Glyphs::Limited.map_symbol("a"), None);
+        assert_eq!(Glyphs::Limited.map_symbol("─"), None);
+        assert_eq!(Glyphs::Limited.map_symbol("é"), None);
+        assert_eq!(Glyphs::Limited.map_symbol("╭").as_deref(), Some("┌"));
+        assert_eq!(Glyphs::Limited.map_symbol("\u{f0041}").as_deref(), Some(" "));
+        assert_eq!(Glyphs::Limited.map_symbol("🦀").as_deref(), Some("  "));
+        assert_eq!(Glyphs::Full.map_symbol("🦀"), None);
+    }
+}
@@ -0,0 +1,92 @@
+use std::fmt::Write as _;
+
+use ratatui::buffer::Buffer;
+use ratatui::layout::Rect;
+use ratatui::style::Style;
+use ratatui::text::Span;
+use ratatui::widgets::Widget;
+
+/// The largest scale factor permitted by the text sizing protocol.
+pub const MAX_SCALE: u8 = 7;
+
+/// Text rendered at an integer multiple of the cell size using kitty's text sizing protocol
+/// (`OSC 66`). The text occupies `scale` rows and `width * scale` columns.
+///
+/// The escape sequence is written into the first cell of the area and every other cell is
+/// marked as skipped, the same way `ratatui_image` emits its image protocols. Since skipped
+/// cells are never diffed, the sequence is prefixed with erase sequences to clear whatever was
+/// previously drawn in the reserved area.
+#[derive(Debug, Clone)]
+pub struct ScaledText<'a> {
+    text: &'a str,
+    scale: u8,
+    style: Style,
+}
+
+impl<'a> ScaledText<'a> {
+    pub fn new(text: &'a str, scale: u8) -> Self {
+        Self { text, scale: scale.clamp(1, MAX_SCALE), style: Style::default() }
+    }
+
+    pub fn style(mut self, style: Style) -> Self {
+        self.style = style;
+        self
+    }
+
+    /// The number of columns the text takes up once scaled.
+    pub fn width(&self) -> u16 {
+        (Span::raw(self.text).width() as u16).saturating_mul(self.scale.into())
+    }
+
+    /// The number of rows the text takes up once scaled.
+    pub fn height(&self) -> u16 {
+        self.scale.into()
+    }
+}
+
+impl Widget for ScaledText<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let area = area.intersection(buf.area);
+        if area.is_empty() {
+            return;
+        }
+
+        // Shrink the scale if we do not have enough rows, the terminal would otherwise draw
+        // over whatever comes after us
+        let scale = self.scale.min(area.height.min(MAX_SCALE.into()) as u8);
+        let max_chars = (area.width / u16::from(scale)) as usize;
+        let text: String = self
+            .text
+            .chars()
+            .filter(|ch| !ch.is_control())
+            .scan(0, |width, ch| {
+                *width += Span::raw(ch.to_string()).width();
+                (*width <= max_chars).then_some(ch)
+            })
+            .collect();
+
+        let mut seq = String::new();
+        for row in 0..scale {
+            // Erase the reserved columns without moving the cursor, then step down a row
+            let _ = write!(seq, "\x1b[{}X", area.width);
+            if row + 1 < scale {
+                seq.push_str("\x1b[1B");
+            }
+        }
+
+        if scale > 1 {
+            let _ = write!(seq, "\x1b[{}A", scale - 1);
+        }
+
+        let _ = write!(seq, "\x1b]66;s={scale};{text}\x07");
+
+        for y in area.top()..area.top() + u16::from(scale) {
+            for x in area.left()..area.right() {
+                buf[(x, y)].reset();
+                buf[(x, y)].set_skip(true);
+            }
+        }
+
+        buf[(area.x, area.y)].set_symbol(&seq).set_style(self.style).set_skip(false);
+    }
+}